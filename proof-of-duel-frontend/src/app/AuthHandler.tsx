@@ -1,75 +1,31 @@
 "use client";
 
-import { useConnection, useWallet } from "@solana/wallet-adapter-react";
+import { useWallet } from "@solana/wallet-adapter-react";
 import { useUser } from "@civic/auth/react";
 import { useEffect } from "react";
-import { program } from "./anchor/anchor";
-import * as anchor from "@coral-xyz/anchor";
+import { GAME_CALLBACK_URL } from "./gameCallback";
 
 const AuthHandler = () => {
-  const { publicKey, sendTransaction } = useWallet();
-  const { user } = useUser();
-  const { connection } = useConnection();
+  const { publicKey } = useWallet();
+  const { user, idToken } = useUser();
 
   useEffect(() => {
-    if (publicKey && user?.name) {
+    if (publicKey && user?.name && idToken) {
       console.log("Public Key:", publicKey.toString());
       console.log("User Info:", user.given_name);
 
-      fetch("http://localhost:8080/login", {
+      fetch(`${GAME_CALLBACK_URL}/login`, {
         method: "POST",
         headers: {
           "Content-Type": "application/json",
         },
         body: JSON.stringify({
-          public_key: publicKey?.toString(),
-          username: user?.name || "",
+          id_token: idToken,
         }),
       })
         .then(async (response) => {
           if (response.ok) {
             console.log("Login successful");
-
-            if (!publicKey) {
-              console.error("Wallet not connected");
-              return;
-            }
-
-            const [playerPda] = anchor.web3.PublicKey.findProgramAddressSync(
-              [Buffer.from("player"), publicKey.toBuffer()],
-              program.programId
-            );
-
-            // Check if player data already exists
-            let playerData = await program.account.player.fetch(playerPda);
-            if (playerData) {
-              console.log("Player data already exists:", playerData);
-              return;
-            }
-
-            console.log("Player PDA:", playerPda.toString());
-
-            try {
-              const transaction = await program.methods
-                .initializePlayer()
-                .accountsPartial({
-                  player: playerPda,
-                  signer: publicKey,
-                  systemProgram: anchor.web3.SystemProgram.programId,
-                })
-                .transaction();
-
-              const transactionSignature = await sendTransaction(
-                transaction,
-                connection
-              );
-
-              console.log(
-                `View on explorer: https://solana.fm/tx/${transactionSignature}?cluster=devnet`
-              );
-            } catch (error) {
-              console.error("Error:", error);
-            }
           } else {
             console.error("Request failed", response);
           }
@@ -78,7 +34,7 @@ const AuthHandler = () => {
           console.error("Error making request:", error);
         });
     }
-  }, [publicKey, user?.name]);
+  }, [publicKey, user?.name, idToken]);
 
   return null;
 };
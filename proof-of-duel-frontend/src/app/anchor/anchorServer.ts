@@ -1,4 +1,5 @@
 import 'server-only';
+import { timingSafeEqual } from 'crypto';
 import * as anchor from '@coral-xyz/anchor';
 import { Keypair, Transaction, VersionedTransaction } from '@solana/web3.js';
 import bs58 from 'bs58';
@@ -50,4 +51,14 @@ export function makeServerProgram(): anchor.Program<ProofOfDuelProgram> {
     anchor.setProvider(provider);
 
     return new anchor.Program(idl as ProofOfDuelProgram, provider) as anchor.Program<ProofOfDuelProgram>;
-}
\ No newline at end of file
+}
+
+// Only the game server may report match results, it proves itself with a shared secret.
+export function isGameServer(req: Request): boolean {
+    const secret = process.env.MATCH_API_SECRET;
+    if (!secret) throw new Error('MATCH_API_SECRET is missing');
+
+    const given = Buffer.from(req.headers.get('authorization') ?? '');
+    const expected = Buffer.from(`Bearer ${secret}`);
+    return given.length === expected.length && timingSafeEqual(given, expected);
+}
@@ -13,6 +13,295 @@ export type ProofOfDuelProgram = {
     "description": "Created with Anchor"
   },
   "instructions": [
+    {
+      "name": "commitMatch",
+      "discriminator": [
+        175,
+        146,
+        74,
+        234,
+        39,
+        25,
+        248,
+        114
+      ],
+      "accounts": [
+        {
+          "name": "authority",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "config",
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  99,
+                  111,
+                  110,
+                  102,
+                  105,
+                  103
+                ]
+              }
+            ]
+          }
+        },
+        {
+          "name": "matchResult",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  109,
+                  97,
+                  116,
+                  99,
+                  104
+                ]
+              },
+              {
+                "kind": "arg",
+                "path": "matchId"
+              }
+            ]
+          }
+        },
+        {
+          "name": "systemProgram",
+          "address": "11111111111111111111111111111111"
+        }
+      ],
+      "args": [
+        {
+          "name": "matchId",
+          "type": {
+            "array": [
+              "u8",
+              16
+            ]
+          }
+        },
+        {
+          "name": "transcriptHash",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          }
+        },
+        {
+          "name": "winner",
+          "type": "pubkey"
+        },
+        {
+          "name": "loser",
+          "type": "pubkey"
+        },
+        {
+          "name": "disputeWindow",
+          "type": "i64"
+        }
+      ]
+    },
+    {
+      "name": "disputeMatch",
+      "discriminator": [
+        121,
+        36,
+        129,
+        243,
+        36,
+        44,
+        145,
+        165
+      ],
+      "accounts": [
+        {
+          "name": "disputer",
+          "signer": true
+        },
+        {
+          "name": "matchResult",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  109,
+                  97,
+                  116,
+                  99,
+                  104
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "match_result.match_id",
+                "account": "matchResult"
+              }
+            ]
+          }
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "finalizeMatch",
+      "discriminator": [
+        6,
+        103,
+        47,
+        7,
+        66,
+        1,
+        85,
+        207
+      ],
+      "accounts": [
+        {
+          "name": "authority",
+          "signer": true
+        },
+        {
+          "name": "matchResult",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  109,
+                  97,
+                  116,
+                  99,
+                  104
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "match_result.match_id",
+                "account": "matchResult"
+              }
+            ]
+          },
+          "relations": [
+            "authority"
+          ]
+        },
+        {
+          "name": "winnerPlayer",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  112,
+                  108,
+                  97,
+                  121,
+                  101,
+                  114
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "match_result.winner",
+                "account": "matchResult"
+              }
+            ]
+          }
+        },
+        {
+          "name": "loserPlayer",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  112,
+                  108,
+                  97,
+                  121,
+                  101,
+                  114
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "match_result.loser",
+                "account": "matchResult"
+              }
+            ]
+          }
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "initializeConfig",
+      "discriminator": [
+        208,
+        127,
+        21,
+        1,
+        194,
+        190,
+        196,
+        70
+      ],
+      "accounts": [
+        {
+          "name": "upgradeAuthority",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "config",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  99,
+                  111,
+                  110,
+                  102,
+                  105,
+                  103
+                ]
+              }
+            ]
+          }
+        },
+        {
+          "name": "program",
+          "address": "GsetEEa4YtiaFcQP4NnqM2vBtJrtbFjKBgfdszMK8ePC"
+        },
+        {
+          "name": "programData"
+        },
+        {
+          "name": "systemProgram",
+          "address": "11111111111111111111111111111111"
+        }
+      ],
+      "args": [
+        {
+          "name": "authority",
+          "type": "pubkey"
+        }
+      ]
+    },
     {
       "name": "initializePlayer",
       "discriminator": [
@@ -62,20 +351,50 @@ export type ProofOfDuelProgram = {
       "args": []
     },
     {
-      "name": "lossIncrement",
+      "name": "resolveMatch",
       "discriminator": [
-        121,
-        84,
-        103,
-        70,
-        81,
-        36,
-        158,
-        189
+        73,
+        0,
+        15,
+        197,
+        178,
+        47,
+        21,
+        193
       ],
       "accounts": [
         {
-          "name": "player",
+          "name": "authority",
+          "signer": true
+        },
+        {
+          "name": "matchResult",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  109,
+                  97,
+                  116,
+                  99,
+                  104
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "match_result.match_id",
+                "account": "matchResult"
+              }
+            ]
+          },
+          "relations": [
+            "authority"
+          ]
+        },
+        {
+          "name": "winnerPlayer",
           "writable": true,
           "pda": {
             "seeds": [
@@ -92,32 +411,14 @@ export type ProofOfDuelProgram = {
               },
               {
                 "kind": "account",
-                "path": "wallet"
+                "path": "match_result.winner",
+                "account": "matchResult"
               }
             ]
           }
         },
         {
-          "name": "wallet"
-        }
-      ],
-      "args": []
-    },
-    {
-      "name": "winIncrement",
-      "discriminator": [
-        208,
-        31,
-        40,
-        121,
-        88,
-        26,
-        136,
-        248
-      ],
-      "accounts": [
-        {
-          "name": "player",
+          "name": "loserPlayer",
           "writable": true,
           "pda": {
             "seeds": [
@@ -134,19 +435,48 @@ export type ProofOfDuelProgram = {
               },
               {
                 "kind": "account",
-                "path": "wallet"
+                "path": "match_result.loser",
+                "account": "matchResult"
               }
             ]
           }
-        },
-        {
-          "name": "wallet"
         }
       ],
-      "args": []
+      "args": [
+        {
+          "name": "resultStands",
+          "type": "bool"
+        }
+      ]
     }
   ],
   "accounts": [
+    {
+      "name": "config",
+      "discriminator": [
+        155,
+        12,
+        170,
+        224,
+        30,
+        250,
+        204,
+        130
+      ]
+    },
+    {
+      "name": "matchResult",
+      "discriminator": [
+        234,
+        166,
+        33,
+        250,
+        153,
+        92,
+        223,
+        196
+      ]
+    },
     {
       "name": "player",
       "discriminator": [
@@ -161,7 +491,133 @@ export type ProofOfDuelProgram = {
       ]
     }
   ],
+  "errors": [
+    {
+      "code": 6000,
+      "name": "invalidDisputeWindow",
+      "msg": "Dispute window must be between 1 second and 24 hours"
+    },
+    {
+      "code": 6001,
+      "name": "sameWinnerAndLoser",
+      "msg": "Winner and loser must be different wallets"
+    },
+    {
+      "code": 6002,
+      "name": "notAParticipant",
+      "msg": "Only a participant of the match can dispute its result"
+    },
+    {
+      "code": 6003,
+      "name": "disputeWindowClosed",
+      "msg": "The dispute window for this match has closed"
+    },
+    {
+      "code": 6004,
+      "name": "disputeWindowOpen",
+      "msg": "The dispute window for this match is still open"
+    },
+    {
+      "code": 6005,
+      "name": "matchDisputed",
+      "msg": "This match result has been disputed"
+    },
+    {
+      "code": 6006,
+      "name": "alreadyFinalized",
+      "msg": "This match result has already been finalized"
+    },
+    {
+      "code": 6007,
+      "name": "unauthorized",
+      "msg": "Only the server authority can do this"
+    },
+    {
+      "code": 6008,
+      "name": "notDisputed",
+      "msg": "This match result has not been disputed"
+    }
+  ],
   "types": [
+    {
+      "name": "config",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "authority",
+            "docs": [
+              "The game server key, the only one allowed to commit, finalize and",
+              "resolve match results."
+            ],
+            "type": "pubkey"
+          }
+        ]
+      }
+    },
+    {
+      "name": "matchResult",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "matchId",
+            "type": {
+              "array": [
+                "u8",
+                16
+              ]
+            }
+          },
+          {
+            "name": "authority",
+            "type": "pubkey"
+          },
+          {
+            "name": "winner",
+            "type": "pubkey"
+          },
+          {
+            "name": "loser",
+            "type": "pubkey"
+          },
+          {
+            "name": "transcriptHash",
+            "type": {
+              "array": [
+                "u8",
+                32
+              ]
+            }
+          },
+          {
+            "name": "committedAt",
+            "type": "i64"
+          },
+          {
+            "name": "disputeWindow",
+            "type": "i64"
+          },
+          {
+            "name": "disputedBy",
+            "type": {
+              "option": "pubkey"
+            }
+          },
+          {
+            "name": "finalized",
+            "type": "bool"
+          },
+          {
+            "name": "voided",
+            "docs": [
+              "Set when a dispute was resolved against the result, it never counts."
+            ],
+            "type": "bool"
+          }
+        ]
+      }
+    },
     {
       "name": "player",
       "type": {
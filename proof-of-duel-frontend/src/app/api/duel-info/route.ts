@@ -1,5 +1,6 @@
 import { makeServerProgram } from "@/app/anchor/anchorServer";
 import * as anchor from "@coral-xyz/anchor";
+import { GAME_CALLBACK_URL } from "@/app/gameCallback";
 
 export async function POST(req: Request) {
     const program = makeServerProgram();
@@ -14,29 +15,43 @@ export async function POST(req: Request) {
         program.programId,
     );
 
-    const playerAccount = await program.account.player.fetch(playerPda);
+    const playerAccount = await program.account.player.fetchNullable(playerPda);
+
+    // The game offers to create the account when it doesn't exist yet.
+    if (!playerAccount) {
+        return new Response(JSON.stringify({ error: "Player account not found" }), {
+            status: 404,
+            headers: { 'Content-Type': 'application/json' }
+        })
+    }
+
     const payload: { win: number, loss: number } = {
         win: playerAccount.win.toNumber(),
         loss: playerAccount.loss.toNumber(),
     };
 
-    await fetch("http://localhost:8080/update-stats", {
-        method: "POST",
-        headers: {
-            "Content-Type": "application/json",
-        },
-        body: JSON.stringify(payload),
-    })
-        .then(async (response) => {
-            if (response.ok) {
-                console.log("Stats updated successfully");
-            } else {
-                console.error("Failed to update stats");
-            }
+    // Lookups of other players (e.g. the opponent's record) must not overwrite the game's own stats.
+    const notify: boolean = body.notify ?? true;
+
+    if (notify) {
+        await fetch(`${GAME_CALLBACK_URL}/update-stats`, {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify(payload),
         })
-        .catch((error) => {
-            console.error("Error making request:", error);
-        });
+            .then(async (response) => {
+                if (response.ok) {
+                    console.log("Stats updated successfully");
+                } else {
+                    console.error("Failed to update stats");
+                }
+            })
+            .catch((error) => {
+                console.error("Error making request:", error);
+            });
+    }
 
     return new Response(JSON.stringify(payload), {
         status: 200,
@@ -0,0 +1,60 @@
+import { isGameServer, makeServerProgram } from '@/app/anchor/anchorServer';
+import * as anchor from '@coral-xyz/anchor';
+
+const uuidToBytes = (uuid: string): number[] =>
+    Array.from(Buffer.from(uuid.replace(/-/g, ''), 'hex'));
+
+export async function POST(req: Request) {
+    if (!isGameServer(req)) {
+        return new Response(null, {
+            status: 401,
+        });
+    }
+
+    const program = makeServerProgram();
+
+    const body = await req.json();
+    const matchId = uuidToBytes(body.match_id);
+    const transcriptHash = Array.from(Buffer.from(body.transcript_hash, 'hex'));
+    const winner = new anchor.web3.PublicKey(body.winner);
+    const loser = new anchor.web3.PublicKey(body.loser);
+    const disputeWindow = new anchor.BN(body.dispute_window);
+
+    const [matchResultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("match"), Buffer.from(matchId)],
+        program.programId,
+    );
+
+    // A retried report for a match that is already on-chain is a success, not a second commit.
+    const existing = await program.account.matchResult.fetchNullable(matchResultPda);
+
+    if (existing) {
+        const sameHash = Buffer.from(existing.transcriptHash).equals(Buffer.from(transcriptHash));
+        console.log(`Match ${body.match_id} is already committed`);
+        return new Response(null, {
+            status: sameHash ? 200 : 409,
+        });
+    }
+
+    try {
+        const tx = await program.methods
+            .commitMatch(matchId, transcriptHash, winner, loser, disputeWindow)
+            .accountsPartial({
+                matchResult: matchResultPda,
+            })
+            .rpc();
+
+        console.log(`Transaction successful: https://solana.fm/tx/${tx}?cluster=devnet`);
+    } catch (error) {
+        console.error("Error committing match:", error);
+        // The program refused it, the game server must not send it again.
+        return new Response(null, {
+            status: error instanceof anchor.AnchorError ? 422 : 500,
+        });
+    }
+
+    return new Response(null, {
+        status: 200,
+        headers: { 'Content-Type': 'application/json' }
+    })
+}
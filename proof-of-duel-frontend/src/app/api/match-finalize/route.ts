@@ -0,0 +1,84 @@
+import { isGameServer, makeServerProgram } from '@/app/anchor/anchorServer';
+import * as anchor from '@coral-xyz/anchor';
+
+const uuidToBytes = (uuid: string): number[] =>
+    Array.from(Buffer.from(uuid.replace(/-/g, ''), 'hex'));
+
+export async function POST(req: Request) {
+    if (!isGameServer(req)) {
+        return new Response(null, {
+            status: 401,
+        });
+    }
+
+    const program = makeServerProgram();
+
+    const body = await req.json();
+    const matchId = uuidToBytes(body.match_id);
+
+    const [matchResultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("match"), Buffer.from(matchId)],
+        program.programId,
+    );
+
+    const matchResult = await program.account.matchResult.fetchNullable(matchResultPda);
+
+    if (!matchResult) {
+        return new Response(null, {
+            status: 404,
+        });
+    }
+
+    // A retried report must not count the result twice.
+    if (matchResult.finalized) {
+        console.log(`Match ${body.match_id} is already finalized`);
+        return new Response(null, {
+            status: 200,
+        });
+    }
+
+    if (matchResult.disputedBy) {
+        console.log(`Match ${body.match_id} was disputed by ${matchResult.disputedBy.toString()}`);
+        return new Response(null, {
+            status: 409,
+        });
+    }
+
+    const [winnerPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("player"), matchResult.winner.toBuffer()],
+        program.programId,
+    );
+
+    const [loserPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("player"), matchResult.loser.toBuffer()],
+        program.programId,
+    );
+
+    try {
+        const tx = await program.methods
+            .finalizeMatch()
+            .accountsPartial({
+                matchResult: matchResultPda,
+                winnerPlayer: winnerPda,
+                loserPlayer: loserPda,
+            })
+            .rpc();
+
+        console.log(`Transaction successful: https://solana.fm/tx/${tx}?cluster=devnet`);
+    } catch (error) {
+        console.error("Error finalizing match:", error);
+        // The program refused it. An early finalize, or one whose players
+        // haven't created their `Player` account yet, is worth retrying.
+        const retryable = ["DisputeWindowOpen", "AccountNotInitialized"];
+        const refused = error instanceof anchor.AnchorError
+            && !retryable.includes(error.error.errorCode.code);
+        return new Response(null, {
+            status: refused ? 422 : 500,
+        });
+    }
+
+    return new Response(null, {
+        status: 200,
+        headers: { 'Content-Type': 'application/json' }
+    })
+}
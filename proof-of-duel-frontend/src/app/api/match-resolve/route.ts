@@ -0,0 +1,79 @@
+import { isGameServer, makeServerProgram } from '@/app/anchor/anchorServer';
+import * as anchor from '@coral-xyz/anchor';
+
+const uuidToBytes = (uuid: string): number[] =>
+    Array.from(Buffer.from(uuid.replace(/-/g, ''), 'hex'));
+
+export async function POST(req: Request) {
+    if (!isGameServer(req)) {
+        return new Response(null, {
+            status: 401,
+        });
+    }
+
+    const program = makeServerProgram();
+
+    const body = await req.json();
+    const matchId = uuidToBytes(body.match_id);
+    const resultStands = Boolean(body.result_stands);
+
+    const [matchResultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("match"), Buffer.from(matchId)],
+        program.programId,
+    );
+
+    const matchResult = await program.account.matchResult.fetchNullable(matchResultPda);
+
+    if (!matchResult) {
+        return new Response(null, {
+            status: 404,
+        });
+    }
+
+    if (matchResult.finalized) {
+        console.log(`Match ${body.match_id} is already settled`);
+        return new Response(null, {
+            status: 200,
+        });
+    }
+
+    // Undisputed matches are finalized once their window closes, not resolved.
+    if (!matchResult.disputedBy) {
+        return new Response(null, {
+            status: 409,
+        });
+    }
+
+    const [winnerPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("player"), matchResult.winner.toBuffer()],
+        program.programId,
+    );
+
+    const [loserPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("player"), matchResult.loser.toBuffer()],
+        program.programId,
+    );
+
+    try {
+        const tx = await program.methods
+            .resolveMatch(resultStands)
+            .accountsPartial({
+                matchResult: matchResultPda,
+                winnerPlayer: winnerPda,
+                loserPlayer: loserPda,
+            })
+            .rpc();
+
+        console.log(`Transaction successful: https://solana.fm/tx/${tx}?cluster=devnet`);
+    } catch (error) {
+        console.error("Error resolving match:", error);
+        return new Response(null, {
+            status: 500,
+        });
+    }
+
+    return new Response(null, {
+        status: 200,
+        headers: { 'Content-Type': 'application/json' }
+    })
+}
@@ -0,0 +1,90 @@
+"use client";
+
+import { useConnection, useWallet } from "@solana/wallet-adapter-react";
+import { useState } from "react";
+import * as anchor from "@coral-xyz/anchor";
+import { program } from "../anchor/anchor";
+import { GAME_CALLBACK_URL } from "../gameCallback";
+
+// Creates the on-chain Player account for the connected wallet and tells the
+// game how it went.
+export default function CreateProfilePage() {
+  const { publicKey, sendTransaction } = useWallet();
+  const { connection } = useConnection();
+  const [status, setStatus] = useState<string>();
+
+  const reportToGame = async (body: object) => {
+    const response = await fetch(`${GAME_CALLBACK_URL}/profile-created`, {
+      method: "POST",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify(body),
+    });
+
+    if (!response.ok) {
+      console.error("Request failed", response);
+    }
+  };
+
+  const create = async () => {
+    if (!publicKey) {
+      setStatus("Connect your wallet first");
+      return;
+    }
+
+    const [playerPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("player"), publicKey.toBuffer()],
+      program.programId
+    );
+
+    try {
+      const existing = await program.account.player.fetchNullable(playerPda);
+      if (existing) {
+        setStatus("Your duel profile already exists, you can return to the game");
+        await reportToGame({ public_key: publicKey.toString(), signature: "" });
+        return;
+      }
+
+      const transaction = await program.methods
+        .initializePlayer()
+        .accountsPartial({
+          player: playerPda,
+          signer: publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .transaction();
+
+      setStatus("Creating your duel profile...");
+
+      const signature = await sendTransaction(transaction, connection);
+      await connection.confirmTransaction(signature, "confirmed");
+
+      console.log(
+        `View on explorer: https://solana.fm/tx/${signature}?cluster=devnet`
+      );
+
+      setStatus("Duel profile created, you can return to the game");
+      await reportToGame({ public_key: publicKey.toString(), signature });
+    } catch (error) {
+      console.error("Error:", error);
+      setStatus("Could not create your duel profile, try again");
+      await reportToGame({
+        public_key: publicKey.toString(),
+        error: error instanceof Error ? error.message : String(error),
+      }).catch((error) => {
+        console.error("Error making request:", error);
+      });
+    }
+  };
+
+  return (
+    <div className="flex flex-col items-center gap-4">
+      <span className="font-bold">Create your duel profile on Solana</span>
+      <button className="border border-white px-4 py-2" onClick={create}>
+        Create Duel Profile
+      </button>
+      {status && <span>{status}</span>}
+    </div>
+  );
+}
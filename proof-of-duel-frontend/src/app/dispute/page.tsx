@@ -0,0 +1,70 @@
+"use client";
+
+import { useConnection, useWallet } from "@solana/wallet-adapter-react";
+import { useSearchParams } from "next/navigation";
+import { Suspense, useState } from "react";
+import { program } from "../anchor/anchor";
+import * as anchor from "@coral-xyz/anchor";
+
+const uuidToBytes = (uuid: string): number[] =>
+  Array.from(Buffer.from(uuid.replace(/-/g, ""), "hex"));
+
+const DisputeForm = () => {
+  const matchId = useSearchParams().get("match_id") ?? "";
+  const { publicKey, sendTransaction } = useWallet();
+  const { connection } = useConnection();
+  const [status, setStatus] = useState<string>();
+
+  const dispute = async () => {
+    if (!publicKey) {
+      setStatus("Connect your wallet first");
+      return;
+    }
+
+    const [matchResultPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("match"), Buffer.from(uuidToBytes(matchId))],
+      program.programId
+    );
+
+    try {
+      const transaction = await program.methods
+        .disputeMatch()
+        .accountsPartial({
+          disputer: publicKey,
+          matchResult: matchResultPda,
+        })
+        .transaction();
+
+      const transactionSignature = await sendTransaction(
+        transaction,
+        connection
+      );
+
+      setStatus("Dispute submitted, the result is on hold until the server reviews the transcript");
+      console.log(
+        `View on explorer: https://solana.fm/tx/${transactionSignature}?cluster=devnet`
+      );
+    } catch (error) {
+      console.error("Error:", error);
+      setStatus("Dispute failed, the window may have closed");
+    }
+  };
+
+  return (
+    <div className="flex flex-col items-center gap-4">
+      <span className="font-bold">Dispute match {matchId}</span>
+      <button className="border border-white px-4 py-2" onClick={dispute}>
+        Dispute Result
+      </button>
+      {status && <span>{status}</span>}
+    </div>
+  );
+};
+
+export default function DisputePage() {
+  return (
+    <Suspense>
+      <DisputeForm />
+    </Suspense>
+  );
+}
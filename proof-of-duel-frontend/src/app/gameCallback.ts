@@ -0,0 +1,3 @@
+// Where the game's local web server listens, must match the game's `callback_port`.
+export const GAME_CALLBACK_URL =
+  process.env.NEXT_PUBLIC_GAME_CALLBACK_URL ?? "http://localhost:8080";
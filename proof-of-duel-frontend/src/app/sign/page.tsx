@@ -0,0 +1,67 @@
+"use client";
+
+import { useWallet } from "@solana/wallet-adapter-react";
+import { useSearchParams } from "next/navigation";
+import { Suspense, useState } from "react";
+import * as anchor from "@coral-xyz/anchor";
+import { GAME_CALLBACK_URL } from "../gameCallback";
+
+const SignForm = () => {
+  const nonce = useSearchParams().get("nonce") ?? "";
+  const { publicKey, signMessage } = useWallet();
+  const [status, setStatus] = useState<string>();
+
+  const sign = async () => {
+    if (!publicKey || !signMessage) {
+      setStatus("Connect your wallet first");
+      return;
+    }
+
+    try {
+      const message = new TextEncoder().encode(
+        `Proof of Duel wallet check: ${nonce}`
+      );
+      const signature = await signMessage(message);
+
+      const response = await fetch(`${GAME_CALLBACK_URL}/wallet-signature`, {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+        },
+        body: JSON.stringify({
+          public_key: publicKey.toString(),
+          nonce,
+          signature: anchor.utils.bytes.bs58.encode(signature),
+        }),
+      });
+
+      if (response.ok) {
+        setStatus("Wallet verified, you can return to the game");
+      } else {
+        console.error("Request failed", response);
+        setStatus("Could not reach the game, try again");
+      }
+    } catch (error) {
+      console.error("Error:", error);
+      setStatus("Signing was cancelled");
+    }
+  };
+
+  return (
+    <div className="flex flex-col items-center gap-4">
+      <span className="font-bold">Sign in to the duel with your wallet</span>
+      <button className="border border-white px-4 py-2" onClick={sign}>
+        Sign Message
+      </button>
+      {status && <span>{status}</span>}
+    </div>
+  );
+};
+
+export default function SignPage() {
+  return (
+    <Suspense>
+      <SignForm />
+    </Suspense>
+  );
+}
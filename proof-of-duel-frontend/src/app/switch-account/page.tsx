@@ -0,0 +1,24 @@
+"use client";
+
+import { useUser } from "@civic/auth/react";
+import { useRouter } from "next/navigation";
+import { useEffect } from "react";
+
+// Signs out of Civic and sends the player back to the login page, the game
+// keeps the old account until the new login reaches it.
+export default function SwitchAccountPage() {
+  const { signOut } = useUser();
+  const router = useRouter();
+
+  useEffect(() => {
+    signOut()
+      .catch((error) => {
+        console.error("Error:", error);
+      })
+      .finally(() => {
+        router.push("/");
+      });
+  }, []);
+
+  return <span>Signing out...</span>;
+}
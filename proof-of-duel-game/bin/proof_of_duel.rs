@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use axum::{
     http::{HeaderValue, Method},
     routing::post,
@@ -6,64 +8,161 @@ use bevy::{
     audio::{AudioPlugin, SpatialScale},
     log::{Level, LogPlugin},
     prelude::*,
+    ui::UiSystem,
+    window::WindowResolution,
 };
 use bevy_aseprite_ultra::prelude::*;
 // use bevy_fps_counter::FpsCounterPlugin;
 use bevy_quinnet::client::QuinnetClientPlugin;
 use bevy_webserver::{BevyWebServerPlugin, RouterAppExt, WebServerConfig};
 use proof_of_duel_game::{
-    AUDIO_SCALE, GameState, LoggedInState, cameras,
-    civic_auth::{self, AuthStateWatcher},
-    connection::{self, ConnectionState, IsConnected},
-    player::{self, PlayerHertsStatus, PlayerHit, PlayerSelection, PlayersCounting, ShootingLock},
+    AUDIO_SCALE, GameState, LoggedInState,
+    bridge::{
+        self, LoggedIn, LoggedOut, OpponentStatsLoaded, ProfileCreated, StatsFetched, StatsUpdated,
+        WalletSigned,
+    },
+    cameras,
+    civic_auth::{self, CivicKeysLoader, SharedCivicVerifier},
+    config::GameConfig,
+    connection::{self, ConnectionState, IsConnected, ServerMessageReceived},
+    duel_profile::{self, DuelProfile},
+    guest,
+    input::{self, InputDevice, MenuFocus},
+    lobby::{self, LobbyBrowser, LobbyRefreshTimer},
+    local_duel::{self, LocalDuelStates},
+    player::{
+        self, MatchPlayers, PlayerHertsStatus, PlayerHit, PlayerSelection, PlayersCounting,
+        ShootingLock,
+    },
+    practice::{self, PracticeMatch, PracticeShot},
+    replay::{self, ReplayPlayback, ReplayRecorder, ReplayedKeyPress},
+    rhythm::{self, BeatClock, CALIBRATION_FILE, ComboMeter, InputCalibration},
     scene,
-    shooting::{self, CheckShootingKeyEvent, ResetKeysEvent, ShootingEvent, ShootingStates},
+    series::{self, RoundIntro, SeriesScore},
+    settings::{self, UserSettings},
+    shooting::{
+        self, CheckShootingKeyEvent, MatchSettings, ResetKeysEvent, ShootingEvent, ShootingStates,
+    },
     sounds,
-    stats::{self, StatsData, StatsStateWatcher},
+    stats::{self, OpponentStats, StatsClient, StatsData, StatsSubscription},
+    tournament::Bracket,
     ui::{
         self,
-        game_over::WhoIsWinner,
-        join_game::{BackspaceTimer, IsHost, MatchIdInput, MatchNotFoundError},
+        calibration::CalibrationTaps,
+        game_over::{MatchCommitment, RematchStatus, WhoIsWinner},
+        join_game::{BackspaceTimer, IsHost, IsSpectator, MatchIdInput, MatchNotFoundError},
         main_menu::MainMenuState,
         play_now_ui::GameStartTimer,
         profile::ProfileData,
+        settings_ui::{Rebinding, SettingsPage},
+        tournament_ui::{TournamentEntry, TournamentStatus},
     },
+    wallet::{self, DevKeypair, PendingMatchRequest, WalletProof},
 };
 use tower_http::cors::{Any, CorsLayer};
 
 fn main() {
+    let (bridge_sender, bridge_receiver) = bridge::event_bridge();
+    let (config, mut config_problems) = GameConfig::load();
+    let civic_verifier = SharedCivicVerifier::new(config.civic());
+    let user_settings = UserSettings::load(Path::new(settings::SETTINGS_FILE));
+
+    let cors_origins: Vec<HeaderValue> = config
+        .cors_origins
+        .iter()
+        .filter_map(|origin| match origin.parse::<HeaderValue>() {
+            Ok(origin) => Some(origin),
+            Err(_) => {
+                config_problems.push(format!("{} is not a valid CORS origin", origin));
+                None
+            }
+        })
+        .collect();
+
+    // Logging only starts with the App, and only at ERROR level, so report
+    // config problems on stderr.
+    for problem in &config_problems {
+        eprintln!("Warning: {}", problem);
+    }
+
+    let dev_keypair = DevKeypair::from_env().unwrap_or_else(|e| {
+        eprintln!("❌ {}", e);
+        DevKeypair::default()
+    });
+
     App::new()
         .insert_resource(ClearColor(Color::BLACK))
         .insert_resource(ShootingStates::default())
+        .insert_resource(InputDevice::default())
+        .insert_resource(MenuFocus::default())
+        .insert_resource(MatchSettings::default())
         .insert_resource(PlayerHertsStatus::default())
         .insert_resource(PlayerSelection::default())
         .insert_resource(GameStartTimer::new(3.0))
         .insert_resource(PlayersCounting::default())
+        .insert_resource(MatchPlayers::default())
+        .insert_resource(OpponentStats::default())
         .insert_resource(WhoIsWinner::default())
+        .insert_resource(MatchCommitment::default())
+        .insert_resource(RematchStatus::default())
+        .insert_resource(SeriesScore::default())
+        .insert_resource(RoundIntro::new(series::ROUND_INTRO_SECS))
+        .insert_resource(Bracket::default())
+        .insert_resource(LobbyBrowser::default())
+        .insert_resource(LobbyRefreshTimer::default())
+        .insert_resource(TournamentEntry::default())
+        .insert_resource(TournamentStatus::default())
         .insert_resource(ShootingLock::default())
         .insert_resource(MatchIdInput::default())
         .insert_resource(BackspaceTimer::default())
         .insert_resource(IsHost::default())
+        .insert_resource(IsSpectator::default())
         .insert_resource(IsConnected::default())
         .insert_resource(ProfileData::default())
-        .insert_resource(AuthStateWatcher::default())
-        .insert_resource(StatsStateWatcher::default())
+        .insert_resource(StatsClient::default())
+        .insert_resource(StatsSubscription::default())
         .insert_resource(StatsData::default())
+        .insert_resource(DuelProfile::default())
+        .insert_resource(PracticeMatch::default())
+        .insert_resource(LocalDuelStates::default())
+        .insert_resource(ReplayRecorder::default())
+        .insert_resource(ReplayPlayback::default())
+        .insert_resource(BeatClock::default())
+        .insert_resource(ComboMeter::default())
+        .insert_resource(InputCalibration::load(Path::new(CALIBRATION_FILE)))
+        .insert_resource(CalibrationTaps::default())
+        .insert_resource(user_settings.clone())
+        .insert_resource(SettingsPage::default())
+        .insert_resource(Rebinding::default())
+        .insert_resource(dev_keypair)
+        .insert_resource(PendingMatchRequest::default())
+        .insert_resource(WalletProof::default())
+        .insert_resource(bridge_sender.clone())
+        .insert_resource(civic_verifier.clone())
+        .insert_resource(CivicKeysLoader::default())
+        .insert_resource(bridge_receiver)
         .insert_resource(WebServerConfig {
-            port: 8080,
+            port: config.callback_port,
             ..Default::default()
         })
+        .insert_resource(config)
         .add_plugins((DefaultPlugins
             .set(WindowPlugin {
                 primary_window: Some(Window {
                     title: "Proof of Duel".into(),
                     resizable: true,
+                    resolution: WindowResolution::new(
+                        user_settings.resolution.0 as f32,
+                        user_settings.resolution.1 as f32,
+                    ),
+                    mode: user_settings.window_mode(),
+                    present_mode: user_settings.present_mode(),
                     position: WindowPosition::Centered(MonitorSelection::Primary),
                     resize_constraints: WindowResizeConstraints {
-                        min_width: 1280.,
-                        min_height: 640.,
-                        max_width: 1920.,
-                        max_height: 1080.,
+                        min_width: settings::MIN_RESOLUTION.0 as f32,
+                        min_height: settings::MIN_RESOLUTION.1 as f32,
+                        max_width: settings::MAX_RESOLUTION.0 as f32,
+                        max_height: settings::MAX_RESOLUTION.1 as f32,
                     },
                     ..Default::default()
                 }),
@@ -83,12 +182,13 @@ fn main() {
         .add_plugins(BevyWebServerPlugin)
         .route("/login", post(civic_auth::login))
         .route("/update-stats", post(stats::update_stats))
+        .route("/wallet-signature", post(wallet::wallet_signature))
+        .route("/profile-created", post(duel_profile::profile_created))
+        .layer(bridge_sender.extension())
+        .layer(civic_verifier.extension())
         .layer(
             CorsLayer::new()
-                .allow_origin([
-                    "http://localhost:3000".parse::<HeaderValue>().unwrap(),
-                    "http://127.0.0.1:3000".parse::<HeaderValue>().unwrap(),
-                ])
+                .allow_origin(cors_origins)
                 .allow_methods([
                     Method::GET,
                     Method::POST,
@@ -109,25 +209,64 @@ fn main() {
         .add_event::<ResetKeysEvent>()
         .add_event::<CheckShootingKeyEvent>()
         .add_event::<PlayerHit>()
+        .add_event::<LoggedIn>()
+        .add_event::<LoggedOut>()
+        .add_event::<StatsUpdated>()
+        .add_event::<StatsFetched>()
+        .add_event::<OpponentStatsLoaded>()
+        .add_event::<WalletSigned>()
+        .add_event::<ProfileCreated>()
+        .add_event::<PracticeShot>()
+        .add_event::<ServerMessageReceived>()
+        .add_event::<ReplayedKeyPress>()
+        .add_systems(
+            PreUpdate,
+            (
+                input::track_input_device,
+                input::gamepad_menu_navigation.run_if(not(in_state(GameState::InGame))),
+            )
+                .chain()
+                .after(UiSystem::Focus),
+        )
         .add_systems(Startup, ui::profile::spawn_profile_ui)
         .add_systems(
             Update,
             (
-                civic_auth::poll_auth_state,
-                stats::get_stats_scheduler,
+                civic_auth::load_civic_jwks,
+                bridge::drain_event_bridge,
+                civic_auth::apply_logged_in,
+                civic_auth::apply_logged_out,
+                stats::apply_stats_updated,
+                stats::apply_stats_fetched,
+                stats::apply_opponent_stats,
+                duel_profile::apply_profile_created,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                stats::fetch_stats,
+                stats::subscribe_stats,
                 ui::profile::update_username,
-                stats::poll_stats_state,
-                stats::get_stats_scheduler,
                 ui::profile::update_win,
                 ui::profile::update_loss,
+                ui::profile::update_stats_freshness,
+                ui::profile::update_duel_profile,
+                stats::fetch_opponent_stats,
+                rhythm::sync_beat_clock,
+                sounds::music::loop_bg_music,
             ),
         )
         .add_systems(
             OnEnter(MainMenuState::MainMenu),
             (
                 ui::join_game::reset_is_host,
+                ui::join_game::reset_is_spectator,
+                series::reset_series_score,
                 cameras::main_menu_camera_setup,
                 connection::disconnect,
+                wallet::reset_wallet_proof,
             ),
         )
         .add_systems(
@@ -140,7 +279,10 @@ fn main() {
         )
         .add_systems(
             OnEnter(LoggedInState::LoggedIn),
-            ui::main_menu::spawn_main_menu_after_logged_in,
+            (
+                ui::main_menu::spawn_main_menu_after_logged_in,
+                stats::refresh_stats,
+            ),
         )
         .add_systems(
             OnExit(LoggedInState::LoggedIn),
@@ -151,6 +293,8 @@ fn main() {
             (
                 ui::main_menu::main_menu_button_pressed_handler,
                 ui::main_menu::main_menu_ui_interaction,
+                ui::profile::duel_profile_button_pressed_handler,
+                civic_auth::expire_session,
             )
                 .run_if(in_state(GameState::MainMenu))
                 .run_if(in_state(MainMenuState::MainMenu)),
@@ -189,7 +333,9 @@ fn main() {
                 ui::play_now_ui::play_now_button_pressed_handler,
                 ui::play_now_ui::play_now_ui_interaction,
                 ui::play_now_ui::update_play_now_text,
+                ui::play_now_ui::update_match_settings_text,
                 ui::play_now_ui::update_game_start_countdown,
+                ui::join_game::match_not_found_error,
             )
                 .run_if(in_state(GameState::MainMenu))
                 .run_if(in_state(MainMenuState::PlayNow)),
@@ -199,6 +345,7 @@ fn main() {
             (
                 cameras::despawn_play_now_ui_camera,
                 ui::play_now_ui::despawn_play_now_ui,
+                ui::join_game::despawn_join_game_ui,
             )
                 .chain(),
         )
@@ -208,6 +355,8 @@ fn main() {
                 cameras::join_game_ui_camera_setup,
                 ui::join_game::spawn_join_game_ui,
                 connection::open_connection,
+                connection::to_connection_state,
+                lobby::open_lobby_browser.run_if(not(ui::join_game::is_spectating)),
             )
                 .chain(),
         )
@@ -215,8 +364,11 @@ fn main() {
             Update,
             (
                 ui::join_game::join_game_button_pressed_handler,
+                ui::join_game::lobby_button_pressed_handler,
                 ui::join_game::join_game_ui_interaction,
                 ui::join_game::update_match_id_input,
+                ui::join_game::spawn_open_matches,
+                lobby::refresh_lobby_browser.run_if(not(ui::join_game::is_spectating)),
                 ui::join_game::match_not_found_error,
             )
                 .run_if(in_state(GameState::MainMenu))
@@ -230,6 +382,154 @@ fn main() {
             )
                 .chain(),
         )
+        .add_systems(
+            OnEnter(MainMenuState::Practice),
+            (
+                cameras::practice_ui_camera_setup,
+                ui::practice_ui::spawn_practice_ui,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                ui::practice_ui::practice_button_pressed_handler,
+                ui::practice_ui::practice_ui_interaction,
+            )
+                .run_if(in_state(GameState::MainMenu))
+                .run_if(in_state(MainMenuState::Practice)),
+        )
+        .add_systems(
+            OnExit(MainMenuState::Practice),
+            (
+                cameras::despawn_practice_ui_camera,
+                ui::practice_ui::despawn_practice_ui,
+            )
+                .chain(),
+        )
+        .add_systems(
+            OnEnter(MainMenuState::Calibration),
+            (
+                cameras::practice_ui_camera_setup,
+                ui::calibration::spawn_calibration_ui,
+                sounds::music::play_bg_music,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                ui::calibration::calibration_input,
+                ui::calibration::update_calibration_text,
+                ui::calibration::calibration_button_pressed_handler,
+                ui::calibration::calibration_ui_interaction,
+            )
+                .run_if(in_state(GameState::MainMenu))
+                .run_if(in_state(MainMenuState::Calibration)),
+        )
+        .add_systems(
+            OnExit(MainMenuState::Calibration),
+            (
+                cameras::despawn_practice_ui_camera,
+                ui::calibration::despawn_calibration_ui,
+                sounds::music::stop_playing_bg_music,
+            )
+                .chain(),
+        )
+        .add_systems(
+            OnEnter(MainMenuState::Settings),
+            (
+                cameras::settings_ui_camera_setup,
+                ui::settings_ui::spawn_settings_ui,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                ui::settings_ui::settings_button_pressed_handler,
+                ui::settings_ui::capture_rebinding,
+                ui::settings_ui::spawn_settings_page,
+                ui::settings_ui::update_settings_text,
+                ui::settings_ui::settings_ui_interaction,
+            )
+                .chain()
+                .run_if(in_state(GameState::MainMenu))
+                .run_if(in_state(MainMenuState::Settings)),
+        )
+        .add_systems(
+            OnExit(MainMenuState::Settings),
+            (
+                cameras::despawn_settings_ui_camera,
+                ui::settings_ui::despawn_settings_ui,
+            )
+                .chain(),
+        )
+        .add_systems(
+            OnEnter(MainMenuState::Replays),
+            (
+                cameras::replays_ui_camera_setup,
+                ui::replays_ui::spawn_replays_ui,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                ui::replays_ui::replays_button_pressed_handler,
+                ui::replays_ui::replays_ui_interaction,
+            )
+                .chain()
+                .run_if(in_state(GameState::MainMenu))
+                .run_if(in_state(MainMenuState::Replays)),
+        )
+        .add_systems(
+            OnExit(MainMenuState::Replays),
+            (
+                cameras::despawn_replays_ui_camera,
+                ui::replays_ui::despawn_replays_ui,
+            )
+                .chain(),
+        )
+        .add_systems(
+            OnEnter(MainMenuState::Tournament),
+            (
+                cameras::tournament_ui_camera_setup,
+                ui::tournament_ui::spawn_tournament_ui,
+                connection::open_connection,
+                connection::to_connection_state,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                ui::tournament_ui::tournament_button_pressed_handler,
+                ui::tournament_ui::tournament_ui_interaction,
+                ui::join_game::update_match_id_input,
+                ui::tournament_ui::spawn_bracket,
+                ui::tournament_ui::update_tournament_status_text,
+                ui::tournament_ui::rejoin_tournament.run_if(in_state(ConnectionState::Connected)),
+                ui::play_now_ui::update_game_start_countdown,
+            )
+                .run_if(in_state(GameState::MainMenu))
+                .run_if(in_state(MainMenuState::Tournament)),
+        )
+        .add_systems(
+            OnExit(MainMenuState::Tournament),
+            (
+                cameras::despawn_tournament_ui_camera,
+                ui::tournament_ui::despawn_tournament_ui,
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                settings::apply_audio_settings,
+                settings::apply_display_settings,
+            ),
+        )
         .add_systems(
             OnEnter(GameState::InGame),
             (
@@ -237,25 +537,75 @@ fn main() {
                 scene::setup_background,
                 player::setup_player_1,
                 player::setup_player_2,
-                shooting::spawn_shooting_keys,
+                shooting::spawn_shooting_keys.run_if(not(ui::join_game::is_spectating)),
+                local_duel::spawn_local_duel_keys.run_if(practice::is_local_duel),
+                rhythm::spawn_combo_meter,
+                series::spawn_series_hud,
                 sounds::music::play_bg_music,
+                replay::spawn_replay_hud.run_if(replay::is_playing_replay),
+                replay::finish_replay_rebuild,
             )
                 .chain(),
         )
         .add_systems(
             Update,
             (
-                shooting::shooting_key_input,
+                shooting::shooting_key_input
+                    .run_if(not(replay::is_playing_replay))
+                    .run_if(not(ui::join_game::is_spectating))
+                    .run_if(not(series::is_round_intro)),
+                shooting::replayed_key_input.run_if(replay::is_playing_replay),
                 shooting::spawn_new_shooting_keys,
+                shooting::update_shooting_key_glyphs,
                 player::player_shooting,
                 player::who_was_hit,
                 player::update_heart_status,
+                player::update_opponent_record_text,
+                rhythm::update_combo_meter_text,
+                series::update_series_hud,
             )
                 .run_if(in_state(GameState::InGame)),
         )
         .add_systems(
             Update,
-            connection::handle_server_messages.run_if(in_state(ConnectionState::Connected)),
+            (practice::ai_gunslinger, practice::resolve_practice_shots)
+                .chain()
+                .run_if(in_state(GameState::InGame))
+                .run_if(practice::is_practicing),
+        )
+        .add_systems(
+            Update,
+            (
+                local_duel::local_duel_key_input,
+                local_duel::spawn_new_local_duel_keys,
+            )
+                .run_if(in_state(GameState::InGame))
+                .run_if(practice::is_local_duel),
+        )
+        .add_systems(
+            Update,
+            (replay::replay_controls, replay::update_replay_hud)
+                .chain()
+                .run_if(in_state(GameState::InGame))
+                .run_if(replay::is_playing_replay),
+        )
+        .add_systems(
+            Update,
+            (
+                connection::receive_server_messages.run_if(in_state(ConnectionState::Connected)),
+                replay::play_replay.run_if(replay::is_playing_replay),
+                ui::game_over::handle_rematch_messages,
+                connection::handle_server_messages,
+                series::handle_series_messages,
+                ui::tournament_ui::handle_tournament_messages,
+                lobby::handle_lobby_messages,
+                (
+                    wallet::sign_wallet_challenge,
+                    wallet::send_pending_match_request,
+                )
+                    .run_if(in_state(ConnectionState::Connected)),
+            )
+                .chain(),
         )
         .add_systems(
             OnExit(GameState::InGame),
@@ -265,6 +615,9 @@ fn main() {
                 scene::despawn_background,
                 player::despawn_player,
                 shooting::despawn_shooting_keys,
+                rhythm::despawn_combo_meter,
+                series::despawn_series_hud,
+                replay::despawn_replay_hud,
                 cameras::despawn_game_camera,
             ),
         )
@@ -274,14 +627,23 @@ fn main() {
                 cameras::game_over_camera_setup,
                 ui::game_over::spawn_game_over_ui,
                 connection::reset_game_started_timer,
+                ui::game_over::reset_rematch_status,
+                stats::refresh_stats,
+                guest::record_guest_result,
             )
                 .chain(),
         )
         .add_systems(
             Update,
             (
+                ui::game_over::rematch_button_pressed_handler
+                    .before(ui::game_over::game_over_button_pressed_handler),
                 ui::game_over::game_over_button_pressed_handler,
                 ui::game_over::game_over_ui_interaction,
+                ui::game_over::update_commitment_text,
+                ui::game_over::update_rematch_text,
+                // A rematch counts down on the game over screen.
+                ui::play_now_ui::update_game_start_countdown,
             )
                 .run_if(in_state(GameState::GameOver)),
         )
@@ -290,6 +652,9 @@ fn main() {
             (
                 ui::game_over::despawn_game_over_ui,
                 cameras::despawn_game_over_camera,
+                practice::end_practice_match,
+                replay::save_recorded_replay,
+                replay::end_replay,
             ),
         )
         .run();
@@ -1,19 +1,44 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet, hash_map::Entry},
+    path::Path,
+    time::{Duration, Instant},
+};
 
-use bevy::{app::ScheduleRunnerPlugin, prelude::*};
+use bevy::{app::ScheduleRunnerPlugin, log::LogPlugin, prelude::*, time::TimePlugin};
 use bevy_quinnet::{
     server::{
-        ConnectionLostEvent, QuinnetServer, QuinnetServerPlugin, ServerEndpointConfiguration,
-        certificate::CertificateRetrievalMode,
+        ConnectionLostEvent, Endpoint, QuinnetServer, QuinnetServerPlugin,
+        ServerEndpointConfiguration, certificate::CertificateRetrievalMode,
     },
     shared::ClientId,
 };
+use crossbeam_channel::{Receiver, Sender};
 use proof_of_duel_game::{
-    ClientMessage, LOCAL_BIND_IP, SERVER_HOST, SERVER_PORT, ServerChannel, ServerMessage,
+    ClientMessage, DISPUTE_WINDOW_SECS, LOCAL_BIND_IP, SERVER_HOST, SERVER_PORT, STARTING_HEARTS,
+    ServerChannel, ServerMessage,
+    config::GameConfig,
+    lobby::{self, OpenMatch},
+    outbox::{DeliveryOutcome, DeliveryResult, OUTBOX_FILE, Outbox, ReportKind},
     player::Player,
+    replay::{MatchReplay, REPLAY_DIR},
+    series::{self, SeriesScore},
+    session::unix_now,
+    shooting::MatchSettings,
+    stats::StatsSource,
+    tournament::{self, Bracket, TOURNAMENT_DIR},
+    transcript::{self, MatchTranscript, TRANSCRIPT_DIR},
+    wallet,
 };
+use serde::Serialize;
 use uuid::Uuid;
 
+/// How long a host's looked up record is shown before it is looked up again.
+const HOST_RECORD_TTL_SECS: i64 = 300;
+
+/// How long a bracket duel waits for a disconnected player before it goes to
+/// their opponent.
+const REJOIN_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[derive(Resource, Default, Debug)]
 pub struct Matches {
     pub sessions: HashMap<Uuid, MatchSession>,
@@ -23,14 +48,419 @@ pub struct Matches {
 pub struct MatchSession {
     pub id: Uuid,
     pub players: HashMap<ClientId, Player>,
+    /// Clients watching the match. They get everything that is broadcast but
+    /// never shoot.
+    pub spectators: HashSet<ClientId>,
+    /// Players who asked for a rematch since the match ended.
+    pub rematch_votes: HashSet<ClientId>,
+    /// Rematches played in this session so far, see `transcript::game_id`.
+    pub rematch: u32,
     pub player_1_hearts: usize,
     pub player_2_hearts: usize,
+    pub is_over: bool,
+    pub winner: Option<usize>,
+    /// The code of the tournament this duel belongs to, if any.
+    pub tournament: Option<String>,
+    pub settings: MatchSettings,
+    /// Rounds won so far, the match is over once the series is decided.
+    pub series: SeriesScore,
+    /// Shots are refused until the round intro the clients show is over.
+    pub next_round_at: Option<Instant>,
+    pub key_seed: u64,
+    pub created_at: i64,
+    pub transcript: MatchTranscript,
+    pub replay: MatchReplay,
 }
 
 impl MatchSession {
+    pub fn new(id: Uuid, settings: MatchSettings) -> Self {
+        let settings = settings.sanitized();
+
+        Self {
+            id,
+            players: HashMap::new(),
+            spectators: HashSet::new(),
+            rematch_votes: HashSet::new(),
+            rematch: 0,
+            player_1_hearts: STARTING_HEARTS,
+            player_2_hearts: STARTING_HEARTS,
+            is_over: false,
+            winner: None,
+            tournament: None,
+            settings,
+            series: SeriesScore::new(settings.best_of),
+            next_round_at: None,
+            key_seed: rand::random(),
+            created_at: unix_now(),
+            transcript: MatchTranscript::new(id),
+            replay: MatchReplay::new(id, 0),
+        }
+    }
+
     pub fn is_full(&self) -> bool {
         self.players.len() == 2
     }
+
+    pub fn is_round_intro(&self, now: Instant) -> bool {
+        self.next_round_at
+            .is_some_and(|next_round_at| now < next_round_at)
+    }
+
+    /// The id the current game is saved and committed on-chain under.
+    pub fn game_id(&self) -> Uuid {
+        transcript::game_id(self.id, self.rematch)
+    }
+
+    pub fn players_by_number(&self) -> Vec<Player> {
+        let mut players: Vec<Player> = self.players.values().cloned().collect();
+        players.sort_by_key(|player| player.player_number);
+        players
+    }
+
+    pub fn is_host(&self, client_id: ClientId) -> bool {
+        self.players
+            .get(&client_id)
+            .is_some_and(|player| player.player_number == 1)
+    }
+
+    /// The lobby browser entry for this match, while it is public and still
+    /// waiting for an opponent.
+    pub fn open_match(&self, now: i64, host_records: &HostRecords) -> Option<OpenMatch> {
+        if !self.settings.public || self.is_full() || self.is_over || self.tournament.is_some() {
+            return None;
+        }
+
+        let host = self
+            .players
+            .values()
+            .find(|player| player.player_number == 1)?;
+
+        Some(OpenMatch {
+            match_id: self.id,
+            host_name: host.display_name(),
+            host_record: host_records.record_of(&host.wallet),
+            settings: self.settings,
+            age_secs: now - self.created_at,
+        })
+    }
+
+    pub fn wallet_of(&self, player_number: usize) -> Option<&str> {
+        self.players
+            .values()
+            .find(|player| player.player_number == player_number)
+            .map(|player| player.wallet.as_str())
+    }
+
+    /// Sends `message` to one player of this match and records it in the
+    /// transcript and the replay.
+    pub fn send(
+        &mut self,
+        endpoint: &mut Endpoint,
+        client_id: ClientId,
+        channel: ServerChannel,
+        message: ServerMessage,
+    ) {
+        let channel_id = channel as u8;
+
+        endpoint
+            .send_message_on(client_id, channel_id, &message)
+            .unwrap();
+        self.transcript.record(&message);
+        self.replay.record_server(channel_id, &message);
+    }
+
+    /// Sends `message` to every player and spectator of this match and records
+    /// it once in the transcript and the replay.
+    pub fn broadcast(
+        &mut self,
+        endpoint: &mut Endpoint,
+        channel: ServerChannel,
+        message: ServerMessage,
+    ) {
+        let channel_id = channel as u8;
+
+        endpoint
+            .send_group_message_on(
+                self.players.keys().chain(self.spectators.iter()),
+                channel_id,
+                &message,
+            )
+            .unwrap();
+        self.transcript.record(&message);
+        self.replay.record_server(channel_id, &message);
+    }
+
+    /// Resets this finished match for its rematch: the same session, players,
+    /// spectators and settings with fresh hearts and a new key seed. The rematch
+    /// counter gives the new game its own transcript and on-chain commit.
+    pub fn restart_as_rematch(&mut self, endpoint: &mut Endpoint) {
+        self.rematch += 1;
+        self.rematch_votes.clear();
+        self.player_1_hearts = STARTING_HEARTS;
+        self.player_2_hearts = STARTING_HEARTS;
+        self.is_over = false;
+        self.winner = None;
+        self.series = SeriesScore::new(self.settings.best_of);
+        self.next_round_at = None;
+        self.key_seed = rand::random();
+        self.transcript = MatchTranscript::for_rematch(self.id, self.rematch);
+        self.replay = MatchReplay::for_rematch(self.id, self.rematch, 0);
+
+        // Not part of either transcript, it only tells the clients to reset.
+        endpoint
+            .send_group_message_on(
+                self.players.keys().chain(self.spectators.iter()),
+                ServerChannel::Lobby,
+                &ServerMessage::RematchStarting {
+                    match_id: self.id,
+                    rematch: self.rematch,
+                },
+            )
+            .unwrap();
+
+        self.broadcast(
+            endpoint,
+            ServerChannel::Lobby,
+            ServerMessage::IsGameReadyToStart {
+                match_id: self.id,
+                is_ready: true,
+                players: self.players_by_number(),
+                settings: self.settings,
+                key_seed: self.key_seed,
+            },
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct Entrant {
+    pub wallet: String,
+    pub entry_token: Uuid,
+    /// Empty while the player is disconnected.
+    pub client_id: Option<ClientId>,
+    pub disconnected_at: Option<Instant>,
+}
+
+impl Entrant {
+    fn is_connected(&self) -> bool {
+        self.client_id.is_some()
+    }
+
+    fn is_gone(&self, now: Instant) -> bool {
+        self.disconnected_at
+            .is_some_and(|disconnected_at| now - disconnected_at >= REJOIN_TIMEOUT)
+    }
+}
+
+#[derive(Debug)]
+pub struct Tournament {
+    pub bracket: Bracket,
+    pub organizer: ClientId,
+    /// Registered players by the name they play under.
+    pub entrants: HashMap<String, Entrant>,
+    /// Everyone the bracket is sent to, players included.
+    pub followers: HashSet<ClientId>,
+}
+
+impl Tournament {
+    pub fn new(bracket: Bracket, organizer: ClientId) -> Self {
+        Self {
+            bracket,
+            organizer,
+            entrants: HashMap::new(),
+            followers: HashSet::from([organizer]),
+        }
+    }
+
+    /// Sends the bracket to everyone following it and dumps it to
+    /// `TOURNAMENT_DIR`.
+    pub fn publish(&self, endpoint: &mut Endpoint) {
+        let _ = endpoint.send_group_message_on(
+            self.followers.iter(),
+            ServerChannel::Lobby,
+            &ServerMessage::TournamentUpdated {
+                bracket: self.bracket.clone(),
+            },
+        );
+
+        if let Err(e) = self.bracket.save(Path::new(TOURNAMENT_DIR)) {
+            error!(
+                "❌ Failed to save bracket of tournament {:?}: {:?}",
+                self.bracket.code, e
+            );
+        }
+    }
+
+    /// The player still connected to the duel at `round`/`slot` once the other
+    /// has been gone for longer than `REJOIN_TIMEOUT`. They win by forfeit.
+    pub fn forfeit_winner(&self, round: usize, slot: usize, now: Instant) -> Option<String> {
+        let bracket_match = &self.bracket.rounds[round][slot];
+        let player_1 = bracket_match.player_1.clone()?;
+        let player_2 = bracket_match.player_2.clone()?;
+
+        let entrant_1 = self.entrants.get(&player_1)?;
+        let entrant_2 = self.entrants.get(&player_2)?;
+
+        if entrant_1.is_gone(now) && entrant_2.is_connected() {
+            Some(player_2)
+        } else if entrant_2.is_gone(now) && entrant_1.is_connected() {
+            Some(player_1)
+        } else {
+            None
+        }
+    }
+
+    /// Opens a session for the duel at `round`/`slot` once both of its
+    /// players are connected.
+    pub fn start_match(
+        &mut self,
+        endpoint: &mut Endpoint,
+        round: usize,
+        slot: usize,
+    ) -> Option<MatchSession> {
+        let bracket_match = &self.bracket.rounds[round][slot];
+        let names = [
+            bracket_match.player_1.clone()?,
+            bracket_match.player_2.clone()?,
+        ];
+
+        let mut session = MatchSession::new(Uuid::new_v4(), self.bracket.settings);
+        session.tournament = Some(self.bracket.code.clone());
+
+        for (player_number, name) in (1..).zip(names) {
+            let entrant = self.entrants.get(&name)?;
+
+            session.players.insert(
+                entrant.client_id?,
+                Player::new(player_number, entrant.wallet.clone(), name),
+            );
+        }
+
+        let match_id = session.id;
+
+        for (client_id, player) in session.players.clone() {
+            session.send(
+                endpoint,
+                client_id,
+                ServerChannel::Lobby,
+                ServerMessage::JoinedMatch {
+                    match_id,
+                    player_number: player.player_number,
+                },
+            );
+        }
+
+        session.broadcast(
+            endpoint,
+            ServerChannel::Lobby,
+            ServerMessage::IsGameReadyToStart {
+                match_id,
+                is_ready: true,
+                players: session.players_by_number(),
+                settings: session.settings,
+                key_seed: session.key_seed,
+            },
+        );
+
+        self.bracket.rounds[round][slot].match_id = Some(match_id);
+
+        Some(session)
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct Tournaments(pub HashMap<String, Tournament>);
+
+fn reject_tournament_request(endpoint: &mut Endpoint, client_id: ClientId, error_message: String) {
+    let _ = endpoint.send_message_on(
+        client_id,
+        ServerChannel::Lobby,
+        &ServerMessage::TournamentRejected { error_message },
+    );
+}
+
+/// The nonce each connected client has to sign before its wallet is trusted.
+#[derive(Resource, Default, Debug)]
+pub struct WalletChallenges(pub HashMap<ClientId, String>);
+
+impl WalletChallenges {
+    /// Consumes the client's nonce and checks the wallet signed it. An empty
+    /// wallet is let through as an anonymous player whose results stay off-chain.
+    pub fn verify(
+        &mut self,
+        client_id: ClientId,
+        player_wallet: String,
+        wallet_signature: &str,
+    ) -> Result<String, String> {
+        let nonce = self.0.remove(&client_id);
+
+        if player_wallet.is_empty() {
+            return Ok(player_wallet);
+        }
+
+        let Some(nonce) = nonce else {
+            return Err("Wallet challenge missing".to_string());
+        };
+
+        if !wallet::verify_wallet_signature(&player_wallet, &nonce, wallet_signature) {
+            return Err("Wallet verification failed".to_string());
+        }
+
+        Ok(player_wallet)
+    }
+}
+
+#[derive(Debug)]
+pub struct HostRecord {
+    pub looked_up_at: i64,
+    /// Empty while the lookup runs, or when it failed.
+    pub record: Option<(u32, u32)>,
+}
+
+/// The on-chain win/loss records of public match hosts, shown as their rating
+/// in the lobby browser. Lookups run on their own threads and report back
+/// through the channel.
+#[derive(Resource)]
+pub struct HostRecords {
+    pub records: HashMap<String, HostRecord>,
+    sender: Sender<(String, Option<(u32, u32)>)>,
+    receiver: Receiver<(String, Option<(u32, u32)>)>,
+}
+
+impl Default for HostRecords {
+    fn default() -> Self {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+
+        Self {
+            records: HashMap::new(),
+            sender,
+            receiver,
+        }
+    }
+}
+
+impl HostRecords {
+    pub fn record_of(&self, wallet: &str) -> Option<(u32, u32)> {
+        self.records
+            .get(wallet)
+            .and_then(|host_record| host_record.record)
+    }
+
+    /// Whether the wallet has never been looked up, or not for a while.
+    pub fn is_due(&self, wallet: &str, now: i64) -> bool {
+        self.records
+            .get(wallet)
+            .is_none_or(|host_record| now - host_record.looked_up_at >= HOST_RECORD_TTL_SECS)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchCommitPayload {
+    pub match_id: Uuid,
+    pub transcript_hash: String,
+    pub winner: String,
+    pub loser: String,
+    pub dispute_window: i64,
 }
 
 fn start_listening(mut server: ResMut<QuinnetServer>) {
@@ -45,7 +475,14 @@ fn start_listening(mut server: ResMut<QuinnetServer>) {
         .unwrap();
 }
 
-fn handle_client_messages(mut server: ResMut<QuinnetServer>, mut matches: ResMut<Matches>) {
+fn handle_client_messages(
+    mut server: ResMut<QuinnetServer>,
+    mut matches: ResMut<Matches>,
+    mut outbox: ResMut<Outbox>,
+    mut wallet_challenges: ResMut<WalletChallenges>,
+    mut tournaments: ResMut<Tournaments>,
+    host_records: Res<HostRecords>,
+) {
     let endpoint = server.endpoint_mut();
 
     for client_id in endpoint.clients() {
@@ -53,57 +490,111 @@ fn handle_client_messages(mut server: ResMut<QuinnetServer>, mut matches: ResMut
             endpoint.try_receive_message_from::<ClientMessage>(client_id)
         {
             match message {
+                ClientMessage::WalletChallengeRequest => {
+                    if channel_id == 0 {
+                        let nonce = Uuid::new_v4().to_string();
+
+                        wallet_challenges.0.insert(client_id, nonce.clone());
+
+                        endpoint
+                            .send_message_on(
+                                client_id,
+                                ServerChannel::Lobby,
+                                &ServerMessage::WalletChallenge { nonce },
+                            )
+                            .unwrap();
+                    }
+                }
                 ClientMessage::CreateMatchRequest {
                     match_id,
                     player_wallet,
+                    wallet_signature,
+                    username,
+                    settings,
                 } => {
                     if channel_id == 0 {
-                        let player_1_hearts = 5;
-                        let player_2_hearts = 5;
-
-                        let mut new_session = MatchSession {
-                            id: match_id,
-                            players: HashMap::new(),
-                            player_1_hearts,
-                            player_2_hearts,
+                        let player_wallet = match wallet_challenges.verify(
+                            client_id,
+                            player_wallet,
+                            &wallet_signature,
+                        ) {
+                            Ok(player_wallet) => player_wallet,
+                            Err(error_message) => {
+                                endpoint
+                                    .send_message_on(
+                                        client_id,
+                                        ServerChannel::Lobby,
+                                        &ServerMessage::MatchJoinError { error_message },
+                                    )
+                                    .unwrap();
+                                continue;
+                            }
                         };
 
-                        new_session.players.insert(
+                        let mut new_session = MatchSession::new(match_id, settings);
+
+                        new_session
+                            .players
+                            .insert(client_id, Player::new(1, player_wallet, username));
+
+                        new_session.send(
+                            endpoint,
                             client_id,
-                            Player {
+                            ServerChannel::Lobby,
+                            ServerMessage::MatchCreated {
+                                match_id,
                                 player_number: 1,
-                                wallet: player_wallet,
                             },
                         );
 
-                        matches.sessions.insert(match_id, new_session);
+                        new_session.send(
+                            endpoint,
+                            client_id,
+                            ServerChannel::Lobby,
+                            ServerMessage::PlayerCountingUpdate { match_id },
+                        );
 
-                        endpoint
-                            .send_message_on(
-                                client_id,
-                                ServerChannel::Lobby,
-                                &ServerMessage::MatchCreated {
-                                    match_id: match_id,
-                                    player_number: 1,
-                                },
-                            )
-                            .unwrap();
+                        matches.sessions.insert(match_id, new_session);
+                    }
+                }
 
-                        endpoint
-                            .send_message_on(
-                                client_id,
-                                ServerChannel::Lobby,
-                                &ServerMessage::PlayerCountingUpdate { match_id },
-                            )
-                            .unwrap();
+                ClientMessage::UpdateMatchSettings { match_id, settings } => {
+                    // Only the host picks the rules, and only until an opponent joins.
+                    if let Some(session) = matches.sessions.get_mut(&match_id)
+                        && channel_id == 0
+                        && session.is_host(client_id)
+                        && !session.is_full()
+                    {
+                        session.settings = settings.sanitized();
+                        session.series = SeriesScore::new(session.settings.best_of);
                     }
                 }
 
                 ClientMessage::JoinMatchRequest {
                     match_id,
                     player_wallet,
+                    wallet_signature,
+                    username,
                 } => {
                     if channel_id == 0 {
+                        let player_wallet = match wallet_challenges.verify(
+                            client_id,
+                            player_wallet,
+                            &wallet_signature,
+                        ) {
+                            Ok(player_wallet) => player_wallet,
+                            Err(error_message) => {
+                                endpoint
+                                    .send_message_on(
+                                        client_id,
+                                        ServerChannel::Lobby,
+                                        &ServerMessage::MatchJoinError { error_message },
+                                    )
+                                    .unwrap();
+                                continue;
+                            }
+                        };
+
                         match matches.sessions.get_mut(&match_id) {
                             Some(session) => {
                                 if session.is_full() {
@@ -121,44 +612,38 @@ fn handle_client_messages(mut server: ResMut<QuinnetServer>, mut matches: ResMut
 
                                     session.players.insert(
                                         client_id,
-                                        Player {
+                                        Player::new(player_number, player_wallet, username),
+                                    );
+
+                                    session.send(
+                                        endpoint,
+                                        client_id,
+                                        ServerChannel::Lobby,
+                                        ServerMessage::JoinedMatch {
+                                            match_id,
                                             player_number,
-                                            wallet: player_wallet,
                                         },
                                     );
 
-                                    endpoint
-                                        .send_message_on(
-                                            client_id,
+                                    session.send(
+                                        endpoint,
+                                        client_id,
+                                        ServerChannel::Lobby,
+                                        ServerMessage::PlayerCountingUpdate { match_id },
+                                    );
+
+                                    if session.is_full() {
+                                        session.broadcast(
+                                            endpoint,
                                             ServerChannel::Lobby,
-                                            &ServerMessage::JoinedMatch {
+                                            ServerMessage::IsGameReadyToStart {
                                                 match_id,
-                                                player_number,
+                                                is_ready: true,
+                                                players: session.players_by_number(),
+                                                settings: session.settings,
+                                                key_seed: session.key_seed,
                                             },
-                                        )
-                                        .unwrap();
-
-                                    endpoint
-                                        .send_message_on(
-                                            client_id,
-                                            ServerChannel::Lobby,
-                                            &ServerMessage::PlayerCountingUpdate { match_id },
-                                        )
-                                        .unwrap();
-
-                                    if session.is_full() {
-                                        for pid in session.players.keys() {
-                                            endpoint
-                                                .send_message_on(
-                                                    *pid,
-                                                    ServerChannel::Lobby,
-                                                    &ServerMessage::IsGameReadyToStart {
-                                                        match_id,
-                                                        is_ready: true,
-                                                    },
-                                                )
-                                                .unwrap();
-                                        }
+                                        );
                                     }
                                 }
                             }
@@ -176,57 +661,364 @@ fn handle_client_messages(mut server: ResMut<QuinnetServer>, mut matches: ResMut
                         }
                     }
                 }
-                ClientMessage::ShootingCommand {
-                    match_id,
-                    player_number,
+                ClientMessage::SpectateMatchRequest { match_id } => {
+                    if channel_id == 0 {
+                        match matches.sessions.get_mut(&match_id) {
+                            Some(session)
+                                if !session.players.contains_key(&client_id)
+                                    && !session.is_over =>
+                            {
+                                session.spectators.insert(client_id);
+
+                                // Not part of the transcript, watching doesn't change the result.
+                                endpoint
+                                    .send_message_on(
+                                        client_id,
+                                        ServerChannel::Lobby,
+                                        &ServerMessage::SpectatingMatch {
+                                            match_id,
+                                            players: session.players_by_number(),
+                                            player_1_hearts: session.player_1_hearts,
+                                            player_2_hearts: session.player_2_hearts,
+                                            series: session.series,
+                                            in_progress: session.is_full(),
+                                        },
+                                    )
+                                    .unwrap();
+                            }
+                            _ => {
+                                endpoint
+                                    .send_message_on(
+                                        client_id,
+                                        ServerChannel::Lobby,
+                                        &ServerMessage::MatchJoinError {
+                                            error_message: "Match not found".to_string(),
+                                        },
+                                    )
+                                    .unwrap();
+                            }
+                        }
+                    }
+                }
+                ClientMessage::CreateTournament { code, settings } => {
+                    if channel_id != 0 {
+                        continue;
+                    }
+
+                    if !tournament::is_valid_code(&code) {
+                        reject_tournament_request(
+                            endpoint,
+                            client_id,
+                            "Invalid tournament code".to_string(),
+                        );
+                        continue;
+                    }
+
+                    match tournaments.0.entry(code.clone()) {
+                        Entry::Occupied(_) => reject_tournament_request(
+                            endpoint,
+                            client_id,
+                            "Tournament code already taken".to_string(),
+                        ),
+                        Entry::Vacant(entry) => {
+                            let tournament =
+                                Tournament::new(Bracket::new(code.clone(), settings), client_id);
+                            tournament.publish(endpoint);
+
+                            info!("Tournament {:?} created", code);
+                            entry.insert(tournament);
+                        }
+                    }
+                }
+                ClientMessage::RegisterTournament {
+                    code,
+                    player_wallet,
+                    wallet_signature,
                 } => {
-                    if let Some(session) = matches.sessions.get_mut(&match_id) {
-                        if session.players.get(&client_id).is_some() && channel_id == 1 {
-                            if player_number == 1 {
-                                session.player_2_hearts = session.player_2_hearts.saturating_sub(1);
-                            } else {
-                                session.player_1_hearts = session.player_1_hearts.saturating_sub(1);
+                    if channel_id != 0 {
+                        continue;
+                    }
+
+                    let player_wallet =
+                        match wallet_challenges.verify(client_id, player_wallet, &wallet_signature)
+                        {
+                            Ok(player_wallet) => player_wallet,
+                            Err(error_message) => {
+                                reject_tournament_request(endpoint, client_id, error_message);
+                                continue;
                             }
+                        };
 
-                            endpoint
-                                .broadcast_message_on(
-                                    ServerChannel::UpdateHeartsStatus,
-                                    &ServerMessage::UpdateHeartsStatus {
-                                        match_id,
-                                        who_is_hit: player_number,
-                                        player_1_hearts: session.player_1_hearts,
-                                        player_2_hearts: session.player_2_hearts,
-                                    },
-                                )
-                                .unwrap();
+                    let Some(tournament) = tournaments.0.get_mut(&code) else {
+                        reject_tournament_request(
+                            endpoint,
+                            client_id,
+                            "Tournament not found".to_string(),
+                        );
+                        continue;
+                    };
+
+                    // Entrants play under their verified wallet, a name the client
+                    // sends could be anyone's. Guests are listed as such.
+                    let name = if player_wallet.is_empty() {
+                        String::new()
+                    } else {
+                        wallet::short_wallet(&player_wallet)
+                    };
+
+                    match tournament.bracket.register(&name) {
+                        Ok(name) => {
+                            let entry_token = Uuid::new_v4();
+
+                            tournament.entrants.insert(
+                                name,
+                                Entrant {
+                                    wallet: player_wallet,
+                                    entry_token,
+                                    client_id: Some(client_id),
+                                    disconnected_at: None,
+                                },
+                            );
+                            tournament.followers.insert(client_id);
+
+                            let _ = endpoint.send_message_on(
+                                client_id,
+                                ServerChannel::Lobby,
+                                &ServerMessage::TournamentRegistered { code, entry_token },
+                            );
 
+                            tournament.publish(endpoint);
+                        }
+                        Err(e) => reject_tournament_request(endpoint, client_id, e.to_string()),
+                    }
+                }
+                ClientMessage::RejoinTournament { code, entry_token } => {
+                    if channel_id != 0 {
+                        continue;
+                    }
+
+                    let Some(tournament) = tournaments.0.get_mut(&code) else {
+                        reject_tournament_request(
+                            endpoint,
+                            client_id,
+                            "Tournament not found".to_string(),
+                        );
+                        continue;
+                    };
+
+                    match tournament
+                        .entrants
+                        .values_mut()
+                        .find(|entrant| entrant.entry_token == entry_token)
+                    {
+                        Some(entrant) => {
+                            entrant.client_id = Some(client_id);
+                            entrant.disconnected_at = None;
+                            tournament.followers.insert(client_id);
+                            tournament.publish(endpoint);
+                        }
+                        None => reject_tournament_request(
+                            endpoint,
+                            client_id,
+                            "Tournament entry not found".to_string(),
+                        ),
+                    }
+                }
+                ClientMessage::StartTournament { code } => {
+                    if channel_id != 0 {
+                        continue;
+                    }
+
+                    match tournaments.0.get_mut(&code) {
+                        Some(tournament) if tournament.organizer == client_id => {
+                            match tournament.bracket.start() {
+                                Ok(()) => {
+                                    info!("Tournament {:?} started", code);
+                                    tournament.publish(endpoint);
+                                }
+                                Err(e) => {
+                                    reject_tournament_request(endpoint, client_id, e.to_string())
+                                }
+                            }
+                        }
+                        Some(_) => reject_tournament_request(
+                            endpoint,
+                            client_id,
+                            "Only the organizer can start the tournament".to_string(),
+                        ),
+                        None => reject_tournament_request(
+                            endpoint,
+                            client_id,
+                            "Tournament not found".to_string(),
+                        ),
+                    }
+                }
+                ClientMessage::WatchTournament { code } => {
+                    if channel_id != 0 {
+                        continue;
+                    }
+
+                    match tournaments.0.get_mut(&code) {
+                        Some(tournament) => {
+                            tournament.followers.insert(client_id);
+                            tournament.publish(endpoint);
+                        }
+                        None => reject_tournament_request(
+                            endpoint,
+                            client_id,
+                            "Tournament not found".to_string(),
+                        ),
+                    }
+                }
+                ClientMessage::ListOpenMatches { page } => {
+                    if channel_id != 0 {
+                        continue;
+                    }
+
+                    let now = unix_now();
+                    let open_matches: Vec<OpenMatch> = matches
+                        .sessions
+                        .values()
+                        .filter_map(|session| session.open_match(now, &host_records))
+                        .collect();
+
+                    endpoint
+                        .send_message_on(
+                            client_id,
+                            ServerChannel::Lobby,
+                            lobby::open_matches_page(open_matches, page),
+                        )
+                        .unwrap();
+                }
+                ClientMessage::RematchRequest { match_id } => {
+                    // Bracket duels are scheduled by the tournament, not rematched.
+                    if channel_id == 0
+                        && let Some(session) = matches.sessions.get_mut(&match_id)
+                        && session.is_over
+                        && session.tournament.is_none()
+                        && let Some(player) = session.players.get(&client_id)
+                    {
+                        let player_number = player.player_number;
+                        session.rematch_votes.insert(client_id);
+
+                        if session.rematch_votes.len() < 2 {
                             endpoint
-                                .broadcast_message_on(
-                                    ServerChannel::Shooting,
-                                    &ServerMessage::ShootingCommand {
+                                .send_group_message_on(
+                                    session.players.keys().filter(|id| **id != client_id),
+                                    ServerChannel::Lobby,
+                                    &ServerMessage::RematchRequested {
                                         match_id,
                                         player_number,
                                     },
                                 )
                                 .unwrap();
+                        } else {
+                            session.restart_as_rematch(endpoint);
 
-                            if session.player_1_hearts == 0 || session.player_2_hearts == 0 {
-                                let winner = if session.player_1_hearts == 0
-                                    && session.player_2_hearts == 0
-                                {
-                                    0
-                                } else if session.player_1_hearts == 0 {
-                                    2
-                                } else {
-                                    1
-                                };
+                            info!(
+                                "Match {:?} restarted for rematch {}",
+                                match_id, session.rematch
+                            );
+                        }
+                    }
+                }
+                ClientMessage::DeclineRematch { match_id } => {
+                    if channel_id == 0
+                        && let Some(session) = matches.sessions.get_mut(&match_id)
+                        && session.is_over
+                        && session.players.contains_key(&client_id)
+                    {
+                        session.rematch_votes.clear();
 
-                                endpoint
-                                    .broadcast_message_on(
-                                        ServerChannel::GameOver,
-                                        &ServerMessage::GameOver { match_id, winner },
-                                    )
-                                    .unwrap();
+                        endpoint
+                            .send_group_message_on(
+                                session.players.keys().filter(|id| **id != client_id),
+                                ServerChannel::Lobby,
+                                &ServerMessage::RematchDeclined { match_id },
+                            )
+                            .unwrap();
+                    }
+                }
+                ClientMessage::ShootingCommand {
+                    match_id,
+                    player_number,
+                } => {
+                    // Spectators aren't in `players`, so they can never shoot.
+                    if let Some(session) = matches.sessions.get_mut(&match_id)
+                        && session.players.contains_key(&client_id)
+                        && channel_id == 1
+                        && !session.is_over
+                        && !session.is_round_intro(Instant::now())
+                    {
+                        if player_number == 1 {
+                            session.player_2_hearts = session.player_2_hearts.saturating_sub(1);
+                        } else {
+                            session.player_1_hearts = session.player_1_hearts.saturating_sub(1);
+                        }
+
+                        let player_1_hearts = session.player_1_hearts;
+                        let player_2_hearts = session.player_2_hearts;
+
+                        session.broadcast(
+                            endpoint,
+                            ServerChannel::UpdateHeartsStatus,
+                            ServerMessage::UpdateHeartsStatus {
+                                match_id,
+                                who_is_hit: player_number,
+                                player_1_hearts,
+                                player_2_hearts,
+                            },
+                        );
+
+                        session.broadcast(
+                            endpoint,
+                            ServerChannel::Shooting,
+                            ServerMessage::ShootingCommand {
+                                match_id,
+                                player_number,
+                            },
+                        );
+
+                        let Some(round_winner) =
+                            series::round_winner(player_1_hearts, player_2_hearts)
+                        else {
+                            continue;
+                        };
+
+                        match session.series.record_round(round_winner) {
+                            Some(winner) => {
+                                session.broadcast(
+                                    endpoint,
+                                    ServerChannel::GameOver,
+                                    ServerMessage::GameOver { match_id, winner },
+                                );
+
+                                session.is_over = true;
+                                session.winner = Some(winner);
+
+                                commit_match_result(endpoint, session, winner, &mut outbox);
+                            }
+                            None => {
+                                session.player_1_hearts = STARTING_HEARTS;
+                                session.player_2_hearts = STARTING_HEARTS;
+                                // The clients start their intro on `RoundOver`, after
+                                // this, so a shot they allow is never refused here.
+                                session.next_round_at = Some(
+                                    Instant::now()
+                                        + Duration::from_secs_f32(series::ROUND_INTRO_SECS),
+                                );
+
+                                // Sent after the hearts update that ended the round,
+                                // on its channel, so the refill can't overtake it.
+                                session.broadcast(
+                                    endpoint,
+                                    ServerChannel::UpdateHeartsStatus,
+                                    ServerMessage::RoundOver {
+                                        match_id,
+                                        round_winner,
+                                        series: session.series,
+                                    },
+                                );
                             }
                         }
                     }
@@ -236,40 +1028,368 @@ fn handle_client_messages(mut server: ResMut<QuinnetServer>, mut matches: ResMut
     }
 }
 
+/// Saves the transcript, tells both players its hash and queues the on-chain
+/// commit in the outbox. Stats only change once the dispute window has
+/// passed, the finalization is queued when the commit goes through.
+fn commit_match_result(
+    endpoint: &mut Endpoint,
+    session: &MatchSession,
+    winner: usize,
+    outbox: &mut Outbox,
+) {
+    let match_id = session.id;
+    let game_id = session.game_id();
+    let hash = session.transcript.commitment();
+    let transcript_hash = transcript::to_hex(&hash);
+
+    match session.transcript.save(Path::new(TRANSCRIPT_DIR)) {
+        Ok(path) => info!("Transcript for match {:?} saved to {:?}", game_id, path),
+        Err(e) => error!(
+            "❌ Failed to save transcript for match {:?}: {:?}",
+            game_id, e
+        ),
+    }
+
+    match session.replay.save(Path::new(REPLAY_DIR)) {
+        Ok(path) => info!("Replay of match {:?} saved to {:?}", game_id, path),
+        Err(e) => error!("❌ Failed to save replay for match {:?}: {:?}", game_id, e),
+    }
+
+    let _ = endpoint.send_group_message_on(
+        session.players.keys(),
+        ServerChannel::GameOver,
+        &ServerMessage::MatchCommitted {
+            match_id,
+            game_id,
+            transcript_hash: transcript_hash.clone(),
+        },
+    );
+
+    let loser = match winner {
+        1 => 2,
+        2 => 1,
+        _ => return,
+    };
+
+    let (Some(winner_wallet), Some(loser_wallet)) =
+        (session.wallet_of(winner), session.wallet_of(loser))
+    else {
+        return;
+    };
+
+    if winner_wallet.is_empty() || loser_wallet.is_empty() {
+        info!(
+            "Match {:?} has no wallets attached, skipping on-chain commit",
+            game_id
+        );
+        return;
+    }
+
+    let payload = MatchCommitPayload {
+        match_id: game_id,
+        transcript_hash,
+        winner: winner_wallet.to_string(),
+        loser: loser_wallet.to_string(),
+        dispute_window: DISPUTE_WINDOW_SECS,
+    };
+
+    outbox.push(game_id, ReportKind::Commit, payload, unix_now());
+
+    let _ = endpoint.send_group_message_on(
+        session.players.keys(),
+        ServerChannel::GameOver,
+        &ServerMessage::ResultSyncPending { match_id },
+    );
+}
+
+/// Sends every report that is due. Each one runs on its own thread and reports
+/// back through the outbox channel.
+fn deliver_reports(mut outbox: ResMut<Outbox>, config: Res<GameConfig>) {
+    for report in outbox.take_due() {
+        let request = config.match_api_post(report.kind.path());
+        let sender = outbox.sender();
+
+        std::thread::spawn(move || {
+            let status = match request.send_json(&report.payload) {
+                Ok(response) => response.status().as_u16(),
+                Err(ureq::Error::StatusCode(status)) => status,
+                Err(e) => {
+                    warn!(
+                        "Match {:?} {:?} failed: {:?}, will retry",
+                        report.match_id, report.kind, e
+                    );
+                    0
+                }
+            };
+
+            let outcome = DeliveryOutcome::from_status(status);
+
+            match outcome {
+                DeliveryOutcome::Delivered => {
+                    info!("✅ Match {:?} {:?} delivered", report.match_id, report.kind)
+                }
+                DeliveryOutcome::Rejected => error!(
+                    "❌ Match {:?} {:?} was rejected ({}), dropping it",
+                    report.match_id, report.kind, status
+                ),
+                DeliveryOutcome::Retry if status != 0 => warn!(
+                    "Match {:?} {:?} failed: {}, will retry",
+                    report.match_id, report.kind, status
+                ),
+                DeliveryOutcome::Retry => {}
+            }
+
+            let _ = sender.send(DeliveryResult {
+                match_id: report.match_id,
+                kind: report.kind,
+                outcome,
+            });
+        });
+    }
+}
+
+/// Looks up the record of every public match host who has a wallet, so the
+/// lobby browser can show it.
+fn lookup_host_records(
+    matches: Res<Matches>,
+    mut host_records: ResMut<HostRecords>,
+    config: Res<GameConfig>,
+) {
+    let now = unix_now();
+
+    for session in matches.sessions.values() {
+        if !session.settings.public || session.is_full() || session.tournament.is_some() {
+            continue;
+        }
+
+        let Some(wallet) = session.wallet_of(1) else {
+            continue;
+        };
+
+        if wallet.is_empty() || !host_records.is_due(wallet, now) {
+            continue;
+        }
+
+        let wallet = wallet.to_string();
+        let record = host_records.record_of(&wallet);
+
+        host_records.records.insert(
+            wallet.clone(),
+            HostRecord {
+                looked_up_at: now,
+                record,
+            },
+        );
+
+        let sender = host_records.sender.clone();
+        let source = StatsSource::from_config(&config);
+
+        std::thread::spawn(move || {
+            let record = source.get_record(&wallet).map(|record| record.win_loss());
+            let _ = sender.send((wallet, record));
+        });
+    }
+}
+
+fn settle_host_records(mut host_records: ResMut<HostRecords>) {
+    while let Ok((wallet, record)) = host_records.receiver.try_recv() {
+        // A failed lookup keeps the record shown so far.
+        if let Some(record) = record
+            && let Some(host_record) = host_records.records.get_mut(&wallet)
+        {
+            host_record.record = Some(record);
+        }
+    }
+}
+
+fn settle_reports(
+    mut server: ResMut<QuinnetServer>,
+    mut outbox: ResMut<Outbox>,
+    matches: Res<Matches>,
+) {
+    let endpoint = server.endpoint_mut();
+
+    for result in outbox.settle() {
+        if result.outcome != DeliveryOutcome::Delivered {
+            continue;
+        }
+
+        // Reports are filed under the game id, a rematch's differs from its session's.
+        if let Some(session) = matches
+            .sessions
+            .values()
+            .find(|session| session.game_id() == result.match_id)
+        {
+            // Stats only change once the finalization goes through.
+            let message = match result.kind {
+                ReportKind::Commit => ServerMessage::ResultCommitted {
+                    match_id: session.id,
+                },
+                ReportKind::Finalize => ServerMessage::ResultSynced {
+                    match_id: session.id,
+                },
+            };
+
+            let _ = endpoint.send_group_message_on(
+                session.players.keys(),
+                ServerChannel::GameOver,
+                &message,
+            );
+        }
+    }
+}
+
+/// Starts every bracket duel whose players are both connected, and moves
+/// the winners of finished ones on. A draw, or a duel someone left, is
+/// played again.
+fn run_tournaments(
+    mut server: ResMut<QuinnetServer>,
+    mut matches: ResMut<Matches>,
+    mut tournaments: ResMut<Tournaments>,
+) {
+    let endpoint = server.endpoint_mut();
+    let now = Instant::now();
+
+    for tournament in tournaments.0.values_mut() {
+        let mut changed = false;
+
+        for (round, slot) in tournament.bracket.ready_matches() {
+            let bracket_match = tournament.bracket.rounds[round][slot].clone();
+
+            let Some(match_id) = bracket_match.match_id else {
+                if let Some(winner) = tournament.forfeit_winner(round, slot, now) {
+                    info!(
+                        "Tournament {:?}: {} wins round {} by forfeit",
+                        tournament.bracket.code,
+                        winner,
+                        round + 1
+                    );
+                    tournament.bracket.record_winner(round, slot, winner);
+                    changed = true;
+                } else if let Some(session) = tournament.start_match(endpoint, round, slot) {
+                    matches.sessions.insert(session.id, session);
+                    changed = true;
+                }
+                continue;
+            };
+
+            let winner = match matches.sessions.get(&match_id) {
+                Some(session) => match session.winner {
+                    Some(1) => bracket_match.player_1,
+                    Some(2) => bracket_match.player_2,
+                    Some(_) => None,
+                    None => continue,
+                },
+                None => None,
+            };
+
+            match winner {
+                Some(winner) => tournament.bracket.record_winner(round, slot, winner),
+                None => tournament.bracket.rounds[round][slot].match_id = None,
+            }
+
+            changed = true;
+        }
+
+        if changed {
+            if let Some(champion) = &tournament.bracket.champion {
+                info!(
+                    "Tournament {:?} standing, champion: {}",
+                    tournament.bracket.code, champion
+                );
+            }
+
+            tournament.publish(endpoint);
+        }
+    }
+}
+
 fn handle_disconnection(
+    mut server: ResMut<QuinnetServer>,
     mut disconnect_events: EventReader<ConnectionLostEvent>,
     mut matches: ResMut<Matches>,
+    mut wallet_challenges: ResMut<WalletChallenges>,
+    mut tournaments: ResMut<Tournaments>,
 ) {
+    let endpoint = server.endpoint_mut();
+
     for event in disconnect_events.read() {
         let client_id = event.id;
 
-        let mut session_to_remove: Option<Uuid> = None;
+        wallet_challenges.0.remove(&client_id);
+
+        // Entrants keep their spot and can rejoin with their entry token, until
+        // `run_tournaments` gives their duel away.
+        for tournament in tournaments.0.values_mut() {
+            tournament.followers.remove(&client_id);
+
+            for entrant in tournament.entrants.values_mut() {
+                if entrant.client_id == Some(client_id) {
+                    entrant.client_id = None;
+                    entrant.disconnected_at = Some(Instant::now());
+                }
+            }
+        }
 
+        let mut sessions_to_remove: Vec<Uuid> = Vec::new();
+
+        // A tournament player can still be in their last duel while the next
+        // one runs, so every session is checked.
         for (id, session) in matches.sessions.iter_mut() {
+            if session.spectators.remove(&client_id) {
+                info!("Spectator {:?} left match {:?}", client_id, id);
+                continue;
+            }
+
             if session.players.remove(&client_id).is_some() {
-                println!("Client {:?} disconnected from match {:?}", client_id, id);
+                info!("Client {:?} disconnected from match {:?}", client_id, id);
+
+                let _ = endpoint.send_group_message_on(
+                    session.players.keys(),
+                    ServerChannel::Lobby,
+                    &ServerMessage::OpponentLeft { match_id: *id },
+                );
 
                 if session.players.len() < 2 {
-                    println!("Match {:?} is no longer active. Removing it.", id);
-                    session_to_remove = Some(*id);
+                    info!("Match {:?} is no longer active. Removing it.", id);
+                    sessions_to_remove.push(*id);
                 }
-                break;
             }
         }
 
-        if let Some(id) = session_to_remove {
+        for id in sessions_to_remove {
             matches.sessions.remove(&id);
         }
     }
 }
 
+fn load_config() -> GameConfig {
+    let (config, problems) = GameConfig::load();
+
+    for problem in problems {
+        warn!("{}", problem);
+    }
+
+    config
+}
+
 pub fn main() {
     App::new()
+        // First, so loading the outbox and config can already log.
+        .add_plugins(LogPlugin::default())
         .insert_resource(Matches::default())
+        .insert_resource(Outbox::load(Path::new(OUTBOX_FILE)))
+        .insert_resource(WalletChallenges::default())
+        .insert_resource(Tournaments::default())
+        .insert_resource(HostRecords::default())
+        .insert_resource(load_config())
         .add_plugins(ScheduleRunnerPlugin::default())
+        .add_plugins(TimePlugin)
         .add_plugins(QuinnetServerPlugin::default())
         .add_systems(Startup, start_listening)
-        .add_systems(Update, handle_client_messages)
+        .add_systems(Update, (handle_client_messages, run_tournaments).chain())
+        .add_systems(Update, (deliver_reports, settle_reports))
+        .add_systems(Update, (lookup_host_records, settle_host_records))
         .add_systems(Update, handle_disconnection)
         .run();
 }
@@ -0,0 +1,145 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use proof_of_duel_game::{
+    config::GameConfig,
+    shooting::{keycode, sequence},
+    transcript::{self, MatchTranscript},
+};
+
+/// Re-plays a saved match transcript and checks it against the hash the
+/// server committed on-chain.
+///
+/// With `--resolve` it also settles the disputed match on-chain: the result
+/// stands if the replay is consistent and the transcript matches the committed
+/// hash, and is voided otherwise. The program takes the authority's word for
+/// it, so the hash has to be checked here.
+///
+/// Usage: verify_transcript <transcripts/MATCH_ID.json> [COMMITTED_HASH_HEX] [--resolve]
+pub fn main() -> ExitCode {
+    let (flags, mut args): (Vec<String>, Vec<String>) = std::env::args()
+        .skip(1)
+        .partition(|arg| arg.starts_with("--"));
+    let resolve = flags.iter().any(|flag| flag == "--resolve");
+    args.reverse();
+
+    let Some(path) = args.pop().map(PathBuf::from) else {
+        eprintln!("Usage: verify_transcript <transcript.json> [committed-hash-hex] [--resolve]");
+        return ExitCode::FAILURE;
+    };
+    let committed = args.pop();
+
+    let transcript = match MatchTranscript::load(&path) {
+        Ok(transcript) => transcript,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let hash = transcript.commitment();
+    println!("Match ID: {}", transcript.match_id);
+    if transcript.rematch > 0 {
+        println!(
+            "Rematch: {} (committed as {})",
+            transcript.rematch,
+            transcript.game_id()
+        );
+    }
+    println!("Messages: {}", transcript.messages.len());
+    println!("Transcript hash: {}", transcript::to_hex(&hash));
+
+    if let Some((settings, key_seed)) = transcript.key_sequence() {
+        let first_combo = sequence::combo_keys(key_seed, 0, &settings)
+            .into_iter()
+            .map(keycode::key_label)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        println!(
+            "Keys: {}, combo of {}, seed {}",
+            settings.key_pool.label(),
+            settings.combo_length,
+            key_seed
+        );
+        println!("First combo: {}", first_combo);
+    }
+
+    let consistent = match transcript.replay() {
+        Ok(0) => {
+            println!("✅ Replay is consistent: draw");
+            true
+        }
+        Ok(winner) => {
+            println!("✅ Replay is consistent: player {} wins", winner);
+            true
+        }
+        Err(e) => {
+            eprintln!("❌ Replay failed: {}", e);
+            false
+        }
+    };
+
+    let matches_commitment = match committed {
+        Some(committed) => match transcript::from_hex(committed.trim()) {
+            Some(expected) if expected == hash => {
+                println!("✅ Transcript matches the commitment");
+                true
+            }
+            Some(_) => {
+                eprintln!("❌ Transcript does not match the commitment");
+                false
+            }
+            None => {
+                eprintln!("❌ Commitment must be a 64 character hex string");
+                return ExitCode::FAILURE;
+            }
+        },
+        None if resolve => {
+            eprintln!("❌ --resolve needs the committed hash to check the transcript against");
+            return ExitCode::FAILURE;
+        }
+        None => true,
+    };
+
+    if resolve && !resolve_match(&transcript, consistent && matches_commitment) {
+        return ExitCode::FAILURE;
+    }
+
+    if !consistent || !matches_commitment {
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Asks the frontend to settle the disputed match.
+fn resolve_match(transcript: &MatchTranscript, result_stands: bool) -> bool {
+    let (config, problems) = GameConfig::load();
+
+    for problem in problems {
+        eprintln!("Warning: {}", problem);
+    }
+
+    let payload = serde_json::json!({
+        "match_id": transcript.game_id(),
+        "result_stands": result_stands,
+    });
+
+    match config
+        .match_api_post("/api/match-resolve")
+        .send_json(&payload)
+    {
+        Ok(_) if result_stands => {
+            println!("✅ Dispute resolved, the result stands");
+            true
+        }
+        Ok(_) => {
+            println!("✅ Dispute resolved, the result is void");
+            true
+        }
+        Err(e) => {
+            eprintln!("❌ Could not resolve the dispute: {}", e);
+            false
+        }
+    }
+}
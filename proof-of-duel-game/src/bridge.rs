@@ -0,0 +1,265 @@
+use axum::Extension;
+use bevy::prelude::*;
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+
+use crate::stats::PlayerRecord;
+
+/// What the local HTTP handlers and background tasks have to tell the game.
+#[derive(Debug, Clone)]
+pub enum BridgeMessage {
+    LoggedIn {
+        public_key: String,
+        username: String,
+        expires_at: i64,
+        id_token: String,
+    },
+    LoggedOut,
+    StatsUpdated {
+        win: u32,
+        loss: u32,
+    },
+    /// The answer to a stats fetch, `None` if it failed.
+    StatsFetched {
+        public_key: String,
+        record: Option<PlayerRecord>,
+    },
+    OpponentStatsLoaded {
+        wallet: String,
+        win: u32,
+        loss: u32,
+    },
+    WalletSigned {
+        public_key: String,
+        nonce: String,
+        signature: String,
+    },
+    /// The frontend's answer to `initialize_player`, the transaction signature
+    /// or why it failed.
+    ProfileCreated {
+        public_key: String,
+        result: Result<String, String>,
+    },
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct LoggedIn {
+    pub public_key: String,
+    pub username: String,
+    /// Unix time after which the login has to be renewed in the browser.
+    pub expires_at: i64,
+    /// The verified Civic token, kept so the next launch can check it again.
+    pub id_token: String,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct LoggedOut;
+
+#[derive(Event, Debug, Clone)]
+pub struct StatsUpdated {
+    pub win: u32,
+    pub loss: u32,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct StatsFetched {
+    pub public_key: String,
+    pub record: Option<PlayerRecord>,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct OpponentStatsLoaded {
+    pub wallet: String,
+    pub win: u32,
+    pub loss: u32,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct WalletSigned {
+    pub public_key: String,
+    pub nonce: String,
+    pub signature: String,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct ProfileCreated {
+    pub public_key: String,
+    pub result: Result<String, String>,
+}
+
+/// The sending half of the bridge. Axum handlers get it as an `Extension`,
+/// systems that spawn background tasks get it as a resource.
+#[derive(Resource, Clone)]
+pub struct BridgeSender(Sender<BridgeMessage>);
+
+impl BridgeSender {
+    pub fn send(&self, message: BridgeMessage) {
+        // The receiver lives as long as the app, so this only fails during shutdown.
+        let _ = self.0.send(message);
+    }
+
+    pub fn extension(&self) -> Extension<BridgeSender> {
+        Extension(self.clone())
+    }
+}
+
+#[derive(Resource)]
+pub struct BridgeReceiver(Receiver<BridgeMessage>);
+
+pub fn event_bridge() -> (BridgeSender, BridgeReceiver) {
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    (BridgeSender(sender), BridgeReceiver(receiver))
+}
+
+/// Drains everything sent over the bridge since the last frame into Bevy events.
+#[allow(clippy::too_many_arguments)]
+pub fn drain_event_bridge(
+    receiver: Res<BridgeReceiver>,
+    mut logged_in: EventWriter<LoggedIn>,
+    mut logged_out: EventWriter<LoggedOut>,
+    mut stats_updated: EventWriter<StatsUpdated>,
+    mut stats_fetched: EventWriter<StatsFetched>,
+    mut opponent_stats_loaded: EventWriter<OpponentStatsLoaded>,
+    mut wallet_signed: EventWriter<WalletSigned>,
+    mut profile_created: EventWriter<ProfileCreated>,
+) {
+    loop {
+        let message = match receiver.0.try_recv() {
+            Ok(message) => message,
+            Err(TryRecvError::Empty) => break,
+            Err(TryRecvError::Disconnected) => {
+                error!("❌ Event bridge disconnected");
+                break;
+            }
+        };
+
+        match message {
+            BridgeMessage::LoggedIn {
+                public_key,
+                username,
+                expires_at,
+                id_token,
+            } => {
+                logged_in.write(LoggedIn {
+                    public_key,
+                    username,
+                    expires_at,
+                    id_token,
+                });
+            }
+            BridgeMessage::LoggedOut => {
+                logged_out.write(LoggedOut);
+            }
+            BridgeMessage::StatsUpdated { win, loss } => {
+                stats_updated.write(StatsUpdated { win, loss });
+            }
+            BridgeMessage::StatsFetched { public_key, record } => {
+                stats_fetched.write(StatsFetched { public_key, record });
+            }
+            BridgeMessage::OpponentStatsLoaded { wallet, win, loss } => {
+                opponent_stats_loaded.write(OpponentStatsLoaded { wallet, win, loss });
+            }
+            BridgeMessage::WalletSigned {
+                public_key,
+                nonce,
+                signature,
+            } => {
+                wallet_signed.write(WalletSigned {
+                    public_key,
+                    nonce,
+                    signature,
+                });
+            }
+            BridgeMessage::ProfileCreated { public_key, result } => {
+                profile_created.write(ProfileCreated { public_key, result });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bridge_app() -> (App, BridgeSender) {
+        let (sender, receiver) = event_bridge();
+
+        let mut app = App::new();
+        app.insert_resource(receiver)
+            .add_event::<LoggedIn>()
+            .add_event::<LoggedOut>()
+            .add_event::<StatsUpdated>()
+            .add_event::<StatsFetched>()
+            .add_event::<OpponentStatsLoaded>()
+            .add_event::<WalletSigned>()
+            .add_event::<ProfileCreated>()
+            .add_systems(Update, drain_event_bridge);
+
+        (app, sender)
+    }
+
+    fn read_events<E: Event + Clone>(app: &App) -> Vec<E> {
+        let events = app.world().resource::<Events<E>>();
+        events.get_cursor().read(events).cloned().collect()
+    }
+
+    #[test]
+    fn messages_become_events_in_order() {
+        let (mut app, sender) = bridge_app();
+
+        sender.send(BridgeMessage::StatsUpdated { win: 1, loss: 0 });
+        sender.send(BridgeMessage::StatsUpdated { win: 2, loss: 1 });
+        sender.send(BridgeMessage::LoggedOut);
+        app.update();
+
+        let stats: Vec<(u32, u32)> = read_events::<StatsUpdated>(&app)
+            .into_iter()
+            .map(|event| (event.win, event.loss))
+            .collect();
+
+        assert_eq!(stats, vec![(1, 0), (2, 1)]);
+        assert_eq!(read_events::<LoggedOut>(&app).len(), 1);
+        assert!(read_events::<LoggedIn>(&app).is_empty());
+    }
+
+    #[test]
+    fn messages_from_other_threads_arrive_on_the_next_update() {
+        let (mut app, sender) = bridge_app();
+        app.update();
+
+        let extension = sender.extension();
+        std::thread::spawn(move || {
+            extension.0.send(BridgeMessage::WalletSigned {
+                public_key: "wallet".to_string(),
+                nonce: "nonce".to_string(),
+                signature: "signature".to_string(),
+            });
+        })
+        .join()
+        .unwrap();
+
+        assert!(read_events::<WalletSigned>(&app).is_empty());
+        app.update();
+
+        let signed = read_events::<WalletSigned>(&app);
+        assert_eq!(signed.len(), 1);
+        assert_eq!(signed[0].nonce, "nonce");
+    }
+
+    #[test]
+    fn every_message_is_drained_once() {
+        let (mut app, sender) = bridge_app();
+
+        sender.send(BridgeMessage::LoggedIn {
+            public_key: "wallet".to_string(),
+            username: "Gunslinger".to_string(),
+            expires_at: 0,
+            id_token: String::new(),
+        });
+        app.update();
+        app.update();
+
+        let world = app.world();
+        let events = world.resource::<Events<LoggedIn>>();
+        assert_eq!(events.len(), 1);
+    }
+}
@@ -9,6 +9,18 @@ pub struct PlayNowUICamera;
 #[derive(Component)]
 pub struct JoinGameUICamera;
 
+#[derive(Component)]
+pub struct PracticeUICamera;
+
+#[derive(Component)]
+pub struct SettingsUICamera;
+
+#[derive(Component)]
+pub struct ReplaysUICamera;
+
+#[derive(Component)]
+pub struct TournamentUICamera;
+
 #[derive(Component)]
 pub struct GameCamera;
 
@@ -66,6 +78,74 @@ pub fn despawn_join_game_ui_camera(
     }
 }
 
+pub fn practice_ui_camera_setup(mut commands: Commands) {
+    commands.spawn((
+        PracticeUICamera,
+        Camera2d,
+        Transform::from_xyz(0.0, 0.0, 1000.),
+    ));
+}
+
+pub fn despawn_practice_ui_camera(
+    mut commands: Commands,
+    query: Query<Entity, With<PracticeUICamera>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+pub fn settings_ui_camera_setup(mut commands: Commands) {
+    commands.spawn((
+        SettingsUICamera,
+        Camera2d,
+        Transform::from_xyz(0.0, 0.0, 1000.),
+    ));
+}
+
+pub fn despawn_settings_ui_camera(
+    mut commands: Commands,
+    query: Query<Entity, With<SettingsUICamera>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+pub fn replays_ui_camera_setup(mut commands: Commands) {
+    commands.spawn((
+        ReplaysUICamera,
+        Camera2d,
+        Transform::from_xyz(0.0, 0.0, 1000.),
+    ));
+}
+
+pub fn despawn_replays_ui_camera(
+    mut commands: Commands,
+    query: Query<Entity, With<ReplaysUICamera>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+pub fn tournament_ui_camera_setup(mut commands: Commands) {
+    commands.spawn((
+        TournamentUICamera,
+        Camera2d,
+        Transform::from_xyz(0.0, 0.0, 1000.),
+    ));
+}
+
+pub fn despawn_tournament_ui_camera(
+    mut commands: Commands,
+    query: Query<Entity, With<TournamentUICamera>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
 pub fn game_camera_setup(mut commands: Commands) {
     commands.spawn((GameCamera, Camera2d, Transform::from_xyz(0.0, 0.0, 1000.)));
 }
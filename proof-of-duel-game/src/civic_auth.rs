@@ -1,72 +1,769 @@
-use std::sync::{Arc, LazyLock, RwLock};
+use std::{
+    path::Path,
+    sync::{Arc, Mutex, PoisonError, RwLock},
+    time::{Duration, Instant},
+};
 
-use axum::{Json, http::StatusCode, response::IntoResponse};
-use bevy::prelude::*;
+use crossbeam_channel::{Receiver, Sender};
+
+use axum::{Extension, Json, http::StatusCode, response::IntoResponse};
+use bevy::{prelude::*, tasks::AsyncComputeTaskPool};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, Validation, decode, decode_header,
+    jwk::{Jwk, JwkSet},
+};
 use serde::{Deserialize, Serialize};
 
-use crate::{LoggedInState, ui::profile::ProfileData};
+use crate::{
+    LoggedInState,
+    bridge::{BridgeMessage, BridgeSender, LoggedIn, LoggedOut},
+    config::GameConfig,
+    duel_profile::DuelProfile,
+    session::{SESSION_FILE, Session, unix_now},
+    stats::StatsData,
+    ui::profile::ProfileData,
+};
+
+pub const CIVIC_ISSUER: &str = "https://auth.civic.com/oauth/";
+pub const CIVIC_JWKS: &str = "https://auth.civic.com/oauth/jwks";
+pub const CIVIC_CLIENT_ID: &str = "3443c4fa-fb55-493c-817a-cc7ef29f23f6";
+
+const JWKS_ATTEMPTS: u32 = 6;
+const JWKS_RETRY_BASE: Duration = Duration::from_secs(1);
+const JWKS_RETRY_MAX: Duration = Duration::from_secs(30);
+/// How often a token with an unknown `kid` may make us refetch the JWKS.
+const JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Where the Civic signing keys come from and which tokens to accept, taken
+/// from `GameConfig::civic`. `jwks` may be a URL or a path to a local JWKS
+/// file, so a stand-in issuer can be used while testing.
+#[derive(Debug, Clone)]
+pub struct CivicConfig {
+    pub jwks: String,
+    pub issuer: String,
+    pub client_id: String,
+}
+
+impl CivicConfig {
+    fn load_jwks(&self) -> Result<JwkSet, String> {
+        let json = if self.jwks.starts_with("http://") || self.jwks.starts_with("https://") {
+            ureq::get(&self.jwks)
+                .call()
+                .map_err(|e| e.to_string())?
+                .body_mut()
+                .read_to_string()
+                .map_err(|e| e.to_string())?
+        } else {
+            std::fs::read_to_string(&self.jwks).map_err(|e| e.to_string())?
+        };
+
+        serde_json::from_str(&json).map_err(|e| e.to_string())
+    }
+}
+
+/// The Civic signing keys and the rules tokens are checked against. The keys
+/// are refetched when a token names one we don't know, since Civic rotates them.
+struct CivicVerifier {
+    config: CivicConfig,
+    jwks: RwLock<Option<JwkSet>>,
+    refreshed_at: Mutex<Option<Instant>>,
+}
+
+/// The one `CivicVerifier` of the game. `/login` gets it as an `Extension`,
+/// `load_civic_jwks` gets it as a resource.
+#[derive(Resource, Clone)]
+pub struct SharedCivicVerifier(Arc<CivicVerifier>);
 
-#[derive(Default)]
-struct AuthState {
-    public_key: String,
-    username: String,
+impl SharedCivicVerifier {
+    pub fn new(config: CivicConfig) -> Self {
+        Self(Arc::new(CivicVerifier::new(config)))
+    }
+
+    pub fn extension(&self) -> Extension<SharedCivicVerifier> {
+        Extension(self.clone())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct WalletClaim {
+    address: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CivicClaims {
+    exp: i64,
+    name: Option<String>,
+    solana: Option<WalletClaim>,
+}
+
+#[derive(Debug)]
+pub enum CivicTokenError {
+    NotReady,
+    Header(jsonwebtoken::errors::Error),
+    UnknownKey,
+    Invalid(jsonwebtoken::errors::Error),
+    MissingClaim(&'static str),
+}
+
+impl std::fmt::Display for CivicTokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CivicTokenError::NotReady => write!(f, "Civic signing keys are not loaded yet"),
+            CivicTokenError::Header(e) => write!(f, "malformed token header: {e}"),
+            CivicTokenError::UnknownKey => write!(f, "token was not signed by a known Civic key"),
+            CivicTokenError::Invalid(e) => write!(f, "token rejected: {e}"),
+            CivicTokenError::MissingClaim(claim) => write!(f, "token has no {claim} claim"),
+        }
+    }
 }
 
-static AUTH_STATE: LazyLock<Arc<RwLock<AuthState>>> =
-    LazyLock::new(|| Arc::new(RwLock::new(AuthState::default())));
+impl CivicVerifier {
+    fn new(config: CivicConfig) -> Self {
+        Self {
+            config,
+            jwks: RwLock::new(None),
+            refreshed_at: Mutex::new(None),
+        }
+    }
+
+    /// Fetches the keys once. `load_civic_jwks` retries a failed load.
+    fn load(&self) -> Result<(), String> {
+        let jwks = self.config.load_jwks()?;
+        self.set_jwks(jwks);
+        Ok(())
+    }
+
+    /// Refetches the keys once, unless that already happened within
+    /// `JWKS_REFRESH_INTERVAL`. Returns whether new keys were loaded.
+    fn refresh(&self) -> bool {
+        {
+            // A panic elsewhere can't leave the timestamp half written, so a
+            // poisoned lock is still good to use.
+            let mut refreshed_at = self
+                .refreshed_at
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+
+            if refreshed_at.is_some_and(|at| at.elapsed() < JWKS_REFRESH_INTERVAL) {
+                return false;
+            }
+
+            *refreshed_at = Some(Instant::now());
+        }
+
+        match self.config.load_jwks() {
+            Ok(jwks) => {
+                self.set_jwks(jwks);
+                true
+            }
+            Err(e) => {
+                warn!(
+                    "Could not refresh Civic JWKS from {}: {}",
+                    self.config.jwks, e
+                );
+                false
+            }
+        }
+    }
+
+    fn set_jwks(&self, jwks: JwkSet) {
+        *self.jwks.write().unwrap_or_else(PoisonError::into_inner) = Some(jwks);
+    }
+
+    fn find_key(&self, kid: Option<&str>) -> Result<Option<Jwk>, CivicTokenError> {
+        let jwks = self.jwks.read().unwrap_or_else(PoisonError::into_inner);
+        let Some(jwks) = jwks.as_ref() else {
+            return Err(CivicTokenError::NotReady);
+        };
+
+        Ok(match kid {
+            Some(kid) => jwks.find(kid).cloned(),
+            None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+            None => None,
+        })
+    }
+
+    /// Checks the token's signature, issuer, audience and expiry, and returns
+    /// the session it vouches for.
+    fn verify(&self, id_token: &str) -> Result<Session, CivicTokenError> {
+        let header = decode_header(id_token).map_err(CivicTokenError::Header)?;
+
+        // Only asymmetric algorithms, a public JWKS must never verify an HMAC token.
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(CivicTokenError::UnknownKey);
+        }
+
+        let kid = header.kid.as_deref();
+        let jwk = match self.find_key(kid)? {
+            Some(jwk) => jwk,
+            None if self.refresh() => self.find_key(kid)?.ok_or(CivicTokenError::UnknownKey)?,
+            None => return Err(CivicTokenError::UnknownKey),
+        };
+        let key = DecodingKey::from_jwk(&jwk).map_err(CivicTokenError::Invalid)?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.config.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+
+        let claims = decode::<CivicClaims>(id_token, &key, &validation)
+            .map_err(CivicTokenError::Invalid)?
+            .claims;
 
+        let username = claims
+            .name
+            .filter(|name| !name.is_empty())
+            .ok_or(CivicTokenError::MissingClaim("name"))?;
+        let wallet = claims
+            .solana
+            .map(|solana| solana.address)
+            .filter(|address| !address.is_empty())
+            .ok_or(CivicTokenError::MissingClaim("solana"))?;
+
+        Ok(Session {
+            public_key: wallet,
+            username,
+            expires_at: claims.exp,
+            id_token: id_token.to_string(),
+        })
+    }
+}
+
+/// How long to wait before the next JWKS load after `failures` failed ones.
+fn jwks_retry_delay(failures: u32) -> Duration {
+    JWKS_RETRY_BASE
+        .saturating_mul(1 << failures.saturating_sub(1).min(16))
+        .min(JWKS_RETRY_MAX)
+}
+
+/// Tracks loading the Civic JWKS at startup. Each attempt runs on the async
+/// pool and a failed one is retried once `timer` runs out, so no thread
+/// sleeps through the backoff.
 #[derive(Resource)]
-pub struct AuthStateWatcher {
-    pub timer: Timer,
+pub struct CivicKeysLoader {
+    timer: Timer,
+    failures: u32,
+    in_flight: bool,
+    done: bool,
+    sender: Sender<Result<(), String>>,
+    receiver: Receiver<Result<(), String>>,
 }
 
-impl Default for AuthStateWatcher {
+impl Default for CivicKeysLoader {
     fn default() -> Self {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+
         Self {
-            timer: Timer::from_seconds(0.5, TimerMode::Repeating),
+            timer: Timer::new(Duration::ZERO, TimerMode::Once),
+            failures: 0,
+            in_flight: false,
+            done: false,
+            sender,
+            receiver,
         }
     }
 }
 
+/// Fetches the Civic JWKS in the background, then logs back in from the saved
+/// session. `/login` refuses tokens until the keys are loaded.
+pub fn load_civic_jwks(
+    time: Res<Time>,
+    mut loader: ResMut<CivicKeysLoader>,
+    verifier: Res<SharedCivicVerifier>,
+    bridge: Res<BridgeSender>,
+    config: Res<GameConfig>,
+) {
+    if loader.done {
+        return;
+    }
+
+    if let Ok(result) = loader.receiver.try_recv() {
+        loader.in_flight = false;
+
+        match result {
+            Ok(()) => {
+                loader.done = true;
+
+                if !config.offline {
+                    let verifier = verifier.0.clone();
+                    let bridge = bridge.clone();
+
+                    AsyncComputeTaskPool::get()
+                        .spawn(async move {
+                            restore_session(&verifier, &bridge, Path::new(SESSION_FILE));
+                        })
+                        .detach();
+                }
+                return;
+            }
+            Err(e) => {
+                loader.failures += 1;
+
+                if loader.failures == JWKS_ATTEMPTS {
+                    error!(
+                        "❌ Could not load Civic JWKS from {}: {}",
+                        verifier.0.config.jwks, e
+                    );
+                    loader.done = true;
+                    return;
+                }
+
+                let delay = jwks_retry_delay(loader.failures);
+                warn!(
+                    "Could not load Civic JWKS from {} (attempt {}): {}, retrying in {:?}",
+                    verifier.0.config.jwks, loader.failures, e, delay
+                );
+                loader.timer = Timer::new(delay, TimerMode::Once);
+            }
+        }
+    }
+
+    if loader.in_flight || !loader.timer.tick(time.delta()).finished() {
+        return;
+    }
+
+    loader.in_flight = true;
+    let verifier = verifier.0.clone();
+    let sender = loader.sender.clone();
+
+    AsyncComputeTaskPool::get()
+        .spawn(async move {
+            let _ = sender.send(verifier.load());
+        })
+        .detach();
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AuthPayload {
-    public_key: String,
-    username: String,
+    id_token: String,
 }
 
-pub async fn login(Json(auth_payload): Json<AuthPayload>) -> impl IntoResponse {
-    let mut auth_state = AUTH_STATE.write().unwrap();
-    auth_state.public_key = auth_payload.public_key.clone();
-    auth_state.username = auth_payload.username.clone();
+pub async fn login(
+    Extension(verifier): Extension<SharedCivicVerifier>,
+    Extension(bridge): Extension<BridgeSender>,
+    Json(auth_payload): Json<AuthPayload>,
+) -> impl IntoResponse {
+    let verifier = verifier.0;
+
+    // Verifying may refetch the keys, keep that off the server's threads.
+    let verified = AsyncComputeTaskPool::get()
+        .spawn(async move { verifier.verify(&auth_payload.id_token) })
+        .await;
+
+    let session = match verified {
+        Ok(session) => session,
+        Err(CivicTokenError::NotReady) => {
+            return (StatusCode::SERVICE_UNAVAILABLE, "Civic keys not loaded").into_response();
+        }
+        Err(e) => {
+            error!("❌ Login rejected: {}", e);
+            return (StatusCode::UNAUTHORIZED, "Invalid ID token").into_response();
+        }
+    };
+
+    bridge.send(BridgeMessage::LoggedIn {
+        public_key: session.public_key,
+        username: session.username,
+        expires_at: session.expires_at,
+        id_token: session.id_token,
+    });
 
     (StatusCode::OK, "OK").into_response()
 }
 
-pub fn poll_auth_state(
-    time: Res<Time>,
-    mut watcher: ResMut<AuthStateWatcher>,
+/// Logs back in from the saved session, if its token still passes every check
+/// a fresh login would. A session that doesn't is deleted.
+fn restore_session(verifier: &CivicVerifier, bridge: &BridgeSender, path: &Path) {
+    let Some(saved) = Session::load(path) else {
+        return;
+    };
+
+    match verifier.verify(&saved.id_token) {
+        Ok(session) => bridge.send(BridgeMessage::LoggedIn {
+            public_key: session.public_key,
+            username: session.username,
+            expires_at: session.expires_at,
+            id_token: session.id_token,
+        }),
+        Err(e) => {
+            warn!("Discarding saved session: {}", e);
+            Session::clear(path);
+        }
+    }
+}
+
+/// Applies a verified login. A login for a different wallet switches accounts,
+/// unless a match is in progress.
+pub fn apply_logged_in(
+    mut logged_in: EventReader<LoggedIn>,
     mut profile_data: ResMut<ProfileData>,
+    mut stats_data: ResMut<StatsData>,
+    mut duel_profile: ResMut<DuelProfile>,
+    logged_in_state: Res<State<LoggedInState>>,
     mut next_logged_in_sate: ResMut<NextState<LoggedInState>>,
+    config: Res<GameConfig>,
 ) {
-    if profile_data.logged_in {
-        return;
+    for event in logged_in.read() {
+        if config.offline {
+            warn!("Ignoring login while offline");
+            continue;
+        }
+
+        let is_switching = profile_data.logged_in && profile_data.public_key != event.public_key;
+
+        if is_switching && *logged_in_state.get() == LoggedInState::InGame {
+            warn!("Ignoring account switch while a match is in progress");
+            continue;
+        }
+
+        if is_switching {
+            stats_data.reset();
+            duel_profile.reset();
+        }
+
+        let session = Session {
+            public_key: event.public_key.clone(),
+            username: event.username.clone(),
+            expires_at: event.expires_at,
+            id_token: event.id_token.clone(),
+        };
+
+        if let Err(e) = session.save(Path::new(SESSION_FILE)) {
+            error!("❌ Could not save session: {:?}", e);
+        }
+
+        // A guest logging in gets the full menu, so it counts as a fresh login.
+        let was_logged_in = profile_data.is_online();
+
+        profile_data.logged_in = true;
+        profile_data.guest = false;
+        profile_data.public_key = session.public_key;
+        profile_data.username = session.username;
+        profile_data.expires_at = session.expires_at;
+
+        if !was_logged_in {
+            next_logged_in_sate.set(LoggedInState::LoggedIn);
+        }
     }
+}
 
-    if !watcher.timer.tick(time.delta()).finished() {
+pub fn apply_logged_out(
+    mut logged_out: EventReader<LoggedOut>,
+    mut profile_data: ResMut<ProfileData>,
+    mut stats_data: ResMut<StatsData>,
+    mut duel_profile: ResMut<DuelProfile>,
+    mut next_logged_in_sate: ResMut<NextState<LoggedInState>>,
+) {
+    if logged_out.read().count() == 0 {
         return;
     }
 
-    let auth_state = AUTH_STATE.read().unwrap();
+    if !profile_data.guest {
+        Session::clear(Path::new(SESSION_FILE));
+    }
 
-    if !auth_state.public_key.is_empty() && !auth_state.username.is_empty() {
-        profile_data.logged_in = true;
-        profile_data.public_key = auth_state.public_key.clone();
-        profile_data.username = auth_state.username.clone();
-
-        next_logged_in_sate.set(LoggedInState::LoggedIn);
-    } else {
-        profile_data.logged_in = false;
-        profile_data.public_key.clear();
-        profile_data.username.clear();
+    profile_data.reset();
+    stats_data.reset();
+    duel_profile.reset();
+
+    next_logged_in_sate.set(LoggedInState::NotLoggedIn);
+}
+
+/// Logs out once the Civic token behind the session expires. Only runs from the
+/// main menu so a match is never cut short.
+pub fn expire_session(profile_data: Res<ProfileData>, mut logged_out: EventWriter<LoggedOut>) {
+    if profile_data.is_online() && profile_data.expires_at <= unix_now() {
+        logged_out.write(LoggedOut);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+    use ed25519_dalek::SigningKey;
+    use jsonwebtoken::{EncodingKey, Header, encode};
+    use serde_json::json;
+
+    use super::*;
+
+    const ISSUER: &str = "https://issuer.test/";
+    const CLIENT_ID: &str = "test-client";
+    const WALLET: &str = "11111111111111111111111111111111";
+
+    struct TestKey {
+        kid: &'static str,
+        signing_key: SigningKey,
+    }
+
+    impl TestKey {
+        fn new(kid: &'static str, seed: u8) -> Self {
+            Self {
+                kid,
+                signing_key: SigningKey::from_bytes(&[seed; 32]),
+            }
+        }
+
+        fn jwk(&self) -> serde_json::Value {
+            json!({
+                "kty": "OKP",
+                "crv": "Ed25519",
+                "alg": "EdDSA",
+                "kid": self.kid,
+                "x": URL_SAFE_NO_PAD.encode(self.signing_key.verifying_key().as_bytes()),
+            })
+        }
+
+        fn sign(&self, claims: serde_json::Value) -> String {
+            // PKCS#8 v1 wrapping of the raw Ed25519 seed.
+            let mut der = vec![
+                0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22,
+                0x04, 0x20,
+            ];
+            der.extend_from_slice(self.signing_key.as_bytes());
+
+            let mut header = Header::new(Algorithm::EdDSA);
+            header.kid = Some(self.kid.to_string());
+
+            encode(&header, &claims, &EncodingKey::from_ed_der(&der)).unwrap()
+        }
+    }
+
+    fn claims(audience: &str, exp: i64) -> serde_json::Value {
+        json!({
+            "iss": ISSUER,
+            "aud": audience,
+            "exp": exp,
+            "name": "Gunslinger",
+            "solana": { "address": WALLET },
+        })
+    }
+
+    /// A verifier reading its keys from a JWKS file that starts out with `keys`.
+    fn verifier(keys: &[&TestKey]) -> (CivicVerifier, PathBuf) {
+        let path = std::env::temp_dir().join(format!("jwks-{}.json", uuid::Uuid::new_v4()));
+        write_jwks(&path, keys);
+
+        let verifier = CivicVerifier::new(CivicConfig {
+            jwks: path.to_string_lossy().into_owned(),
+            issuer: ISSUER.to_string(),
+            client_id: CLIENT_ID.to_string(),
+        });
+        verifier.load().unwrap();
+
+        (verifier, path)
+    }
+
+    fn write_jwks(path: &Path, keys: &[&TestKey]) {
+        let jwks = json!({ "keys": keys.iter().map(|key| key.jwk()).collect::<Vec<_>>() });
+        std::fs::write(path, jwks.to_string()).unwrap();
+    }
+
+    #[test]
+    fn accepts_a_valid_token() {
+        let key = TestKey::new("key-1", 1);
+        let (verifier, path) = verifier(&[&key]);
+
+        let session = verifier
+            .verify(&key.sign(claims(CLIENT_ID, unix_now() + 3600)))
+            .unwrap();
+        let _ = std::fs::remove_file(path);
+
+        assert_eq!(session.username, "Gunslinger");
+        assert_eq!(session.public_key, WALLET);
+    }
+
+    #[test]
+    fn rejects_an_expired_token() {
+        let key = TestKey::new("key-1", 1);
+        let (verifier, path) = verifier(&[&key]);
+
+        let verified = verifier.verify(&key.sign(claims(CLIENT_ID, unix_now() - 3600)));
+        let _ = std::fs::remove_file(path);
+
+        assert!(matches!(verified, Err(CivicTokenError::Invalid(_))));
+    }
+
+    #[test]
+    fn rejects_a_token_for_another_audience() {
+        let key = TestKey::new("key-1", 1);
+        let (verifier, path) = verifier(&[&key]);
+
+        let verified = verifier.verify(&key.sign(claims("another-client", unix_now() + 3600)));
+        let _ = std::fs::remove_file(path);
+
+        assert!(matches!(verified, Err(CivicTokenError::Invalid(_))));
+    }
+
+    #[test]
+    fn rejects_an_unknown_key() {
+        let key = TestKey::new("key-1", 1);
+        let stranger = TestKey::new("key-2", 2);
+        let (verifier, path) = verifier(&[&key]);
+
+        let verified = verifier.verify(&stranger.sign(claims(CLIENT_ID, unix_now() + 3600)));
+        let _ = std::fs::remove_file(path);
+
+        assert!(matches!(verified, Err(CivicTokenError::UnknownKey)));
+    }
+
+    #[test]
+    fn rejects_a_key_id_with_someone_elses_signature() {
+        let key = TestKey::new("key-1", 1);
+        let forger = TestKey::new("key-1", 2);
+        let (verifier, path) = verifier(&[&key]);
+
+        let verified = verifier.verify(&forger.sign(claims(CLIENT_ID, unix_now() + 3600)));
+        let _ = std::fs::remove_file(path);
+
+        assert!(matches!(verified, Err(CivicTokenError::Invalid(_))));
+    }
+
+    #[test]
+    fn refetches_the_keys_once_for_an_unknown_key() {
+        let old_key = TestKey::new("key-1", 1);
+        let new_key = TestKey::new("key-2", 2);
+        let newer_key = TestKey::new("key-3", 3);
+        let (verifier, path) = verifier(&[&old_key]);
+
+        // Civic rotated its keys after we loaded them.
+        write_jwks(&path, &[&old_key, &new_key]);
+        let rotated = verifier.verify(&new_key.sign(claims(CLIENT_ID, unix_now() + 3600)));
+
+        // A second unknown key right after doesn't trigger another fetch.
+        write_jwks(&path, &[&old_key, &new_key, &newer_key]);
+        let too_soon = verifier.verify(&newer_key.sign(claims(CLIENT_ID, unix_now() + 3600)));
+        let _ = std::fs::remove_file(path);
+
+        assert!(rotated.is_ok());
+        assert!(matches!(too_soon, Err(CivicTokenError::UnknownKey)));
+    }
+
+    #[test]
+    fn keeps_verifying_after_a_panic_poisoned_the_keys() {
+        let key = TestKey::new("key-1", 1);
+        let (verifier, path) = verifier(&[&key]);
+        let verifier = Arc::new(verifier);
+
+        let poisoner = verifier.clone();
+        let _ = std::thread::spawn(move || {
+            let _jwks = poisoner.jwks.write().unwrap();
+            let _refreshed_at = poisoner.refreshed_at.lock().unwrap();
+            panic!("poison the locks");
+        })
+        .join();
+
+        let verified = verifier.verify(&key.sign(claims(CLIENT_ID, unix_now() + 3600)));
+        let _ = std::fs::remove_file(path);
+
+        assert!(verifier.jwks.is_poisoned());
+        assert!(verified.is_ok());
+    }
+
+    fn saved_session(path: &Path, id_token: String) {
+        let session = Session {
+            public_key: WALLET.to_string(),
+            username: "Gunslinger".to_string(),
+            expires_at: unix_now() + 3600,
+            id_token,
+        };
+        session.save(path).unwrap();
+    }
+
+    /// Restores the session at `path` and returns the logins it produced.
+    fn restore(verifier: &CivicVerifier, path: &Path) -> Vec<LoggedIn> {
+        let (sender, receiver) = crate::bridge::event_bridge();
+        restore_session(verifier, &sender, path);
+
+        let mut app = App::new();
+        app.insert_resource(receiver)
+            .add_event::<LoggedIn>()
+            .add_event::<LoggedOut>()
+            .add_event::<crate::bridge::StatsUpdated>()
+            .add_event::<crate::bridge::StatsFetched>()
+            .add_event::<crate::bridge::OpponentStatsLoaded>()
+            .add_event::<crate::bridge::WalletSigned>()
+            .add_event::<crate::bridge::ProfileCreated>()
+            .add_systems(Update, crate::bridge::drain_event_bridge);
+        app.update();
+
+        let events = app.world().resource::<Events<LoggedIn>>();
+        events.get_cursor().read(events).cloned().collect()
+    }
+
+    #[test]
+    fn restores_a_session_whose_token_verifies() {
+        let key = TestKey::new("key-1", 1);
+        let (verifier, jwks_path) = verifier(&[&key]);
+        let path = std::env::temp_dir().join(format!("session-{}.json", uuid::Uuid::new_v4()));
+        saved_session(&path, key.sign(claims(CLIENT_ID, unix_now() + 3600)));
+
+        let logins = restore(&verifier, &path);
+        let kept = path.exists();
+        let _ = std::fs::remove_file(jwks_path);
+        let _ = std::fs::remove_file(path);
+
+        assert_eq!(logins.len(), 1);
+        assert_eq!(logins[0].public_key, WALLET);
+        assert!(kept);
+    }
+
+    #[test]
+    fn discards_a_session_whose_token_fails() {
+        let key = TestKey::new("key-1", 1);
+        let forger = TestKey::new("key-1", 2);
+        let (verifier, jwks_path) = verifier(&[&key]);
+        let path = std::env::temp_dir().join(format!("session-{}.json", uuid::Uuid::new_v4()));
+        saved_session(&path, forger.sign(claims(CLIENT_ID, unix_now() + 3600)));
+
+        let logins = restore(&verifier, &path);
+        let kept = path.exists();
+        let _ = std::fs::remove_file(jwks_path);
+
+        assert!(logins.is_empty());
+        assert!(!kept);
+    }
+
+    #[test]
+    fn discards_a_session_saved_without_a_token() {
+        let key = TestKey::new("key-1", 1);
+        let (verifier, jwks_path) = verifier(&[&key]);
+        let path = std::env::temp_dir().join(format!("session-{}.json", uuid::Uuid::new_v4()));
+        saved_session(&path, String::new());
+
+        let logins = restore(&verifier, &path);
+        let kept = path.exists();
+        let _ = std::fs::remove_file(jwks_path);
+
+        assert!(logins.is_empty());
+        assert!(!kept);
+    }
+
+    #[test]
+    fn jwks_retries_back_off_up_to_the_maximum() {
+        let delays: Vec<_> = (1..=7).map(jwks_retry_delay).collect();
+
+        assert_eq!(
+            delays,
+            [1, 2, 4, 8, 16, 30, 30].map(Duration::from_secs).to_vec()
+        );
+    }
+
+    #[test]
+    fn refuses_tokens_until_the_keys_are_loaded() {
+        let key = TestKey::new("key-1", 1);
+        let verifier = CivicVerifier::new(CivicConfig {
+            jwks: "/nonexistent/jwks.json".to_string(),
+            issuer: ISSUER.to_string(),
+            client_id: CLIENT_ID.to_string(),
+        });
+
+        let verified = verifier.verify(&key.sign(claims(CLIENT_ID, unix_now() + 3600)));
+
+        assert!(matches!(verified, Err(CivicTokenError::NotReady)));
     }
 }
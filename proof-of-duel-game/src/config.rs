@@ -0,0 +1,340 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    civic_auth::{CIVIC_CLIENT_ID, CIVIC_ISSUER, CIVIC_JWKS, CivicConfig},
+    solana_rpc::{PROGRAM_ID, SolanaRpc},
+};
+
+pub const CONFIG_FILE: &str = "config.json";
+
+/// Where the frontend lives and how it reaches back into the game. Read from
+/// `config.json` (or the file at `GAME_CONFIG`), then overridden field by field
+/// by `API_BASE_URL`, `CORS_ORIGINS` (comma separated) and `CALLBACK_PORT`.
+///
+/// Setting `solana_rpc_url` (or `SOLANA_RPC_URL`) makes the game read stats
+/// straight from the chain instead of through the frontend.
+///
+/// The server signs its match reports with `match_api_secret` (or
+/// `MATCH_API_SECRET`), which the frontend checks before touching the chain.
+///
+/// With `offline` (or `OFFLINE=true`) the game only offers guest play and
+/// ignores logins, for builds shipped without the frontend.
+///
+/// `civic_jwks`, `civic_issuer` and `civic_client_id` (or `CIVIC_JWKS`,
+/// `CIVIC_ISSUER` and `CIVIC_CLIENT_ID`) point logins at a stand-in issuer.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    pub api_base_url: String,
+    pub cors_origins: Vec<String>,
+    pub callback_port: u16,
+    pub solana_rpc_url: Option<String>,
+    pub solana_ws_url: Option<String>,
+    pub program_id: String,
+    pub match_api_secret: Option<String>,
+    pub offline: bool,
+    pub civic_jwks: String,
+    pub civic_issuer: String,
+    pub civic_client_id: String,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            api_base_url: "http://localhost:3000".to_string(),
+            cors_origins: vec![
+                "http://localhost:3000".to_string(),
+                "http://127.0.0.1:3000".to_string(),
+            ],
+            callback_port: 8080,
+            solana_rpc_url: None,
+            solana_ws_url: None,
+            program_id: PROGRAM_ID.to_string(),
+            match_api_secret: None,
+            offline: false,
+            civic_jwks: CIVIC_JWKS.to_string(),
+            civic_issuer: CIVIC_ISSUER.to_string(),
+            civic_client_id: CIVIC_CLIENT_ID.to_string(),
+        }
+    }
+}
+
+impl GameConfig {
+    /// Reads the config file and the environment. Returns the problems found
+    /// along the way instead of logging them, since the game loads its config
+    /// before logging is set up.
+    pub fn load() -> (Self, Vec<String>) {
+        dotenvy::dotenv().ok();
+
+        let path = dotenvy::var("GAME_CONFIG").unwrap_or_else(|_| CONFIG_FILE.to_string());
+        let json = std::fs::read_to_string(&path).ok();
+
+        Self::from_sources(&path, json.as_deref(), |name| dotenvy::var(name).ok())
+    }
+
+    /// Builds the config from the file at `path` (if it could be read) and
+    /// the variables `var` finds, which win over the file.
+    fn from_sources(
+        path: &str,
+        json: Option<&str>,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> (Self, Vec<String>) {
+        let mut problems = Vec::new();
+
+        let mut config = match json {
+            Some(json) => serde_json::from_str(json).unwrap_or_else(|e| {
+                problems.push(format!("{} is not a valid config file: {}", path, e));
+                Self::default()
+            }),
+            None => Self::default(),
+        };
+
+        if let Some(api_base_url) = var("API_BASE_URL") {
+            config.api_base_url = api_base_url;
+        }
+
+        if let Some(cors_origins) = var("CORS_ORIGINS") {
+            config.cors_origins = cors_origins
+                .split(',')
+                .map(|origin| origin.trim().to_string())
+                .filter(|origin| !origin.is_empty())
+                .collect();
+        }
+
+        if let Some(callback_port) = var("CALLBACK_PORT") {
+            match callback_port.parse() {
+                Ok(port) => config.callback_port = port,
+                Err(_) => problems.push(format!(
+                    "CALLBACK_PORT {} is not a valid port",
+                    callback_port
+                )),
+            }
+        }
+
+        if let Some(solana_rpc_url) = var("SOLANA_RPC_URL") {
+            config.solana_rpc_url = Some(solana_rpc_url);
+        }
+
+        if let Some(solana_ws_url) = var("SOLANA_WS_URL") {
+            config.solana_ws_url = Some(solana_ws_url);
+        }
+
+        if let Some(program_id) = var("PROGRAM_ID") {
+            config.program_id = program_id;
+        }
+
+        if let Some(match_api_secret) = var("MATCH_API_SECRET") {
+            config.match_api_secret = Some(match_api_secret);
+        }
+
+        if let Some(offline) = var("OFFLINE") {
+            match offline.parse() {
+                Ok(offline) => config.offline = offline,
+                Err(_) => problems.push(format!("OFFLINE {} is not true or false", offline)),
+            }
+        }
+
+        if let Some(civic_jwks) = var("CIVIC_JWKS") {
+            config.civic_jwks = civic_jwks;
+        }
+
+        if let Some(civic_issuer) = var("CIVIC_ISSUER") {
+            config.civic_issuer = civic_issuer;
+        }
+
+        if let Some(civic_client_id) = var("CIVIC_CLIENT_ID") {
+            config.civic_client_id = civic_client_id;
+        }
+
+        if config.solana_ws_url.is_none()
+            && let Some(rpc_url) = &config.solana_rpc_url
+            && let Err(e) = websocket_url(rpc_url)
+        {
+            problems.push(format!("{e}, set SOLANA_WS_URL"));
+        }
+
+        (config, problems)
+    }
+
+    /// The RPC to read `Player` accounts from, if direct reads are enabled.
+    /// Without an explicit websocket URL it follows the Solana CLI convention
+    /// of the same host with the port one above the RPC port, and direct
+    /// reads stay off when that port doesn't exist.
+    pub fn solana_rpc(&self) -> Option<SolanaRpc> {
+        let rpc_url = self.solana_rpc_url.clone()?;
+        let ws_url = match &self.solana_ws_url {
+            Some(ws_url) => ws_url.clone(),
+            None => websocket_url(&rpc_url).ok()?,
+        };
+
+        Some(SolanaRpc {
+            rpc_url,
+            ws_url,
+            program_id: self.program_id.clone(),
+        })
+    }
+
+    /// Where logins get their signing keys and which tokens they accept.
+    pub fn civic(&self) -> CivicConfig {
+        CivicConfig {
+            jwks: self.civic_jwks.clone(),
+            issuer: self.civic_issuer.clone(),
+            client_id: self.civic_client_id.clone(),
+        }
+    }
+
+    /// Joins `path` (starting with `/`) onto the API base URL.
+    pub fn api_url(&self, path: &str) -> String {
+        format!("{}{}", self.api_base_url.trim_end_matches('/'), path)
+    }
+
+    /// A POST to one of the match routes, carrying the shared secret.
+    pub fn match_api_post(&self, path: &str) -> ureq::RequestBuilder<ureq::typestate::WithBody> {
+        let request = ureq::post(self.api_url(path));
+
+        match &self.match_api_secret {
+            Some(secret) => request.header("Authorization", format!("Bearer {secret}")),
+            None => request,
+        }
+    }
+}
+
+fn websocket_url(rpc_url: &str) -> Result<String, String> {
+    let ws_url = if let Some(rest) = rpc_url.strip_prefix("https://") {
+        format!("wss://{rest}")
+    } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+        format!("ws://{rest}")
+    } else {
+        rpc_url.to_string()
+    };
+
+    let (scheme, rest) = ws_url.split_once("://").unwrap_or(("ws", &ws_url));
+    let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+
+    match authority
+        .rsplit_once(':')
+        .and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?)))
+    {
+        Some((host, port)) => match port.checked_add(1) {
+            Some(ws_port) => Ok(format!("{scheme}://{host}:{ws_port}/{path}")),
+            None => Err(format!(
+                "SOLANA_RPC_URL {rpc_url} uses port {port}, which has no websocket port above it"
+            )),
+        },
+        None => Ok(ws_url),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn load(json: Option<&str>, vars: &[(&str, &str)]) -> (GameConfig, Vec<String>) {
+        let vars: HashMap<_, _> = vars.iter().copied().collect();
+
+        GameConfig::from_sources(CONFIG_FILE, json, |name| {
+            vars.get(name).map(|value| value.to_string())
+        })
+    }
+
+    #[test]
+    fn defaults_without_a_file_or_variables() {
+        let (config, problems) = load(None, &[]);
+
+        assert_eq!(config.api_base_url, "http://localhost:3000");
+        assert_eq!(config.callback_port, 8080);
+        assert!(!config.offline);
+        assert!(problems.is_empty());
+    }
+
+    #[test]
+    fn the_file_overrides_the_defaults() {
+        let (config, problems) = load(
+            Some(r#"{ "api_base_url": "https://duel.example", "callback_port": 9000 }"#),
+            &[],
+        );
+
+        assert_eq!(config.api_base_url, "https://duel.example");
+        assert_eq!(config.callback_port, 9000);
+        assert_eq!(config.program_id, PROGRAM_ID);
+        assert!(problems.is_empty());
+    }
+
+    #[test]
+    fn variables_override_the_file() {
+        let (config, problems) = load(
+            Some(r#"{ "api_base_url": "https://duel.example", "offline": false }"#),
+            &[
+                ("API_BASE_URL", "http://localhost:4000"),
+                ("CORS_ORIGINS", "https://a.example, ,https://b.example"),
+                ("OFFLINE", "true"),
+                ("CIVIC_ISSUER", "http://localhost:4000/"),
+            ],
+        );
+
+        assert_eq!(config.api_base_url, "http://localhost:4000");
+        assert_eq!(
+            config.cors_origins,
+            ["https://a.example", "https://b.example"]
+        );
+        assert!(config.offline);
+        assert_eq!(config.civic().issuer, "http://localhost:4000/");
+        assert!(problems.is_empty());
+    }
+
+    #[test]
+    fn invalid_values_keep_the_previous_ones_and_are_reported() {
+        let (config, problems) = load(
+            Some(r#"{ "callback_port": 9000 }"#),
+            &[("CALLBACK_PORT", "http"), ("OFFLINE", "yes")],
+        );
+
+        assert_eq!(config.callback_port, 9000);
+        assert!(!config.offline);
+        assert_eq!(problems.len(), 2);
+    }
+
+    #[test]
+    fn an_invalid_file_falls_back_to_the_defaults() {
+        let (config, problems) = load(Some("{ not json"), &[("CALLBACK_PORT", "9001")]);
+
+        assert_eq!(config.api_base_url, "http://localhost:3000");
+        assert_eq!(config.callback_port, 9001);
+        assert_eq!(problems.len(), 1);
+    }
+
+    #[test]
+    fn the_websocket_port_is_one_above_the_rpc_port() {
+        assert_eq!(
+            websocket_url("http://127.0.0.1:8899").unwrap(),
+            "ws://127.0.0.1:8900/"
+        );
+        assert_eq!(
+            websocket_url("https://rpc.example/v1").unwrap(),
+            "wss://rpc.example/v1"
+        );
+        assert!(websocket_url("http://127.0.0.1:65535").is_err());
+    }
+
+    #[test]
+    fn an_rpc_on_the_last_port_needs_an_explicit_websocket_url() {
+        let (config, problems) = load(None, &[("SOLANA_RPC_URL", "http://127.0.0.1:65535")]);
+
+        assert!(config.solana_rpc().is_none());
+        assert_eq!(problems.len(), 1);
+
+        let (config, problems) = load(
+            None,
+            &[
+                ("SOLANA_RPC_URL", "http://127.0.0.1:65535"),
+                ("SOLANA_WS_URL", "ws://127.0.0.1:9000"),
+            ],
+        );
+
+        assert_eq!(config.solana_rpc().unwrap().ws_url, "ws://127.0.0.1:9000");
+        assert!(problems.is_empty());
+    }
+}
@@ -6,9 +6,16 @@ use bevy_quinnet::client::{
 
 use crate::{
     ClientChannel, GameState, LOCAL_BIND_IP, SERVER_PORT, ServerMessage, get_ip,
-    player::{PlayerHertsStatus, PlayerHit, PlayerSelection, PlayersCounting},
-    shooting::ShootingEvent,
-    ui::{game_over::WhoIsWinner, join_game::MatchNotFoundError, play_now_ui::GameStartTimer},
+    player::{MatchPlayers, PlayerHertsStatus, PlayerHit, PlayerSelection, PlayersCounting},
+    replay::ReplayRecorder,
+    shooting::{ShootingEvent, ShootingStates},
+    ui::{
+        game_over::{MatchCommitment, WhoIsWinner},
+        join_game::MatchNotFoundError,
+        main_menu::MainMenuState,
+        play_now_ui::GameStartTimer,
+    },
+    wallet::WalletProof,
 };
 
 #[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
@@ -47,8 +54,62 @@ pub fn open_connection(mut client: ResMut<QuinnetClient>, mut is_connected: ResM
     is_connected.0 = true;
 }
 
-pub fn handle_server_messages(
+/// A `ServerMessage` with the channel it arrived on, from the server or from
+/// a replay being played back.
+#[derive(Event)]
+pub struct ServerMessageReceived {
+    pub channel: u8,
+    pub message: ServerMessage,
+}
+
+/// Hands everything the server sent on to `handle_server_messages`, recording
+/// the match for its replay on the way.
+pub fn receive_server_messages(
     mut client: ResMut<QuinnetClient>,
+    mut replay_recorder: ResMut<ReplayRecorder>,
+    mut server_message: EventWriter<ServerMessageReceived>,
+) {
+    while let Some((channel, message)) = client
+        .connection_mut()
+        .try_receive_message::<ServerMessage>()
+    {
+        if let ServerMessage::MatchCreated {
+            match_id,
+            player_number,
+        }
+        | ServerMessage::JoinedMatch {
+            match_id,
+            player_number,
+        } = message
+            && channel == 0
+        {
+            replay_recorder.start(match_id, player_number);
+        }
+
+        if let ServerMessage::SpectatingMatch { match_id, .. } = message
+            && channel == 0
+        {
+            replay_recorder.start(match_id, 0);
+        }
+
+        // A rematch is recorded as a match of its own, from the same seat.
+        if let ServerMessage::RematchStarting { match_id, rematch } = message
+            && channel == 0
+        {
+            replay_recorder.start_rematch(match_id, rematch);
+        }
+
+        if let Some(replay) = &mut replay_recorder.0 {
+            replay.record_server(channel, &message);
+        }
+
+        server_message.write(ServerMessageReceived { channel, message });
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_server_messages(
+    mut server_message: EventReader<ServerMessageReceived>,
     mut player_selection: ResMut<PlayerSelection>,
     mut players_counting: ResMut<PlayersCounting>,
     mut game_start_timer: ResMut<GameStartTimer>,
@@ -58,12 +119,17 @@ pub fn handle_server_messages(
     mut next_game_state: ResMut<NextState<GameState>>,
     mut who_is_winner: ResMut<WhoIsWinner>,
     mut match_not_found_error_event: EventWriter<MatchNotFoundError>,
+    mut match_commitment: ResMut<MatchCommitment>,
+    mut wallet_proof: ResMut<WalletProof>,
+    mut match_players: ResMut<MatchPlayers>,
+    mut shooting_states: ResMut<ShootingStates>,
+    game_state: Res<State<GameState>>,
+    mut next_main_menu_state: ResMut<NextState<MainMenuState>>,
 ) {
-    while let Some((channel, message)) = client
-        .connection_mut()
-        .try_receive_message::<ServerMessage>()
-    {
-        match message {
+    for ServerMessageReceived { channel, message } in server_message.read() {
+        let channel = *channel;
+
+        match message.clone() {
             ServerMessage::MatchCreated {
                 player_number,
                 match_id,
@@ -92,12 +158,20 @@ pub fn handle_server_messages(
                     match_not_found_error_event.write(MatchNotFoundError::new(error_message));
                 }
             }
-            ServerMessage::IsGameReadyToStart { match_id, is_ready } => {
+            ServerMessage::IsGameReadyToStart {
+                match_id,
+                is_ready,
+                players,
+                settings,
+                key_seed,
+            } => {
                 if is_ready
                     && !game_start_timer.active
                     && channel == 0
                     && match_id == player_selection.1
                 {
+                    match_players.0 = players;
+                    shooting_states.configure(settings, key_seed);
                     game_start_timer.active = true;
                 }
             }
@@ -131,6 +205,74 @@ pub fn handle_server_messages(
                     next_game_state.set(GameState::GameOver);
                 }
             }
+            ServerMessage::MatchCommitted {
+                match_id,
+                game_id,
+                transcript_hash,
+            } => {
+                if channel == 3 && match_id == player_selection.1 {
+                    match_commitment.game_id = Some(game_id);
+                    match_commitment.transcript_hash = Some(transcript_hash);
+                }
+            }
+            ServerMessage::ResultSyncPending { match_id } => {
+                if channel == 3 && match_id == player_selection.1 {
+                    match_commitment.sync_pending = true;
+                }
+            }
+            ServerMessage::ResultCommitted { match_id } => {
+                if channel == 3 && match_id == player_selection.1 {
+                    match_commitment.sync_pending = false;
+                    match_commitment.in_dispute_window = true;
+                }
+            }
+            ServerMessage::ResultSynced { match_id } => {
+                if channel == 3 && match_id == player_selection.1 {
+                    match_commitment.sync_pending = false;
+                    match_commitment.in_dispute_window = false;
+                }
+            }
+            ServerMessage::WalletChallenge { nonce } => {
+                if channel == 0 {
+                    wallet_proof.begin(nonce);
+                }
+            }
+            ServerMessage::SpectatingMatch {
+                match_id,
+                players,
+                player_1_hearts,
+                player_2_hearts,
+                in_progress,
+                ..
+            } => {
+                if channel == 0 && match_id == player_selection.1 {
+                    players_counting.0 = players.len();
+                    match_players.0 = players;
+
+                    player_hearts_status.player_1_hearts = player_1_hearts;
+                    player_hearts_status.player_2_hearts = player_2_hearts;
+
+                    // A match that is already under way is joined straight
+                    // away, otherwise the countdown starts it like for the players.
+                    if in_progress && *game_state.get() != GameState::InGame {
+                        next_main_menu_state.set(MainMenuState::None);
+                        next_game_state.set(GameState::InGame);
+                    }
+                }
+            }
+            // Handled by `series::handle_series_messages`.
+            ServerMessage::RoundOver { .. } => {}
+            // Handled by `game_over::handle_rematch_messages`.
+            ServerMessage::RematchRequested { .. }
+            | ServerMessage::RematchDeclined { .. }
+            | ServerMessage::OpponentLeft { .. }
+            | ServerMessage::RematchStarting { .. } => {}
+            // Handled by `tournament_ui::handle_tournament_messages`.
+            ServerMessage::TournamentUpdated { .. }
+            | ServerMessage::TournamentRegistered { .. }
+            | ServerMessage::TournamentRejected { .. } => {}
+            // Handled by `lobby::handle_lobby_messages`.
+            ServerMessage::OpenMatches { .. } => {}
         }
     }
 }
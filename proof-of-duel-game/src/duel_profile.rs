@@ -0,0 +1,111 @@
+use axum::{Extension, Json, http::StatusCode, response::IntoResponse};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bridge::{BridgeMessage, BridgeSender, ProfileCreated},
+    config::GameConfig,
+    stats::StatsClient,
+    ui::profile::ProfileData,
+};
+
+/// Whether the logged-in wallet has its on-chain `Player` account yet. Stats
+/// can't be recorded for a wallet without one.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum DuelProfileStatus {
+    #[default]
+    Unknown,
+    Missing,
+    /// Waiting on the browser to submit `initialize_player`.
+    Creating,
+    /// Created from this game, as opposed to found already in place.
+    Created,
+    Ready,
+    Failed(String),
+}
+
+#[derive(Resource, Default)]
+pub struct DuelProfile {
+    pub status: DuelProfileStatus,
+}
+
+impl DuelProfile {
+    pub fn can_create(&self) -> bool {
+        matches!(
+            self.status,
+            DuelProfileStatus::Missing | DuelProfileStatus::Failed(_)
+        )
+    }
+
+    pub fn reset(&mut self) {
+        self.status = DuelProfileStatus::Unknown;
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProfileCreatedPayload {
+    public_key: String,
+    signature: Option<String>,
+    error: Option<String>,
+}
+
+pub async fn profile_created(
+    Extension(bridge): Extension<BridgeSender>,
+    Json(payload): Json<ProfileCreatedPayload>,
+) -> impl IntoResponse {
+    let result = match (payload.signature, payload.error) {
+        (Some(signature), _) => Ok(signature),
+        (None, error) => Err(error.unwrap_or_else(|| "Unknown error".to_string())),
+    };
+
+    bridge.send(BridgeMessage::ProfileCreated {
+        public_key: payload.public_key,
+        result,
+    });
+
+    (StatusCode::OK, "OK").into_response()
+}
+
+/// Opens the frontend page that builds and submits `initialize_player` with
+/// the player's wallet.
+pub fn create_duel_profile(duel_profile: &mut DuelProfile, config: &GameConfig) {
+    if !duel_profile.can_create() {
+        return;
+    }
+
+    match webbrowser::open(&config.api_url("/create-profile")) {
+        Ok(_) => {
+            info!("Opening browser to create the duel profile...");
+            duel_profile.status = DuelProfileStatus::Creating;
+        }
+        Err(e) => error!(
+            "❌ Could not open the browser to create the duel profile: {:?}",
+            e
+        ),
+    }
+}
+
+pub fn apply_profile_created(
+    mut profile_created: EventReader<ProfileCreated>,
+    mut duel_profile: ResMut<DuelProfile>,
+    mut stats_client: ResMut<StatsClient>,
+    profile_data: Res<ProfileData>,
+) {
+    for event in profile_created.read() {
+        if !profile_data.logged_in || event.public_key != profile_data.public_key {
+            continue;
+        }
+
+        match &event.result {
+            Ok(signature) => {
+                info!("✅ Duel profile created: {}", signature);
+                duel_profile.status = DuelProfileStatus::Created;
+                stats_client.refresh_now();
+            }
+            Err(e) => {
+                error!("❌ Duel profile could not be created: {}", e);
+                duel_profile.status = DuelProfileStatus::Failed(e.clone());
+            }
+        }
+    }
+}
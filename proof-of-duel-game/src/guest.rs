@@ -0,0 +1,84 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    player::PlayerSelection,
+    practice::PracticeMatch,
+    replay::ReplayPlayback,
+    stats::StatsData,
+    ui::{game_over::WhoIsWinner, join_game::IsSpectator, profile::ProfileData},
+};
+
+pub const GUEST_STATS_FILE: &str = "guest_stats.json";
+pub const GUEST_USERNAME: &str = "Guest";
+
+/// The guest's win/loss record. It never leaves this machine.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GuestStats {
+    pub win: u32,
+    pub loss: u32,
+}
+
+impl GuestStats {
+    pub fn load(path: &Path) -> Self {
+        fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_vec_pretty(self).expect("GuestStats is serializable");
+        fs::write(path, json)
+    }
+}
+
+/// Plays without a Civic login or a wallet. Matches are anonymous, so the
+/// server never commits them on-chain, and the game makes no HTTP calls.
+pub fn start_guest_session(profile_data: &mut ProfileData, stats_data: &mut StatsData) {
+    let guest_stats = GuestStats::load(Path::new(GUEST_STATS_FILE));
+
+    profile_data.reset();
+    profile_data.logged_in = true;
+    profile_data.guest = true;
+    profile_data.username = GUEST_USERNAME.to_string();
+
+    stats_data.win = guest_stats.win;
+    stats_data.loss = guest_stats.loss;
+}
+
+pub fn record_guest_result(
+    profile_data: Res<ProfileData>,
+    who_is_winner: Res<WhoIsWinner>,
+    player_selection: Res<PlayerSelection>,
+    mut stats_data: ResMut<StatsData>,
+    practice_match: Res<PracticeMatch>,
+    replay_playback: Res<ReplayPlayback>,
+    is_spectator: Res<IsSpectator>,
+) {
+    if !profile_data.guest
+        || practice_match.active
+        || replay_playback.is_playing()
+        || is_spectator.0
+        || who_is_winner.player_number == 0
+    {
+        return;
+    }
+
+    let mut guest_stats = GuestStats::load(Path::new(GUEST_STATS_FILE));
+
+    if who_is_winner.player_number == player_selection.0 {
+        guest_stats.win += 1;
+    } else {
+        guest_stats.loss += 1;
+    }
+
+    if let Err(e) = guest_stats.save(Path::new(GUEST_STATS_FILE)) {
+        error!("❌ Could not save guest stats: {:?}", e);
+    }
+
+    stats_data.win = guest_stats.win;
+    stats_data.loss = guest_stats.loss;
+}
@@ -0,0 +1,239 @@
+use bevy::prelude::*;
+
+use crate::{
+    settings::UserSettings,
+    shooting::{KeyPool, keycode::ALL_KEYS},
+};
+
+/// Face buttons then d-pad, the order a key pool's symbols are mapped onto a
+/// gamepad. The arrow pool goes straight to the d-pad instead.
+const COMBO_BUTTONS: [GamepadButton; 8] = [
+    GamepadButton::West,
+    GamepadButton::North,
+    GamepadButton::East,
+    GamepadButton::South,
+    GamepadButton::DPadLeft,
+    GamepadButton::DPadUp,
+    GamepadButton::DPadDown,
+    GamepadButton::DPadRight,
+];
+
+/// Whatever the player touched last. Key glyphs and menu focus follow it.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputDevice {
+    #[default]
+    Keyboard,
+    Gamepad,
+}
+
+/// The button a gamepad player presses for a combo symbol of `key_pool`.
+pub fn gamepad_button(key_pool: KeyPool, key: KeyCode) -> Option<GamepadButton> {
+    let button = match key {
+        KeyCode::ArrowUp => GamepadButton::DPadUp,
+        KeyCode::ArrowDown => GamepadButton::DPadDown,
+        KeyCode::ArrowLeft => GamepadButton::DPadLeft,
+        KeyCode::ArrowRight => GamepadButton::DPadRight,
+        _ => {
+            let index = key_pool
+                .keys()
+                .iter()
+                .position(|pool_key| *pool_key == key)?;
+            *COMBO_BUTTONS.get(index)?
+        }
+    };
+
+    Some(button)
+}
+
+pub fn gamepad_glyph(button: GamepadButton) -> &'static str {
+    match button {
+        GamepadButton::South => "A",
+        GamepadButton::East => "B",
+        GamepadButton::West => "X",
+        GamepadButton::North => "Y",
+        GamepadButton::DPadUp => "^",
+        GamepadButton::DPadDown => "v",
+        GamepadButton::DPadLeft => "<",
+        GamepadButton::DPadRight => ">",
+        _ => "?",
+    }
+}
+
+/// The glyph drawn on a shooting key for the device in use.
+pub fn combo_glyph(
+    key_pool: KeyPool,
+    key: KeyCode,
+    input_device: InputDevice,
+    user_settings: &UserSettings,
+) -> String {
+    match (input_device, gamepad_button(key_pool, key)) {
+        (InputDevice::Gamepad, Some(button)) => gamepad_glyph(button).to_string(),
+        _ => crate::shooting::keycode::key_label(user_settings.key_for(key)),
+    }
+}
+
+/// One combo press, from the keyboard or any gamepad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComboPress {
+    Correct,
+    Wrong,
+}
+
+pub fn combo_press(
+    key_pool: KeyPool,
+    expected: KeyCode,
+    keyboard_input: &ButtonInput<KeyCode>,
+    gamepads: &Query<&Gamepad>,
+    user_settings: &UserSettings,
+) -> Option<ComboPress> {
+    let expected_button = gamepad_button(key_pool, expected);
+
+    let correct = keyboard_input.just_pressed(user_settings.key_for(expected))
+        || expected_button
+            .is_some_and(|button| gamepads.iter().any(|gamepad| gamepad.just_pressed(button)));
+
+    if correct {
+        Some(ComboPress::Correct)
+    } else if any_input_just_pressed(keyboard_input, gamepads) {
+        Some(ComboPress::Wrong)
+    } else {
+        None
+    }
+}
+
+/// A combo press on a shared keyboard, where only the keys of the player's own
+/// pool count. Anything else belongs to the other player.
+pub fn pool_press(
+    key_pool: KeyPool,
+    expected: KeyCode,
+    keyboard_input: &ButtonInput<KeyCode>,
+    user_settings: &UserSettings,
+) -> Option<ComboPress> {
+    if keyboard_input.just_pressed(user_settings.key_for(expected)) {
+        Some(ComboPress::Correct)
+    } else if key_pool
+        .keys()
+        .iter()
+        .any(|key| keyboard_input.just_pressed(user_settings.key_for(*key)))
+    {
+        Some(ComboPress::Wrong)
+    } else {
+        None
+    }
+}
+
+pub fn any_input_just_pressed(
+    keyboard_input: &ButtonInput<KeyCode>,
+    gamepads: &Query<&Gamepad>,
+) -> bool {
+    keyboard_input.any_just_pressed(ALL_KEYS)
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.any_just_pressed(COMBO_BUTTONS))
+}
+
+pub fn track_input_device(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut input_device: ResMut<InputDevice>,
+) {
+    if gamepads
+        .iter()
+        .any(|gamepad| gamepad.get_just_pressed().next().is_some())
+    {
+        input_device.set_if_neq(InputDevice::Gamepad);
+    } else if keyboard_input.get_just_pressed().next().is_some()
+        || mouse_input.get_just_pressed().next().is_some()
+    {
+        input_device.set_if_neq(InputDevice::Keyboard);
+    }
+}
+
+/// The button the gamepad cursor is on.
+#[derive(Resource, Debug, Default)]
+pub struct MenuFocus(pub Option<Entity>);
+
+/// Drives the mouse-only menus from a gamepad: the d-pad moves between the
+/// visible buttons in screen order, South presses the focused one and East
+/// presses the screen's "Back". It runs right after Bevy's own focus pass and
+/// only touches `Interaction`, so every screen's handlers work unchanged.
+#[allow(clippy::type_complexity)]
+pub fn gamepad_menu_navigation(
+    gamepads: Query<&Gamepad>,
+    input_device: Res<InputDevice>,
+    mut menu_focus: ResMut<MenuFocus>,
+    mut button_query: Query<
+        (
+            Entity,
+            &mut Interaction,
+            &GlobalTransform,
+            &ComputedNode,
+            &InheritedVisibility,
+            Option<&Name>,
+        ),
+        With<Button>,
+    >,
+) {
+    if *input_device != InputDevice::Gamepad {
+        menu_focus.0 = None;
+        return;
+    }
+
+    let mut buttons: Vec<(Entity, Vec2)> = button_query
+        .iter()
+        .filter(|(_, _, _, node, visibility, _)| visibility.get() && node.size() != Vec2::ZERO)
+        .map(|(entity, _, transform, _, _, _)| (entity, transform.translation().truncate()))
+        .collect();
+
+    if buttons.is_empty() {
+        menu_focus.0 = None;
+        return;
+    }
+
+    buttons.sort_by(|(_, a), (_, b)| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+
+    let current = menu_focus
+        .0
+        .and_then(|focused| buttons.iter().position(|(entity, _)| *entity == focused));
+
+    let just_pressed =
+        |button: GamepadButton| gamepads.iter().any(|gamepad| gamepad.just_pressed(button));
+
+    let step: isize =
+        if just_pressed(GamepadButton::DPadDown) || just_pressed(GamepadButton::DPadRight) {
+            1
+        } else if just_pressed(GamepadButton::DPadUp) || just_pressed(GamepadButton::DPadLeft) {
+            -1
+        } else {
+            0
+        };
+
+    let index = match current {
+        Some(index) => (index as isize + step).rem_euclid(buttons.len() as isize) as usize,
+        None => 0,
+    };
+    menu_focus.0 = Some(buttons[index].0);
+
+    let back = just_pressed(GamepadButton::East)
+        .then(|| {
+            button_query
+                .iter()
+                .find(|(entity, _, _, _, _, name)| {
+                    name.is_some_and(|name| name.as_str() == "Back")
+                        && buttons.iter().any(|(visible, _)| visible == entity)
+                })
+                .map(|(entity, ..)| entity)
+        })
+        .flatten();
+
+    for (entity, mut interaction, ..) in button_query.iter_mut() {
+        if Some(entity) == back
+            || (Some(entity) == menu_focus.0 && just_pressed(GamepadButton::South))
+        {
+            *interaction = Interaction::Pressed;
+        } else if Some(entity) == menu_focus.0 {
+            interaction.set_if_neq(Interaction::Hovered);
+        }
+    }
+}
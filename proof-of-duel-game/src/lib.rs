@@ -7,6 +7,11 @@ use bevy_quinnet::shared::channels::{
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::{
+    lobby::OpenMatch, player::Player, series::SeriesScore, shooting::MatchSettings,
+    tournament::Bracket,
+};
+
 pub(crate) const GRID_SIZE: f32 = 32.0;
 pub(crate) const MAP_SIZE_X: usize = 40;
 pub(crate) const MUSIC_VOLUME: f32 = 0.8;
@@ -16,6 +21,9 @@ pub const SERVER_HOST: Ipv4Addr = Ipv4Addr::LOCALHOST;
 pub const LOCAL_BIND_IP: Ipv4Addr = Ipv4Addr::UNSPECIFIED;
 pub const SERVER_PORT: u16 = 6000;
 
+pub const STARTING_HEARTS: usize = 5;
+pub const DISPUTE_WINDOW_SECS: i64 = 10 * 60;
+
 pub fn get_ip() -> Result<Ipv4Addr> {
     dotenvy::dotenv().ok();
 
@@ -51,9 +59,9 @@ pub enum ServerChannel {
     GameOver,
 }
 
-impl Into<ChannelId> for ServerChannel {
-    fn into(self) -> ChannelId {
-        self as ChannelId
+impl From<ServerChannel> for ChannelId {
+    fn from(channel: ServerChannel) -> Self {
+        channel as ChannelId
     }
 }
 
@@ -83,9 +91,9 @@ pub enum ClientChannel {
     Shooting,
 }
 
-impl Into<ChannelId> for ClientChannel {
-    fn into(self) -> ChannelId {
-        self as ChannelId
+impl From<ClientChannel> for ChannelId {
+    fn from(channel: ClientChannel) -> Self {
+        channel as ChannelId
     }
 }
 
@@ -128,6 +136,10 @@ pub enum ServerMessage {
     IsGameReadyToStart {
         match_id: Uuid,
         is_ready: bool,
+        players: Vec<Player>,
+        settings: MatchSettings,
+        /// Seeds the key sequence both players draw their combos from.
+        key_seed: u64,
     },
     ShootingCommand {
         match_id: Uuid,
@@ -143,30 +155,174 @@ pub enum ServerMessage {
         match_id: Uuid,
         winner: usize,
     },
+    MatchCommitted {
+        match_id: Uuid,
+        /// What the result is committed on-chain under, see `transcript::game_id`.
+        #[serde(default)]
+        game_id: Uuid,
+        transcript_hash: String,
+    },
+    /// The result is queued on the server but hasn't reached the chain yet.
+    ResultSyncPending {
+        match_id: Uuid,
+    },
+    /// The result is on-chain but can still be disputed. Stats only change
+    /// once it is finalized.
+    ResultCommitted {
+        match_id: Uuid,
+    },
+    /// The result is finalized and counted in both players' stats.
+    ResultSynced {
+        match_id: Uuid,
+    },
+    WalletChallenge {
+        nonce: String,
+    },
+    /// Sent to a spectator when it starts watching, with the match as it stands.
+    SpectatingMatch {
+        match_id: Uuid,
+        players: Vec<Player>,
+        player_1_hearts: usize,
+        player_2_hearts: usize,
+        series: SeriesScore,
+        in_progress: bool,
+    },
+    /// A round of a series is over but the series isn't, the hearts refill.
+    RoundOver {
+        match_id: Uuid,
+        round_winner: usize,
+        series: SeriesScore,
+    },
+    /// The other player asked for a rematch.
+    RematchRequested {
+        match_id: Uuid,
+        player_number: usize,
+    },
+    RematchDeclined {
+        match_id: Uuid,
+    },
+    /// The other player disconnected from the match.
+    OpponentLeft {
+        match_id: Uuid,
+    },
+    /// Both players want a rematch. The server resets the same session and
+    /// counts it as rematch number `rematch`, which gives the game its own
+    /// transcript, replay and commit.
+    RematchStarting {
+        match_id: Uuid,
+        rematch: u32,
+    },
+    /// The bracket as it stands, sent to everyone following the tournament
+    /// whenever it changes.
+    TournamentUpdated {
+        bracket: Bracket,
+    },
+    /// Registration went through. The token lets the player pick their spot
+    /// back up after reconnecting.
+    TournamentRegistered {
+        code: String,
+        entry_token: Uuid,
+    },
+    TournamentRejected {
+        error_message: String,
+    },
+    /// One page of the public matches waiting for an opponent, newest first.
+    OpenMatches {
+        page: usize,
+        page_count: usize,
+        matches: Vec<OpenMatch>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ClientMessage {
+    WalletChallengeRequest,
     CreateMatchRequest {
         match_id: Uuid,
         player_wallet: String,
+        wallet_signature: String,
+        username: String,
+        settings: MatchSettings,
+    },
+    /// The host changing the rules while waiting for an opponent.
+    UpdateMatchSettings {
+        match_id: Uuid,
+        settings: MatchSettings,
     },
     JoinMatchRequest {
         match_id: Uuid,
         player_wallet: String,
+        wallet_signature: String,
+        username: String,
     },
     ShootingCommand {
         match_id: Uuid,
         player_number: usize,
     },
+    /// Watch a match without playing in it.
+    SpectateMatchRequest {
+        match_id: Uuid,
+    },
+    /// Asks for, or accepts, a rematch once the match is over.
+    RematchRequest {
+        match_id: Uuid,
+    },
+    DeclineRematch {
+        match_id: Uuid,
+    },
+    CreateTournament {
+        code: String,
+        settings: MatchSettings,
+    },
+    /// The entrant is registered under their verified wallet, there's no
+    /// name to pick.
+    RegisterTournament {
+        code: String,
+        player_wallet: String,
+        wallet_signature: String,
+    },
+    RejoinTournament {
+        code: String,
+        entry_token: Uuid,
+    },
+    /// Only the organizer can start it, which closes registration.
+    StartTournament {
+        code: String,
+    },
+    /// Follow the bracket without playing in it.
+    WatchTournament {
+        code: String,
+    },
+    /// Asks for a page of the lobby browser, counting from 0.
+    ListOpenMatches {
+        page: usize,
+    },
 }
 
+pub mod bridge;
 pub mod cameras;
 pub mod civic_auth;
+pub mod config;
 pub mod connection;
+pub mod duel_profile;
+pub mod guest;
+pub mod input;
+pub mod lobby;
+pub mod local_duel;
+pub mod outbox;
 pub mod player;
+pub mod practice;
+pub mod replay;
+pub mod rhythm;
 pub mod scene;
+pub mod series;
+pub mod session;
+pub mod settings;
 pub mod shooting;
+pub mod solana_rpc;
 pub mod sounds;
 pub mod stats;
+pub mod tournament;
+pub mod transcript;
 pub mod ui;
+pub mod wallet;
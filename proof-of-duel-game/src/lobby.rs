@@ -0,0 +1,264 @@
+use bevy::prelude::*;
+use bevy_quinnet::client::QuinnetClient;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    ClientChannel, ClientMessage, ServerMessage, connection::ServerMessageReceived,
+    shooting::MatchSettings,
+};
+
+/// How many open matches the server sends per page.
+pub const LOBBY_PAGE_SIZE: usize = 5;
+
+/// How often the Join Game screen asks for the list again.
+pub const LOBBY_REFRESH_SECS: f32 = 3.0;
+
+/// A public match waiting for an opponent, as listed in the lobby browser.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenMatch {
+    pub match_id: Uuid,
+    pub host_name: String,
+    /// The host's on-chain win/loss record. Unknown for anonymous hosts and
+    /// until the server has looked it up.
+    pub host_record: Option<(u32, u32)>,
+    pub settings: MatchSettings,
+    /// Seconds since the match was created.
+    pub age_secs: i64,
+}
+
+impl OpenMatch {
+    pub fn rating_label(&self) -> String {
+        match self.host_record {
+            Some((win, loss)) => format!("{}W {}L", win, loss),
+            None => "Unrated".to_string(),
+        }
+    }
+
+    pub fn settings_label(&self) -> String {
+        let settings = &self.settings;
+
+        if settings.best_of > 1 {
+            format!(
+                "{} x{} Bo{}",
+                settings.key_pool.label(),
+                settings.combo_length,
+                settings.best_of
+            )
+        } else {
+            format!("{} x{}", settings.key_pool.label(), settings.combo_length)
+        }
+    }
+
+    pub fn age_label(&self) -> String {
+        match self.age_secs {
+            ..60 => "<1m".to_string(),
+            60..3600 => format!("{}m", self.age_secs / 60),
+            _ => format!("{}h", self.age_secs / 3600),
+        }
+    }
+}
+
+/// The number of pages `total` open matches take, never less than one.
+pub fn page_count(total: usize) -> usize {
+    total.div_ceil(LOBBY_PAGE_SIZE).max(1)
+}
+
+/// Page `page` of the open matches, newest first, as the server sends it. A
+/// page past the end, left over from a list that has since shrunk, is moved
+/// back to the last one.
+pub fn open_matches_page(mut open_matches: Vec<OpenMatch>, page: usize) -> ServerMessage {
+    open_matches.sort_by_key(|open_match| (open_match.age_secs, open_match.match_id));
+
+    let page_count = page_count(open_matches.len());
+    let page = page.min(page_count - 1);
+
+    ServerMessage::OpenMatches {
+        page,
+        page_count,
+        matches: open_matches
+            .into_iter()
+            .skip(page * LOBBY_PAGE_SIZE)
+            .take(LOBBY_PAGE_SIZE)
+            .collect(),
+    }
+}
+
+/// The page of open matches the Join Game screen shows.
+#[derive(Resource, Debug)]
+pub struct LobbyBrowser {
+    pub page: usize,
+    pub page_count: usize,
+    pub matches: Vec<OpenMatch>,
+}
+
+impl Default for LobbyBrowser {
+    fn default() -> Self {
+        Self {
+            page: 0,
+            page_count: 1,
+            matches: Vec::new(),
+        }
+    }
+}
+
+impl LobbyBrowser {
+    pub fn request(&self, client: &mut QuinnetClient) {
+        let _ = client.connection_mut().send_message_on(
+            ClientChannel::Lobby,
+            ClientMessage::ListOpenMatches { page: self.page },
+        );
+    }
+
+    pub fn next_page(&mut self) {
+        if self.page + 1 < self.page_count {
+            self.page += 1;
+        }
+    }
+
+    pub fn previous_page(&mut self) {
+        self.page = self.page.saturating_sub(1);
+    }
+}
+
+#[derive(Resource)]
+pub struct LobbyRefreshTimer(pub Timer);
+
+impl Default for LobbyRefreshTimer {
+    fn default() -> Self {
+        LobbyRefreshTimer(Timer::from_seconds(
+            LOBBY_REFRESH_SECS,
+            TimerMode::Repeating,
+        ))
+    }
+}
+
+/// Starts the list over on the first page and asks for it right away.
+pub fn open_lobby_browser(
+    mut lobby_browser: ResMut<LobbyBrowser>,
+    mut refresh_timer: ResMut<LobbyRefreshTimer>,
+    mut client: ResMut<QuinnetClient>,
+) {
+    *lobby_browser = LobbyBrowser::default();
+    refresh_timer.0.reset();
+
+    lobby_browser.request(&mut client);
+}
+
+pub fn refresh_lobby_browser(
+    time: Res<Time>,
+    lobby_browser: Res<LobbyBrowser>,
+    mut refresh_timer: ResMut<LobbyRefreshTimer>,
+    mut client: ResMut<QuinnetClient>,
+) {
+    if refresh_timer.0.tick(time.delta()).just_finished() {
+        lobby_browser.request(&mut client);
+    }
+}
+
+/// Leaves the list alone when nothing on the page changed, so an unchanged
+/// page isn't rebuilt on every refresh.
+pub fn handle_lobby_messages(
+    mut server_message: EventReader<ServerMessageReceived>,
+    mut lobby_browser: ResMut<LobbyBrowser>,
+) {
+    for ServerMessageReceived { channel, message } in server_message.read() {
+        if let ServerMessage::OpenMatches {
+            page,
+            page_count,
+            matches,
+        } = message
+            && *channel == 0
+            && (lobby_browser.page != *page
+                || lobby_browser.page_count != *page_count
+                || lobby_browser.matches != *matches)
+        {
+            lobby_browser.page = *page;
+            lobby_browser.page_count = *page_count;
+            lobby_browser.matches = matches.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_match(age_secs: i64) -> OpenMatch {
+        OpenMatch {
+            match_id: Uuid::new_v4(),
+            host_name: format!("Host {age_secs}"),
+            host_record: None,
+            settings: MatchSettings::default(),
+            age_secs,
+        }
+    }
+
+    fn page_of(open_matches: Vec<OpenMatch>, page: usize) -> (usize, usize, Vec<i64>) {
+        match open_matches_page(open_matches, page) {
+            ServerMessage::OpenMatches {
+                page,
+                page_count,
+                matches,
+            } => (
+                page,
+                page_count,
+                matches
+                    .iter()
+                    .map(|open_match| open_match.age_secs)
+                    .collect(),
+            ),
+            message => panic!("expected OpenMatches, got {message:?}"),
+        }
+    }
+
+    #[test]
+    fn page_count_rounds_up_and_never_drops_to_zero() {
+        assert_eq!(page_count(0), 1);
+        assert_eq!(page_count(1), 1);
+        assert_eq!(page_count(LOBBY_PAGE_SIZE), 1);
+        assert_eq!(page_count(LOBBY_PAGE_SIZE + 1), 2);
+        assert_eq!(page_count(LOBBY_PAGE_SIZE * 3), 3);
+    }
+
+    #[test]
+    fn pages_list_the_newest_matches_first() {
+        let open_matches = (0..12).rev().map(open_match).collect::<Vec<_>>();
+
+        assert_eq!(
+            page_of(open_matches.clone(), 0),
+            (0, 3, vec![0, 1, 2, 3, 4])
+        );
+        assert_eq!(
+            page_of(open_matches.clone(), 1),
+            (1, 3, vec![5, 6, 7, 8, 9])
+        );
+        assert_eq!(page_of(open_matches, 2), (2, 3, vec![10, 11]));
+    }
+
+    #[test]
+    fn a_page_past_the_end_shows_the_last_one() {
+        let open_matches = (0..7).map(open_match).collect::<Vec<_>>();
+
+        assert_eq!(page_of(open_matches, 9), (1, 2, vec![5, 6]));
+        assert_eq!(page_of(Vec::new(), 3), (0, 1, vec![]));
+    }
+
+    #[test]
+    fn browser_stays_within_its_pages() {
+        let mut browser = LobbyBrowser {
+            page_count: 2,
+            ..Default::default()
+        };
+
+        browser.previous_page();
+        assert_eq!(browser.page, 0);
+
+        browser.next_page();
+        browser.next_page();
+        assert_eq!(browser.page, 1);
+
+        browser.previous_page();
+        assert_eq!(browser.page, 0);
+    }
+}
@@ -0,0 +1,194 @@
+use bevy::prelude::*;
+use bevy_aseprite_ultra::prelude::*;
+use uuid::Uuid;
+
+use crate::{
+    input::{self, ComboPress, InputDevice},
+    player::{MatchPlayers, Player, PlayerHertsStatus, PlayerSelection},
+    practice::{PracticeMatch, PracticeShot},
+    settings::UserSettings,
+    shooting::{
+        self, KeyPool, MatchSettings, ResetKeysEvent, ShootingKey, ShootingKeyIndex, ShootingStates,
+    },
+    ui::profile::ProfileData,
+};
+
+/// Player 1 plays the left hand of the keyboard, player 2 the right.
+pub const PLAYER_1_KEY_POOL: KeyPool = KeyPool::Qwer;
+pub const PLAYER_2_KEY_POOL: KeyPool = KeyPool::Uiop;
+
+pub const LOCAL_PLAYER_1: usize = 1;
+pub const LOCAL_PLAYER_2: usize = 2;
+
+/// Player 2's combos in a local duel. Player 1 keeps using `ShootingStates`.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct LocalDuelStates(pub ShootingStates);
+
+/// Marks player 2's key row, so it stays apart from player 1's.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct LocalDuelKey;
+
+/// Sets up a hot-seat duel on this machine. It runs as a practice match
+/// without an AI: shots are resolved locally and no stats are recorded.
+#[allow(clippy::too_many_arguments)]
+pub fn start_local_duel(
+    practice_match: &mut PracticeMatch,
+    player_selection: &mut PlayerSelection,
+    match_players: &mut MatchPlayers,
+    player_hearts_status: &mut PlayerHertsStatus,
+    shooting_states: &mut ShootingStates,
+    local_duel_states: &mut LocalDuelStates,
+    match_settings: MatchSettings,
+    profile_data: &ProfileData,
+) {
+    player_selection.0 = LOCAL_PLAYER_1;
+    player_selection.1 = Uuid::new_v4();
+
+    let player_1_name = if profile_data.username.is_empty() {
+        "Player 1".to_string()
+    } else {
+        profile_data.username.clone()
+    };
+
+    match_players.0 = vec![
+        Player::new(
+            LOCAL_PLAYER_1,
+            profile_data.public_key.clone(),
+            player_1_name,
+        ),
+        Player::new(LOCAL_PLAYER_2, "".to_string(), "Player 2".to_string()),
+    ];
+
+    // Both rows draw from the same seed, each from its own pool.
+    let seed = rand::random();
+
+    shooting_states.configure(
+        MatchSettings {
+            key_pool: PLAYER_1_KEY_POOL,
+            ..match_settings
+        },
+        seed,
+    );
+    local_duel_states.0.configure(
+        MatchSettings {
+            key_pool: PLAYER_2_KEY_POOL,
+            ..match_settings
+        },
+        seed,
+    );
+
+    player_hearts_status.reset();
+    practice_match.start_local_duel();
+}
+
+fn spawn_player_2_keys(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    local_duel_states: &LocalDuelStates,
+    user_settings: &UserSettings,
+) {
+    shooting::spawn_key_row(
+        commands,
+        asset_server,
+        &local_duel_states.0,
+        |key| input::combo_glyph(PLAYER_2_KEY_POOL, key, InputDevice::Keyboard, user_settings),
+        shooting::key_row_center_x(true, LOCAL_PLAYER_2),
+        LocalDuelKey,
+    );
+}
+
+pub fn spawn_local_duel_keys(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    local_duel_states: Res<LocalDuelStates>,
+    user_settings: Res<UserSettings>,
+) {
+    spawn_player_2_keys(
+        &mut commands,
+        &asset_server,
+        &local_duel_states,
+        &user_settings,
+    );
+}
+
+#[allow(clippy::type_complexity)]
+pub fn local_duel_key_input(
+    mut shooting_key_query: Query<
+        (&mut AseAnimation, &ShootingKeyIndex),
+        (With<ShootingKey>, With<LocalDuelKey>),
+    >,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut local_duel_states: ResMut<LocalDuelStates>,
+    mut reset_key_event: EventWriter<ResetKeysEvent>,
+    mut practice_shot: EventWriter<PracticeShot>,
+    user_settings: Res<UserSettings>,
+) {
+    let shooting_states = &mut local_duel_states.0;
+    let current_key_index = shooting_states.current_key_index;
+
+    let Some(data) = shooting_states.data.get_mut(current_key_index) else {
+        return;
+    };
+
+    let Some(press) =
+        input::pool_press(PLAYER_2_KEY_POOL, data.key, &keyboard_input, &user_settings)
+    else {
+        return;
+    };
+
+    if press == ComboPress::Correct {
+        shooting::play_key_animation(shooting_key_query.iter_mut(), current_key_index, "Correct");
+
+        data.is_pressed_correct = true;
+        shooting_states.next_key();
+    } else {
+        shooting::play_key_animation(
+            shooting_key_query.iter_mut(),
+            current_key_index,
+            "InCorrect",
+        );
+
+        shooting_states.wrong_key_increment();
+        shooting_states.reset_current_key_index();
+        reset_key_event.write(ResetKeysEvent::retry(LOCAL_PLAYER_2));
+    }
+
+    if shooting_states.is_last_key() {
+        practice_shot.write(PracticeShot {
+            player: LOCAL_PLAYER_2,
+        });
+
+        shooting_states.reset_current_key_index();
+    }
+}
+
+pub fn spawn_new_local_duel_keys(
+    mut reset_key_event: EventReader<ResetKeysEvent>,
+    mut commands: Commands,
+    shooting_keys_query: Query<Entity, With<LocalDuelKey>>,
+    asset_server: Res<AssetServer>,
+    mut local_duel_states: ResMut<LocalDuelStates>,
+    user_settings: Res<UserSettings>,
+) {
+    for event in reset_key_event
+        .read()
+        .filter(|event| event.player == LOCAL_PLAYER_2)
+    {
+        for entity in shooting_keys_query.iter() {
+            commands.entity(entity).despawn();
+        }
+
+        if event.retry {
+            local_duel_states.0.retry_combo();
+        } else {
+            local_duel_states.0.next_combo();
+        }
+
+        spawn_player_2_keys(
+            &mut commands,
+            &asset_server,
+            &local_duel_states,
+            &user_settings,
+        );
+    }
+}
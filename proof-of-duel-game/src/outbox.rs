@@ -0,0 +1,425 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use crossbeam_channel::{Receiver, Sender};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{DISPUTE_WINDOW_SECS, session::unix_now};
+
+pub const OUTBOX_FILE: &str = "outbox.json";
+
+const RETRY_BASE_SECS: i64 = 5;
+const RETRY_MAX_SECS: i64 = 10 * 60;
+/// Gives the chain a little slack past the dispute window before finalizing.
+const FINALIZE_SLACK_SECS: i64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ReportKind {
+    Commit,
+    Finalize,
+}
+
+impl ReportKind {
+    pub fn path(&self) -> &'static str {
+        match self {
+            ReportKind::Commit => "/api/match-commit",
+            ReportKind::Finalize => "/api/match-finalize",
+        }
+    }
+}
+
+/// A match result report that hasn't been acknowledged by the frontend yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingReport {
+    pub match_id: Uuid,
+    pub kind: ReportKind,
+    pub payload: serde_json::Value,
+    pub attempts: u32,
+    /// Unix time before which the report isn't sent.
+    pub next_attempt_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchFinalizePayload {
+    pub match_id: Uuid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryOutcome {
+    Delivered,
+    /// The report can never go through, e.g. the match was disputed or its
+    /// account doesn't exist. It is dropped.
+    Rejected,
+    Retry,
+}
+
+impl DeliveryOutcome {
+    /// What a response from the frontend means for the report. The match
+    /// account doesn't exist (404), the match was disputed or committed with
+    /// another transcript (409), or the program refused the transaction (422):
+    /// sending it again can't change any of that. Anything else, including a
+    /// player account that isn't created yet, is worth another try.
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            200 => DeliveryOutcome::Delivered,
+            404 | 409 | 422 => DeliveryOutcome::Rejected,
+            _ => DeliveryOutcome::Retry,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeliveryResult {
+    pub match_id: Uuid,
+    pub kind: ReportKind,
+    pub outcome: DeliveryOutcome,
+}
+
+/// A durable queue of match result reports, saved to disk on every change so
+/// nothing is lost if the backend is down or the process exits. Reports are
+/// keyed by match id and kind, so queueing the same report twice is a no-op.
+#[derive(Resource)]
+pub struct Outbox {
+    path: PathBuf,
+    reports: Vec<PendingReport>,
+    in_flight: Vec<(Uuid, ReportKind)>,
+    sender: Sender<DeliveryResult>,
+    receiver: Receiver<DeliveryResult>,
+}
+
+impl Outbox {
+    /// Loads whatever was still queued when the process last stopped.
+    pub fn load(path: &Path) -> Self {
+        let reports = fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Vec<PendingReport>>(&bytes).ok())
+            .unwrap_or_default();
+
+        if !reports.is_empty() {
+            info!("Outbox has {} unsent match reports", reports.len());
+        }
+
+        let (sender, receiver) = crossbeam_channel::unbounded();
+
+        Self {
+            path: path.to_path_buf(),
+            reports,
+            in_flight: Vec::new(),
+            sender,
+            receiver,
+        }
+    }
+
+    fn save(&self) {
+        let json = serde_json::to_vec_pretty(&self.reports).expect("PendingReport is serializable");
+        let tmp = self.path.with_extension("json.tmp");
+
+        if let Err(e) = fs::write(&tmp, json).and_then(|_| fs::rename(&tmp, &self.path)) {
+            error!("❌ Could not save outbox: {:?}", e);
+        }
+    }
+
+    pub fn contains(&self, match_id: Uuid, kind: ReportKind) -> bool {
+        self.reports
+            .iter()
+            .any(|report| report.match_id == match_id && report.kind == kind)
+    }
+
+    pub fn is_pending(&self, match_id: Uuid) -> bool {
+        self.reports
+            .iter()
+            .any(|report| report.match_id == match_id)
+    }
+
+    /// Queues a report to be sent no earlier than `not_before`.
+    pub fn push(
+        &mut self,
+        match_id: Uuid,
+        kind: ReportKind,
+        payload: impl Serialize,
+        not_before: i64,
+    ) {
+        if self.insert(match_id, kind, payload, not_before) {
+            self.save();
+        }
+    }
+
+    /// `push` without the save, returns whether the report was new.
+    fn insert(
+        &mut self,
+        match_id: Uuid,
+        kind: ReportKind,
+        payload: impl Serialize,
+        not_before: i64,
+    ) -> bool {
+        if self.contains(match_id, kind) {
+            return false;
+        }
+
+        self.reports.push(PendingReport {
+            match_id,
+            kind,
+            payload: serde_json::to_value(payload).expect("report payload is serializable"),
+            attempts: 0,
+            next_attempt_at: not_before,
+        });
+        true
+    }
+
+    /// Reports that are due and not already being sent. They are marked in flight.
+    pub fn take_due(&mut self) -> Vec<PendingReport> {
+        let now = unix_now();
+
+        let due: Vec<PendingReport> = self
+            .reports
+            .iter()
+            .filter(|report| {
+                report.next_attempt_at <= now
+                    && !self.in_flight.contains(&(report.match_id, report.kind))
+            })
+            .cloned()
+            .collect();
+
+        self.in_flight
+            .extend(due.iter().map(|report| (report.match_id, report.kind)));
+
+        due
+    }
+
+    pub fn sender(&self) -> Sender<DeliveryResult> {
+        self.sender.clone()
+    }
+
+    /// Applies the delivery results that came back since the last call and
+    /// returns them. A delivered commit is swapped for its finalization in the
+    /// same save, so a crash can't lose the finalization.
+    pub fn settle(&mut self) -> Vec<DeliveryResult> {
+        let results: Vec<DeliveryResult> = self.receiver.try_iter().collect();
+
+        for result in &results {
+            self.in_flight
+                .retain(|key| *key != (result.match_id, result.kind));
+
+            let key = |report: &PendingReport| {
+                report.match_id == result.match_id && report.kind == result.kind
+            };
+
+            match result.outcome {
+                DeliveryOutcome::Delivered => {
+                    self.reports.retain(|report| !key(report));
+
+                    if result.kind == ReportKind::Commit {
+                        self.insert(
+                            result.match_id,
+                            ReportKind::Finalize,
+                            MatchFinalizePayload {
+                                match_id: result.match_id,
+                            },
+                            unix_now() + DISPUTE_WINDOW_SECS + FINALIZE_SLACK_SECS,
+                        );
+                    }
+                }
+                DeliveryOutcome::Rejected => {
+                    self.reports.retain(|report| !key(report));
+                }
+                DeliveryOutcome::Retry => {
+                    if let Some(report) = self.reports.iter_mut().find(|report| key(report)) {
+                        report.attempts += 1;
+
+                        let backoff = RETRY_BASE_SECS
+                            .saturating_mul(1 << report.attempts.min(10))
+                            .min(RETRY_MAX_SECS);
+                        report.next_attempt_at = unix_now() + backoff;
+                    }
+                }
+            }
+        }
+
+        if !results.is_empty() {
+            self.save();
+        }
+
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn outbox_path() -> PathBuf {
+        std::env::temp_dir().join(format!("outbox-{}.json", Uuid::new_v4()))
+    }
+
+    fn deliver(outbox: &mut Outbox, report: &PendingReport, outcome: DeliveryOutcome) {
+        outbox
+            .sender()
+            .send(DeliveryResult {
+                match_id: report.match_id,
+                kind: report.kind,
+                outcome,
+            })
+            .unwrap();
+        outbox.settle();
+    }
+
+    #[test]
+    fn queued_reports_survive_a_restart() {
+        let path = outbox_path();
+        let match_id = Uuid::new_v4();
+
+        let mut outbox = Outbox::load(&path);
+        outbox.push(match_id, ReportKind::Commit, json!({ "a": 1 }), 0);
+        outbox.push(match_id, ReportKind::Commit, json!({ "a": 2 }), 0);
+
+        let reloaded = Outbox::load(&path);
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(reloaded.reports.len(), 1);
+        assert!(reloaded.contains(match_id, ReportKind::Commit));
+        assert_eq!(reloaded.reports[0].payload, json!({ "a": 1 }));
+    }
+
+    #[test]
+    fn only_due_reports_are_taken_once() {
+        let path = outbox_path();
+        let due = Uuid::new_v4();
+        let later = Uuid::new_v4();
+
+        let mut outbox = Outbox::load(&path);
+        outbox.push(due, ReportKind::Commit, json!({}), 0);
+        outbox.push(later, ReportKind::Commit, json!({}), unix_now() + 60);
+
+        let taken = outbox.take_due();
+        let taken_again = outbox.take_due();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(taken.len(), 1);
+        assert_eq!(taken[0].match_id, due);
+        assert!(taken_again.is_empty());
+    }
+
+    #[test]
+    fn retries_back_off_exponentially_up_to_a_cap() {
+        let path = outbox_path();
+        let mut outbox = Outbox::load(&path);
+        outbox.push(Uuid::new_v4(), ReportKind::Commit, json!({}), 0);
+
+        let mut delays = Vec::new();
+        for _ in 0..12 {
+            let report = outbox.reports[0].clone();
+            deliver(&mut outbox, &report, DeliveryOutcome::Retry);
+            delays.push(outbox.reports[0].next_attempt_at - unix_now());
+        }
+
+        let reloaded = Outbox::load(&path);
+        let _ = fs::remove_file(&path);
+
+        // Allow a second of drift between settling and reading the clock.
+        let expected = [10, 20, 40, 80, 160, 320, 600, 600, 600, 600, 600, 600];
+        for (delay, expected) in delays.iter().zip(expected) {
+            assert!(
+                (expected - 1..=expected).contains(delay),
+                "{delay} vs {expected}"
+            );
+        }
+        assert_eq!(reloaded.reports[0].attempts, 12);
+    }
+
+    #[test]
+    fn a_retried_report_waits_for_its_backoff() {
+        let path = outbox_path();
+        let mut outbox = Outbox::load(&path);
+        outbox.push(Uuid::new_v4(), ReportKind::Commit, json!({}), 0);
+
+        let report = outbox.take_due().remove(0);
+        deliver(&mut outbox, &report, DeliveryOutcome::Retry);
+        let _ = fs::remove_file(&path);
+
+        assert!(outbox.take_due().is_empty());
+        assert!(outbox.in_flight.is_empty());
+    }
+
+    #[test]
+    fn a_delivered_commit_is_replaced_by_its_finalization() {
+        let path = outbox_path();
+        let match_id = Uuid::new_v4();
+
+        let mut outbox = Outbox::load(&path);
+        outbox.push(match_id, ReportKind::Commit, json!({}), 0);
+        let report = outbox.take_due().remove(0);
+        deliver(&mut outbox, &report, DeliveryOutcome::Delivered);
+
+        let reloaded = Outbox::load(&path);
+        let _ = fs::remove_file(&path);
+
+        assert!(!reloaded.contains(match_id, ReportKind::Commit));
+        assert!(reloaded.contains(match_id, ReportKind::Finalize));
+        assert!(reloaded.reports[0].next_attempt_at >= unix_now() + DISPUTE_WINDOW_SECS);
+        assert_eq!(reloaded.reports[0].payload, json!({ "match_id": match_id }));
+    }
+
+    #[test]
+    fn statuses_map_to_outcomes() {
+        assert_eq!(
+            DeliveryOutcome::from_status(200),
+            DeliveryOutcome::Delivered
+        );
+        assert_eq!(DeliveryOutcome::from_status(404), DeliveryOutcome::Rejected);
+        assert_eq!(DeliveryOutcome::from_status(409), DeliveryOutcome::Rejected);
+        assert_eq!(DeliveryOutcome::from_status(422), DeliveryOutcome::Rejected);
+        assert_eq!(DeliveryOutcome::from_status(500), DeliveryOutcome::Retry);
+        assert_eq!(DeliveryOutcome::from_status(503), DeliveryOutcome::Retry);
+    }
+
+    #[test]
+    fn a_finalize_missing_a_player_account_is_retried() {
+        let path = outbox_path();
+        let match_id = Uuid::new_v4();
+
+        let mut outbox = Outbox::load(&path);
+        outbox.push(match_id, ReportKind::Finalize, json!({}), 0);
+
+        // The frontend answers 500 while a player has no `Player` account yet.
+        let report = outbox.take_due().remove(0);
+        deliver(&mut outbox, &report, DeliveryOutcome::from_status(500));
+
+        let reloaded = Outbox::load(&path);
+        let _ = fs::remove_file(&path);
+
+        assert!(reloaded.contains(match_id, ReportKind::Finalize));
+        assert_eq!(reloaded.reports[0].attempts, 1);
+        assert!(reloaded.reports[0].next_attempt_at > unix_now());
+    }
+
+    #[test]
+    fn delivered_finalizations_and_rejections_are_dropped() {
+        let path = outbox_path();
+        let finalized = Uuid::new_v4();
+        let rejected = Uuid::new_v4();
+
+        let mut outbox = Outbox::load(&path);
+        outbox.push(finalized, ReportKind::Finalize, json!({}), 0);
+        outbox.push(rejected, ReportKind::Commit, json!({}), 0);
+
+        for report in outbox.take_due() {
+            let outcome = if report.match_id == finalized {
+                DeliveryOutcome::Delivered
+            } else {
+                DeliveryOutcome::Rejected
+            };
+            deliver(&mut outbox, &report, outcome);
+        }
+
+        let reloaded = Outbox::load(&path);
+        let _ = fs::remove_file(&path);
+
+        assert!(reloaded.reports.is_empty());
+    }
+}
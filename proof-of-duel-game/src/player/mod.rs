@@ -1,11 +1,17 @@
 use bevy::{audio::Volume, prelude::*};
 use bevy_aseprite_ultra::prelude::*;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
     GRID_SIZE, MAP_SIZE_X,
+    practice::PracticeMatch,
+    settings::UserSettings,
     shooting::{ResetKeysEvent, ShootingEvent},
     sounds::gun_shot::GunShotSound,
+    stats::OpponentStats,
+    ui::join_game::IsSpectator,
+    wallet,
 };
 
 #[derive(Resource, Debug)]
@@ -33,21 +39,59 @@ impl PlayersCounting {
     }
 }
 
-#[derive(Debug, Clone, Component)]
+#[derive(Debug, Clone, Component, Serialize, Deserialize)]
 pub struct Player {
     pub player_number: usize,
     pub wallet: String,
+    pub username: String,
 }
 
 impl Player {
-    pub fn new(player_number: usize, wallet: String) -> Self {
+    pub fn new(player_number: usize, wallet: String, username: String) -> Self {
         Player {
             player_number,
             wallet,
+            username,
         }
     }
+
+    /// The username, or "Player N" for anonymous players.
+    pub fn display_name(&self) -> String {
+        if self.username.is_empty() {
+            format!("Player {}", self.player_number)
+        } else {
+            self.username.clone()
+        }
+    }
+}
+
+/// Both participants of the current match, as shared by the server once it's ready.
+#[derive(Resource, Default, Debug)]
+pub struct MatchPlayers(pub Vec<Player>);
+
+impl MatchPlayers {
+    pub fn get(&self, player_number: usize) -> Player {
+        self.0
+            .iter()
+            .find(|player| player.player_number == player_number)
+            .cloned()
+            .unwrap_or_else(|| Player::new(player_number, "".to_string(), "".to_string()))
+    }
+
+    pub fn opponent_of(&self, player_number: usize) -> Option<&Player> {
+        self.0
+            .iter()
+            .find(|player| player.player_number != player_number)
+    }
+
+    pub fn reset(&mut self) {
+        self.0.clear();
+    }
 }
 
+#[derive(Component)]
+pub struct OpponentRecordText;
+
 #[derive(Resource, Debug)]
 pub struct PlayerHertsStatus {
     pub player_1_hearts: usize,
@@ -101,24 +145,30 @@ pub fn setup_player_1(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     player_selection: Res<PlayerSelection>,
+    match_players: Res<MatchPlayers>,
+    is_spectator: Res<IsSpectator>,
 ) {
     let aseprite = asset_server.load("sprites/Player1.aseprite");
+    let player = match_players.get(1);
+    let is_opponent = player_selection.0 != 1;
 
-    let player_text = if player_selection.0 == 1 {
-        "You"
+    let player_text = if is_spectator.0 {
+        "Player 1".to_string()
+    } else if is_opponent {
+        opponent_label(&player)
     } else {
-        "Player 1"
+        "You".to_string()
     };
 
     let player_text_color = if player_selection.0 == 1 {
-        Color::srgba(255. / 255., 222. / 255., 99. / 255., 1.0)
+        Color::srgba(1.0, 222. / 255., 99. / 255., 1.0)
     } else {
         Color::WHITE
     };
 
     commands
         .spawn((
-            Player::new(1, "".to_string()),
+            player,
             AseAnimation {
                 aseprite,
                 animation: Animation::tag("idle").with_speed(1.),
@@ -155,6 +205,11 @@ pub fn setup_player_1(
                 },
                 Transform::from_xyz(-GRID_SIZE * 0.5, GRID_SIZE * 4.5, 100.0),
             ));
+        })
+        .with_children(|parent| {
+            if is_opponent {
+                spawn_opponent_record_text(parent, &asset_server, -GRID_SIZE * 0.5);
+            }
         });
 }
 
@@ -162,24 +217,30 @@ pub fn setup_player_2(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     player_selection: Res<PlayerSelection>,
+    match_players: Res<MatchPlayers>,
+    is_spectator: Res<IsSpectator>,
 ) {
     let aseprite = asset_server.load("sprites/Player2.aseprite");
+    let player = match_players.get(2);
+    let is_opponent = player_selection.0 != 2;
 
-    let player_text = if player_selection.0 == 2 {
-        "You"
+    let player_text = if is_spectator.0 {
+        "Player 2".to_string()
+    } else if is_opponent {
+        opponent_label(&player)
     } else {
-        "Player 2"
+        "You".to_string()
     };
 
     let player_text_color = if player_selection.0 == 2 {
-        Color::srgba(255. / 255., 222. / 255., 99. / 255., 1.0)
+        Color::srgba(1.0, 222. / 255., 99. / 255., 1.0)
     } else {
         Color::WHITE
     };
 
     commands
         .spawn((
-            Player::new(2, "".to_string()),
+            player,
             AseAnimation {
                 aseprite,
                 animation: Animation::tag("idle").with_speed(1.),
@@ -216,15 +277,68 @@ pub fn setup_player_2(
                 },
                 Transform::from_xyz(GRID_SIZE * 0.5, GRID_SIZE * 4.5, 100.0),
             ));
+        })
+        .with_children(|parent| {
+            if is_opponent {
+                spawn_opponent_record_text(parent, &asset_server, GRID_SIZE * 0.5);
+            }
         });
 }
 
+fn opponent_label(player: &Player) -> String {
+    if player.wallet.is_empty() {
+        player.display_name()
+    } else {
+        format!(
+            "{}\n{}",
+            player.display_name(),
+            wallet::short_wallet(&player.wallet)
+        )
+    }
+}
+
+fn spawn_opponent_record_text(
+    parent: &mut ChildSpawnerCommands,
+    asset_server: &AssetServer,
+    x: f32,
+) {
+    parent.spawn((
+        OpponentRecordText,
+        Text2d::new(""),
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextFont {
+            font: asset_server.load("fonts/pixeloid_mono.ttf"),
+            font_size: 20.,
+            ..Default::default()
+        },
+        Transform::from_xyz(x, GRID_SIZE * 6.5, 100.0),
+    ));
+}
+
+pub fn update_opponent_record_text(
+    opponent_stats: Res<OpponentStats>,
+    mut query: Query<&mut Text2d, With<OpponentRecordText>>,
+) {
+    if !opponent_stats.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        *text = match opponent_stats.record {
+            Some((win, loss)) => format!("Win: {} Loss: {}", win, loss).into(),
+            None => "".into(),
+        };
+    }
+}
+
 pub fn despawn_player(mut commands: Commands, query: Query<Entity, With<Player>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn();
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn player_shooting(
     mut commands: Commands,
     mut shooting_event: EventReader<ShootingEvent>,
@@ -232,7 +346,12 @@ pub fn player_shooting(
     mut player_query: Query<(&mut AseAnimation, &Player), With<Player>>,
     mut reset_key_event: EventWriter<ResetKeysEvent>,
     player_selection: Res<PlayerSelection>,
+    user_settings: Res<UserSettings>,
+    practice_match: Res<PracticeMatch>,
 ) {
+    // Seeking in a replay can fire several shots in one frame, one gunshot is enough.
+    let mut gun_shot_played = false;
+
     for event in shooting_event.read() {
         for (mut player_animation, player) in player_query.iter_mut() {
             if player.player_number == event.player {
@@ -250,21 +369,26 @@ pub fn player_shooting(
             Vec3::new(-GRID_SIZE * 5., 0., 1000.)
         };
 
-        commands.spawn((
-            GunShotSound,
-            AudioPlayer::new(asset_server.load("sounds/GunShot.ogg")),
-            PlaybackSettings::ONCE
-                .with_spatial(true)
-                .with_volume(Volume::Linear(1.0)),
-            Transform::from_translation(sound_pos),
-        ));
-
-        if event.player == player_selection.0 {
-            reset_key_event.write(ResetKeysEvent);
+        if !gun_shot_played {
+            commands.spawn((
+                GunShotSound,
+                AudioPlayer::new(asset_server.load("sounds/GunShot.ogg")),
+                PlaybackSettings::ONCE
+                    .with_spatial(true)
+                    .with_volume(Volume::Linear(user_settings.sfx_volume)),
+                Transform::from_translation(sound_pos),
+            ));
+            gun_shot_played = true;
+        }
+
+        // In a local duel both key rows are on this machine.
+        if event.player == player_selection.0 || practice_match.local_duel {
+            reset_key_event.write(ResetKeysEvent::next_combo(event.player));
         }
     }
 }
 
+#[allow(clippy::type_complexity)]
 pub fn who_was_hit(
     mut player_query: Query<(&mut AseAnimation, &Player), (With<Player>, Without<PlayterHeart>)>,
     mut player_hit: EventReader<PlayerHit>,
@@ -288,18 +412,22 @@ pub fn update_heart_status(
     mut player_heart_query: Query<(&mut AseAnimation, &PlayterHeart), With<PlayterHeart>>,
     player_hearts_status: Res<PlayerHertsStatus>,
 ) {
-    // Animate hearts UI
+    if !player_hearts_status.is_changed() {
+        return;
+    }
+
+    // Animate hearts UI, they refill between the rounds of a series.
     for (mut anim, heart) in player_heart_query.iter_mut() {
-        if heart.0 == 1 && heart.1 > player_hearts_status.player_1_hearts {
-            anim.animation = Animation::tag("Empty")
-                .with_speed(1.)
-                .with_repeat(AnimationRepeat::Loop);
-        }
+        let hearts = if heart.0 == 1 {
+            player_hearts_status.player_1_hearts
+        } else {
+            player_hearts_status.player_2_hearts
+        };
 
-        if heart.0 == 2 && heart.1 > player_hearts_status.player_2_hearts {
-            anim.animation = Animation::tag("Empty")
-                .with_speed(1.)
-                .with_repeat(AnimationRepeat::Loop);
-        }
+        let tag = if heart.1 > hearts { "Empty" } else { "Full" };
+
+        anim.animation = Animation::tag(tag)
+            .with_speed(1.)
+            .with_repeat(AnimationRepeat::Loop);
     }
 }
@@ -0,0 +1,245 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use uuid::Uuid;
+
+use crate::{
+    GameState,
+    player::{MatchPlayers, Player, PlayerHertsStatus, PlayerHit, PlayerSelection},
+    shooting::{MatchSettings, ShootingEvent, ShootingStates},
+    ui::{game_over::WhoIsWinner, profile::ProfileData},
+};
+
+pub const PRACTICE_PLAYER_NUMBER: usize = 1;
+pub const AI_PLAYER_NUMBER: usize = 2;
+
+/// How the AI gunslinger plays. Every key of its combo takes `reaction_secs`
+/// give or take `reaction_jitter_secs`, and with `error_rate` it presses the
+/// wrong key and has to start the combo over, just like a player would.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AiProfile {
+    pub reaction_secs: f32,
+    pub reaction_jitter_secs: f32,
+    pub error_rate: f32,
+}
+
+impl AiProfile {
+    fn next_reaction(&self) -> f32 {
+        let jitter = rand::rng().random_range(-1.0..=1.0) * self.reaction_jitter_secs;
+        (self.reaction_secs + jitter).max(0.05)
+    }
+
+    fn presses_wrong_key(&self) -> bool {
+        rand::rng().random_bool(self.error_rate.clamp(0.0, 1.0) as f64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PracticeDifficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl PracticeDifficulty {
+    pub const ALL: [PracticeDifficulty; 3] = [
+        PracticeDifficulty::Easy,
+        PracticeDifficulty::Normal,
+        PracticeDifficulty::Hard,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PracticeDifficulty::Easy => "Easy",
+            PracticeDifficulty::Normal => "Normal",
+            PracticeDifficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.label() == label)
+    }
+
+    pub fn ai_profile(&self) -> AiProfile {
+        match self {
+            PracticeDifficulty::Easy => AiProfile {
+                reaction_secs: 0.9,
+                reaction_jitter_secs: 0.3,
+                error_rate: 0.2,
+            },
+            PracticeDifficulty::Normal => AiProfile {
+                reaction_secs: 0.55,
+                reaction_jitter_secs: 0.2,
+                error_rate: 0.1,
+            },
+            PracticeDifficulty::Hard => AiProfile {
+                reaction_secs: 0.3,
+                reaction_jitter_secs: 0.1,
+                error_rate: 0.04,
+            },
+        }
+    }
+}
+
+/// A duel against the AI, played entirely on this machine. Nothing about it
+/// reaches the server, the frontend or the chain.
+#[derive(Resource, Debug, Default)]
+pub struct PracticeMatch {
+    pub active: bool,
+    /// Two players on one keyboard instead of one against the AI.
+    pub local_duel: bool,
+    pub difficulty: PracticeDifficulty,
+    pub ai_profile: Option<AiProfile>,
+    pub key_timer: Timer,
+    pub keys_done: usize,
+}
+
+impl PracticeMatch {
+    pub fn start(&mut self, difficulty: PracticeDifficulty) {
+        self.active = true;
+        self.difficulty = difficulty;
+        self.ai_profile = Some(difficulty.ai_profile());
+        self.keys_done = 0;
+        self.schedule_next_key();
+    }
+
+    /// A local duel has no AI, both sides' shots come from the keyboard.
+    pub fn start_local_duel(&mut self) {
+        self.active = true;
+        self.local_duel = true;
+        self.ai_profile = None;
+        self.keys_done = 0;
+    }
+
+    fn schedule_next_key(&mut self) {
+        if let Some(ai_profile) = self.ai_profile {
+            self.key_timer = Timer::from_seconds(ai_profile.next_reaction(), TimerMode::Once);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// A completed combo in a practice match, from either side.
+#[derive(Event)]
+pub struct PracticeShot {
+    pub player: usize,
+}
+
+pub fn is_practicing(practice_match: Res<PracticeMatch>) -> bool {
+    practice_match.active
+}
+
+pub fn is_local_duel(practice_match: Res<PracticeMatch>) -> bool {
+    practice_match.active && practice_match.local_duel
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn start_practice_match(
+    difficulty: PracticeDifficulty,
+    practice_match: &mut PracticeMatch,
+    player_selection: &mut PlayerSelection,
+    match_players: &mut MatchPlayers,
+    player_hearts_status: &mut PlayerHertsStatus,
+    shooting_states: &mut ShootingStates,
+    match_settings: MatchSettings,
+    profile_data: &ProfileData,
+) {
+    player_selection.0 = PRACTICE_PLAYER_NUMBER;
+    player_selection.1 = Uuid::new_v4();
+
+    match_players.0 = vec![
+        Player::new(
+            PRACTICE_PLAYER_NUMBER,
+            profile_data.public_key.clone(),
+            profile_data.username.clone(),
+        ),
+        Player::new(
+            AI_PLAYER_NUMBER,
+            "".to_string(),
+            format!("AI Gunslinger ({})", difficulty.label()),
+        ),
+    ];
+
+    player_hearts_status.reset();
+    shooting_states.configure(match_settings, rand::random());
+    practice_match.start(difficulty);
+}
+
+pub fn end_practice_match(mut practice_match: ResMut<PracticeMatch>) {
+    practice_match.reset();
+}
+
+pub fn ai_gunslinger(
+    time: Res<Time>,
+    mut practice_match: ResMut<PracticeMatch>,
+    mut practice_shot: EventWriter<PracticeShot>,
+    shooting_states: Res<ShootingStates>,
+) {
+    let Some(ai_profile) = practice_match.ai_profile else {
+        return;
+    };
+
+    if !practice_match.key_timer.tick(time.delta()).finished() {
+        return;
+    }
+
+    if ai_profile.presses_wrong_key() {
+        practice_match.keys_done = 0;
+    } else {
+        practice_match.keys_done += 1;
+    }
+
+    // The AI plays combos as long as the player's.
+    if practice_match.keys_done >= shooting_states.data.len() {
+        practice_match.keys_done = 0;
+        practice_shot.write(PracticeShot {
+            player: AI_PLAYER_NUMBER,
+        });
+    }
+
+    practice_match.schedule_next_key();
+}
+
+/// Plays the part of the server for a practice match: every shot costs the
+/// other side a heart, and the match ends when someone runs out.
+pub fn resolve_practice_shots(
+    mut practice_shot: EventReader<PracticeShot>,
+    mut player_hearts_status: ResMut<PlayerHertsStatus>,
+    mut shooting_event: EventWriter<ShootingEvent>,
+    mut player_hit: EventWriter<PlayerHit>,
+    mut who_is_winner: ResMut<WhoIsWinner>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    for event in practice_shot.read() {
+        if player_hearts_status.player_1_hearts == 0 || player_hearts_status.player_2_hearts == 0 {
+            return;
+        }
+
+        if event.player == 1 {
+            player_hearts_status.player_2_hearts =
+                player_hearts_status.player_2_hearts.saturating_sub(1);
+            player_hit.write(PlayerHit(2));
+        } else {
+            player_hearts_status.player_1_hearts =
+                player_hearts_status.player_1_hearts.saturating_sub(1);
+            player_hit.write(PlayerHit(1));
+        }
+
+        shooting_event.write(ShootingEvent {
+            player: event.player,
+        });
+
+        if player_hearts_status.player_1_hearts == 0 {
+            who_is_winner.player_number = 2;
+            next_game_state.set(GameState::GameOver);
+        } else if player_hearts_status.player_2_hearts == 0 {
+            who_is_winner.player_number = 1;
+            next_game_state.set(GameState::GameOver);
+        }
+    }
+}
@@ -0,0 +1,557 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    GRID_SIZE, GameState, ServerMessage,
+    connection::ServerMessageReceived,
+    player::{MatchPlayers, Player, PlayerHertsStatus, PlayerSelection, PlayersCounting},
+    series::SeriesScore,
+    session::unix_now,
+    shooting::{MatchSettings, ShootingStates, keycode},
+    transcript,
+    ui::{
+        game_over::{MatchCommitment, WhoIsWinner},
+        play_now_ui::GameStartTimer,
+    },
+};
+
+pub const REPLAY_DIR: &str = "replays";
+
+/// Bumped whenever the replay format changes. Files from a newer version are
+/// refused instead of being played back wrong.
+pub const REPLAY_VERSION: u32 = 1;
+
+const SEEK_SECS: f32 = 5.0;
+const SPEEDS: [f32; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ReplayEvent {
+    /// A message as it came off the wire, with the channel it came on.
+    Server { channel: u8, message: ServerMessage },
+    /// A combo key pressed on the recording machine, by `KeyCode` name.
+    KeyPress { key: String, correct: bool },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayEntry {
+    pub at_secs: f32,
+    pub event: ReplayEvent,
+}
+
+/// Everything one side saw of a match, timestamped from the moment it joined.
+/// Played back through `connection::handle_server_messages` like a live match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchReplay {
+    pub version: u32,
+    pub match_id: Uuid,
+    /// Whose view this is. The server records its replays as player 0.
+    pub player_number: usize,
+    /// Which game of the session this is, a rematch keeps the match id.
+    #[serde(default)]
+    pub rematch: u32,
+    pub recorded_at: i64,
+    pub entries: Vec<ReplayEntry>,
+    #[serde(skip, default = "Instant::now")]
+    clock: Instant,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "could not read replay: {e}"),
+            ReplayError::Parse(e) => write!(f, "could not parse replay: {e}"),
+            ReplayError::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "replay version {version} is newer than this game supports"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl MatchReplay {
+    pub fn new(match_id: Uuid, player_number: usize) -> Self {
+        Self::for_rematch(match_id, 0, player_number)
+    }
+
+    pub fn for_rematch(match_id: Uuid, rematch: u32, player_number: usize) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            match_id,
+            player_number,
+            rematch,
+            recorded_at: unix_now(),
+            entries: Vec::new(),
+            clock: Instant::now(),
+        }
+    }
+
+    fn push(&mut self, event: ReplayEvent) {
+        self.entries.push(ReplayEntry {
+            at_secs: self.clock.elapsed().as_secs_f32(),
+            event,
+        });
+    }
+
+    pub fn record_server(&mut self, channel: u8, message: &ServerMessage) {
+        self.push(ReplayEvent::Server {
+            channel,
+            message: message.clone(),
+        });
+    }
+
+    pub fn record_key_press(&mut self, key: KeyCode, correct: bool) {
+        self.push(ReplayEvent::KeyPress {
+            key: keycode::key_name(key),
+            correct,
+        });
+    }
+
+    fn server_messages(&self) -> impl Iterator<Item = &ServerMessage> {
+        self.entries.iter().filter_map(|entry| match &entry.event {
+            ReplayEvent::Server { message, .. } => Some(message),
+            ReplayEvent::KeyPress { .. } => None,
+        })
+    }
+
+    /// The rules and key seed the match started with.
+    pub fn key_sequence(&self) -> Option<(MatchSettings, u64)> {
+        self.server_messages().find_map(|message| match message {
+            ServerMessage::IsGameReadyToStart {
+                settings, key_seed, ..
+            } => Some((*settings, *key_seed)),
+            _ => None,
+        })
+    }
+
+    pub fn players(&self) -> Vec<Player> {
+        self.server_messages()
+            .find_map(|message| match message {
+                ServerMessage::IsGameReadyToStart { players, .. }
+                | ServerMessage::SpectatingMatch { players, .. } => Some(players.clone()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.server_messages()
+            .any(|message| matches!(message, ServerMessage::GameOver { .. }))
+    }
+
+    /// Playback starts when the match did, skipping the wait in the lobby.
+    pub fn start_secs(&self) -> f32 {
+        self.entries
+            .iter()
+            .find(|entry| {
+                matches!(
+                    entry.event,
+                    ReplayEvent::Server {
+                        message: ServerMessage::IsGameReadyToStart { .. },
+                        ..
+                    }
+                )
+            })
+            .map_or(0.0, |entry| entry.at_secs)
+    }
+
+    pub fn end_secs(&self) -> f32 {
+        self.entries.last().map_or(0.0, |entry| entry.at_secs)
+    }
+
+    pub fn label(&self) -> String {
+        let players = MatchPlayers(self.players());
+        let name = |player_number: usize| {
+            let player = players.get(player_number);
+
+            if player.username.is_empty() {
+                format!("Player {}", player_number)
+            } else {
+                player.username
+            }
+        };
+
+        format!(
+            "{} vs {} ({})",
+            name(1),
+            name(2),
+            format_secs(self.end_secs() - self.start_secs())
+        )
+    }
+
+    pub fn path_in(&self, dir: &Path) -> PathBuf {
+        dir.join(format!(
+            "{}-{}.json",
+            transcript::game_id(self.match_id, self.rematch),
+            self.player_number
+        ))
+    }
+
+    pub fn save(&self, dir: &Path) -> std::io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+
+        let path = self.path_in(dir);
+        let json = serde_json::to_vec(self).expect("MatchReplay is serializable");
+        fs::write(&path, json)?;
+
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        let bytes = fs::read(path).map_err(ReplayError::Io)?;
+        let replay: Self = serde_json::from_slice(&bytes).map_err(ReplayError::Parse)?;
+
+        if replay.version > REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(replay.version));
+        }
+
+        Ok(replay)
+    }
+
+    /// Every replay in `dir` that still loads, newest first.
+    pub fn list(dir: &Path) -> Vec<(PathBuf, MatchReplay)> {
+        let Ok(files) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+
+        let mut replays: Vec<(PathBuf, MatchReplay)> = files
+            .filter_map(|file| file.ok())
+            .map(|file| file.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| Self::load(&path).ok().map(|replay| (path, replay)))
+            .collect();
+
+        replays.sort_by_key(|(_, replay)| std::cmp::Reverse(replay.recorded_at));
+        replays
+    }
+}
+
+fn format_secs(secs: f32) -> String {
+    let secs = secs.max(0.0) as u32;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// The match this client is recording, from the moment it was created or
+/// joined. Saved once the game is over.
+#[derive(Resource, Debug, Default)]
+pub struct ReplayRecorder(pub Option<MatchReplay>);
+
+impl ReplayRecorder {
+    pub fn reset(&mut self) {
+        self.0 = None;
+    }
+
+    /// Starts recording a new match. A finished match still held, like the one
+    /// a rematch follows, is saved first.
+    pub fn start(&mut self, match_id: Uuid, player_number: usize) {
+        self.save_finished();
+        self.0 = Some(MatchReplay::new(match_id, player_number));
+    }
+
+    /// Saves the finished game and starts recording its rematch, from the same
+    /// seat.
+    pub fn start_rematch(&mut self, match_id: Uuid, rematch: u32) {
+        let player_number = self.0.as_ref().map_or(0, |replay| replay.player_number);
+
+        self.save_finished();
+        self.0 = Some(MatchReplay::for_rematch(match_id, rematch, player_number));
+    }
+
+    /// Saves and drops the recording once its match is over. A match still
+    /// being played is kept.
+    pub fn save_finished(&mut self) {
+        if !self.0.as_ref().is_some_and(MatchReplay::is_finished) {
+            return;
+        }
+
+        let Some(replay) = self.0.take() else {
+            return;
+        };
+
+        match replay.save(Path::new(REPLAY_DIR)) {
+            Ok(path) => info!("Replay of match {:?} saved to {:?}", replay.match_id, path),
+            Err(e) => error!("❌ Could not save replay: {:?}", e),
+        }
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct ReplayPlayback {
+    pub replay: Option<MatchReplay>,
+    /// The next entry to play.
+    pub cursor: usize,
+    pub position_secs: f32,
+    pub speed: f32,
+    pub paused: bool,
+    /// Set while the game scene is being rebuilt for a (re)start, nothing is
+    /// played until it is back.
+    pub rebuilding: bool,
+}
+
+impl Default for ReplayPlayback {
+    fn default() -> Self {
+        Self {
+            replay: None,
+            cursor: 0,
+            position_secs: 0.0,
+            speed: 1.0,
+            paused: false,
+            rebuilding: false,
+        }
+    }
+}
+
+impl ReplayPlayback {
+    pub fn is_playing(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    pub fn faster(&mut self) {
+        if let Some(speed) = SPEEDS.iter().find(|speed| **speed > self.speed) {
+            self.speed = *speed;
+        }
+    }
+
+    pub fn slower(&mut self) {
+        if let Some(speed) = SPEEDS.iter().rev().find(|speed| **speed < self.speed) {
+            self.speed = *speed;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// A combo key press coming out of a replay instead of the keyboard.
+#[derive(Event)]
+pub struct ReplayedKeyPress {
+    pub correct: bool,
+}
+
+pub fn is_playing_replay(replay_playback: Res<ReplayPlayback>) -> bool {
+    replay_playback.is_playing()
+}
+
+/// Plays `replay_playback`'s match from `position_secs`. Going back in a replay
+/// can't undo what already happened, so the scene is rebuilt from scratch and
+/// everything up to the new position is played again in one go.
+#[allow(clippy::too_many_arguments)]
+pub fn restart_replay(
+    position_secs: f32,
+    replay_playback: &mut ReplayPlayback,
+    player_selection: &mut PlayerSelection,
+    players_counting: &mut PlayersCounting,
+    game_start_timer: &mut GameStartTimer,
+    player_hearts_status: &mut PlayerHertsStatus,
+    who_is_winner: &mut WhoIsWinner,
+    match_commitment: &mut MatchCommitment,
+    match_players: &mut MatchPlayers,
+    shooting_states: &mut ShootingStates,
+    series_score: &mut SeriesScore,
+    next_game_state: &mut NextState<GameState>,
+) {
+    let Some(replay) = &replay_playback.replay else {
+        return;
+    };
+
+    player_selection.0 = replay.player_number;
+    player_selection.1 = replay.match_id;
+    match_players.0 = replay.players();
+
+    // The first key row is spawned before the start message is played again.
+    if let Some((settings, key_seed)) = replay.key_sequence() {
+        shooting_states.configure(settings, key_seed);
+        *series_score = SeriesScore::new(settings.sanitized().best_of);
+    }
+
+    players_counting.reset();
+    game_start_timer.reset();
+    player_hearts_status.reset();
+    who_is_winner.reset();
+    match_commitment.reset();
+
+    replay_playback.position_secs = position_secs.clamp(replay.start_secs(), replay.end_secs());
+    replay_playback.cursor = 0;
+    replay_playback.rebuilding = true;
+
+    next_game_state.set(GameState::InGame);
+}
+
+pub fn finish_replay_rebuild(mut replay_playback: ResMut<ReplayPlayback>) {
+    replay_playback.rebuilding = false;
+}
+
+pub fn play_replay(
+    time: Res<Time>,
+    mut replay_playback: ResMut<ReplayPlayback>,
+    mut server_message: EventWriter<ServerMessageReceived>,
+    mut replayed_key_press: EventWriter<ReplayedKeyPress>,
+) {
+    if replay_playback.rebuilding {
+        return;
+    }
+
+    if !replay_playback.paused {
+        replay_playback.position_secs += time.delta_secs() * replay_playback.speed;
+    }
+
+    let ReplayPlayback {
+        replay: Some(replay),
+        cursor,
+        position_secs,
+        ..
+    } = &mut *replay_playback
+    else {
+        return;
+    };
+
+    while let Some(entry) = replay.entries.get(*cursor)
+        && entry.at_secs <= *position_secs
+    {
+        match &entry.event {
+            ReplayEvent::Server { channel, message } => {
+                server_message.write(ServerMessageReceived {
+                    channel: *channel,
+                    message: message.clone(),
+                });
+            }
+            ReplayEvent::KeyPress { correct, .. } => {
+                replayed_key_press.write(ReplayedKeyPress { correct: *correct });
+            }
+        }
+
+        *cursor += 1;
+    }
+}
+
+/// Space pauses, left and right seek, up and down change the speed.
+#[allow(clippy::too_many_arguments)]
+pub fn replay_controls(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut replay_playback: ResMut<ReplayPlayback>,
+    mut player_selection: ResMut<PlayerSelection>,
+    mut players_counting: ResMut<PlayersCounting>,
+    mut game_start_timer: ResMut<GameStartTimer>,
+    mut player_hearts_status: ResMut<PlayerHertsStatus>,
+    mut who_is_winner: ResMut<WhoIsWinner>,
+    mut match_commitment: ResMut<MatchCommitment>,
+    mut match_players: ResMut<MatchPlayers>,
+    mut shooting_states: ResMut<ShootingStates>,
+    mut series_score: ResMut<SeriesScore>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    if replay_playback.rebuilding {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        replay_playback.paused = !replay_playback.paused;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        replay_playback.faster();
+    }
+
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        replay_playback.slower();
+    }
+
+    if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        replay_playback.position_secs += SEEK_SECS;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        let position_secs = replay_playback.position_secs - SEEK_SECS;
+
+        restart_replay(
+            position_secs,
+            &mut replay_playback,
+            &mut player_selection,
+            &mut players_counting,
+            &mut game_start_timer,
+            &mut player_hearts_status,
+            &mut who_is_winner,
+            &mut match_commitment,
+            &mut match_players,
+            &mut shooting_states,
+            &mut series_score,
+            &mut next_game_state,
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct ReplayHud;
+
+pub fn spawn_replay_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        ReplayHud,
+        Text2d::new(""),
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextFont {
+            font: asset_server.load("fonts/pixeloid_mono.ttf"),
+            font_size: 20.,
+            ..Default::default()
+        },
+        Transform::from_xyz(0., GRID_SIZE * 9., 100.),
+    ));
+}
+
+pub fn update_replay_hud(
+    replay_playback: Res<ReplayPlayback>,
+    mut query: Query<&mut Text2d, With<ReplayHud>>,
+) {
+    let Some(replay) = &replay_playback.replay else {
+        return;
+    };
+
+    let status = if replay_playback.paused {
+        "Paused".to_string()
+    } else {
+        format!("{}x", replay_playback.speed)
+    };
+
+    for mut text in query.iter_mut() {
+        *text = format!(
+            "Replay {} / {} {}\n[Space] Pause  [Left/Right] Seek  [Up/Down] Speed",
+            format_secs(replay_playback.position_secs - replay.start_secs()),
+            format_secs(replay.end_secs() - replay.start_secs()),
+            status
+        )
+        .into();
+    }
+}
+
+pub fn despawn_replay_hud(mut commands: Commands, query: Query<Entity, With<ReplayHud>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Leaving the game over screen for a rematch keeps the rematch's recording going.
+pub fn save_recorded_replay(mut replay_recorder: ResMut<ReplayRecorder>) {
+    replay_recorder.save_finished();
+}
+
+pub fn end_replay(mut replay_playback: ResMut<ReplayPlayback>) {
+    replay_playback.reset();
+}
@@ -0,0 +1,234 @@
+use std::{fs, path::Path};
+
+use bevy::{audio::AudioSink, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GRID_SIZE,
+    sounds::music::{BackgroundMusic, MusicTrack},
+};
+
+pub const CALIBRATION_FILE: &str = "calibration.json";
+
+const PERFECT_WINDOW_SECS: f32 = 0.05;
+const GOOD_WINDOW_SECS: f32 = 0.12;
+
+/// How full the combo meter has to be before the next shot goes off early.
+pub const OVERDRIVE_COMBO: u32 = 10;
+
+/// Where the background music is on its beat grid. It only advances while the
+/// music's `AudioSink` exists and is playing, at the sink's speed, so loading
+/// delays and pauses don't put it out of step with what the player hears.
+#[derive(Resource, Debug, Default)]
+pub struct BeatClock {
+    pub track: Option<MusicTrack>,
+    pub position_secs: f32,
+    pub running: bool,
+}
+
+impl BeatClock {
+    pub fn start(&mut self, track: MusicTrack) {
+        self.track = Some(track);
+        self.position_secs = 0.0;
+        self.running = false;
+    }
+
+    pub fn stop(&mut self) {
+        self.track = None;
+        self.position_secs = 0.0;
+        self.running = false;
+    }
+
+    /// Signed seconds from the nearest beat, positive when late. `None` until
+    /// the music is actually playing.
+    pub fn offset_from_beat(&self) -> Option<f32> {
+        let track = self.track.filter(|_| self.running)?;
+        let beat_secs = 60.0 / track.bpm;
+        let phase = (self.position_secs - track.offset_secs).rem_euclid(beat_secs);
+
+        Some(if phase > beat_secs / 2.0 {
+            phase - beat_secs
+        } else {
+            phase
+        })
+    }
+
+    /// How far through the current beat the music is, from 0 to 1.
+    pub fn beat_progress(&self) -> Option<f32> {
+        let track = self.track.filter(|_| self.running)?;
+        let beat_secs = 60.0 / track.bpm;
+
+        Some((self.position_secs - track.offset_secs).rem_euclid(beat_secs) / beat_secs)
+    }
+
+    pub fn grade(&self, calibration: &InputCalibration) -> Option<BeatGrade> {
+        let offset = self.offset_from_beat()? - calibration.offset_secs();
+        Some(BeatGrade::from_offset(offset))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeatGrade {
+    Perfect,
+    Good,
+    Miss,
+}
+
+impl BeatGrade {
+    pub fn from_offset(offset_secs: f32) -> Self {
+        match offset_secs.abs() {
+            offset if offset <= PERFECT_WINDOW_SECS => BeatGrade::Perfect,
+            offset if offset <= GOOD_WINDOW_SECS => BeatGrade::Good,
+            _ => BeatGrade::Miss,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BeatGrade::Perfect => "Perfect!",
+            BeatGrade::Good => "Good",
+            BeatGrade::Miss => "Miss",
+        }
+    }
+}
+
+/// The player's input latency, measured on the calibration screen and taken
+/// off every press before it is graded.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InputCalibration {
+    pub offset_ms: f32,
+}
+
+impl InputCalibration {
+    pub fn offset_secs(&self) -> f32 {
+        self.offset_ms / 1000.0
+    }
+
+    pub fn load(path: &Path) -> Self {
+        fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_vec_pretty(self).expect("InputCalibration is serializable");
+        fs::write(path, json)
+    }
+}
+
+/// Fills with on-beat presses and empties on a miss or a wrong key. Once it
+/// reaches `OVERDRIVE_COMBO` the next combo fires one key early, which spends
+/// the meter.
+#[derive(Resource, Debug, Default)]
+pub struct ComboMeter {
+    pub combo: u32,
+    pub last_grade: Option<BeatGrade>,
+}
+
+impl ComboMeter {
+    pub fn record(&mut self, grade: BeatGrade) {
+        self.combo = match grade {
+            BeatGrade::Perfect => self.combo + 2,
+            BeatGrade::Good => self.combo + 1,
+            BeatGrade::Miss => 0,
+        };
+        self.last_grade = Some(grade);
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.combo >= OVERDRIVE_COMBO
+    }
+
+    /// Empties a full meter, returning whether there was an overdrive to spend.
+    pub fn spend_overdrive(&mut self) -> bool {
+        let full = self.is_full();
+
+        if full {
+            self.combo = 0;
+        }
+
+        full
+    }
+
+    pub fn reset(&mut self) {
+        self.combo = 0;
+        self.last_grade = None;
+    }
+
+    fn label(&self) -> String {
+        if self.is_full() {
+            "OVERDRIVE".to_string()
+        } else {
+            format!("Combo: {}/{}", self.combo, OVERDRIVE_COMBO)
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ComboMeterText;
+
+pub fn sync_beat_clock(
+    time: Res<Time>,
+    mut beat_clock: ResMut<BeatClock>,
+    music_query: Query<&AudioSink, With<BackgroundMusic>>,
+) {
+    if beat_clock.track.is_none() {
+        return;
+    }
+
+    let Ok(sink) = music_query.single() else {
+        beat_clock.running = false;
+        return;
+    };
+
+    beat_clock.running = !sink.empty();
+
+    if beat_clock.running && !sink.is_paused() {
+        beat_clock.position_secs += time.delta_secs() * sink.speed();
+    }
+}
+
+pub fn spawn_combo_meter(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut combo_meter: ResMut<ComboMeter>,
+) {
+    combo_meter.reset();
+
+    commands.spawn((
+        ComboMeterText,
+        Text2d::new(""),
+        TextColor(Color::srgba(1.0, 222. / 255., 99. / 255., 1.0)),
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextFont {
+            font: asset_server.load("fonts/pixeloid_mono.ttf"),
+            font_size: 28.,
+            ..Default::default()
+        },
+        Transform::from_xyz(0., GRID_SIZE * 1.5, 100.),
+    ));
+}
+
+pub fn update_combo_meter_text(
+    combo_meter: Res<ComboMeter>,
+    mut query: Query<&mut Text2d, With<ComboMeterText>>,
+) {
+    if !combo_meter.is_changed() {
+        return;
+    }
+
+    let Some(grade) = combo_meter.last_grade else {
+        return;
+    };
+
+    for mut text in query.iter_mut() {
+        *text = format!("{}\n{}", grade.label(), combo_meter.label()).into();
+    }
+}
+
+pub fn despawn_combo_meter(mut commands: Commands, query: Query<Entity, With<ComboMeterText>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
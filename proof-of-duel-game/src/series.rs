@@ -0,0 +1,257 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    GRID_SIZE, ServerMessage,
+    connection::ServerMessageReceived,
+    player::{PlayerHertsStatus, PlayerSelection},
+};
+
+/// How many rounds a match can be played over. The series goes to whoever
+/// takes most of them.
+pub const SERIES_LENGTHS: [usize; 3] = [1, 3, 5];
+
+/// How long the round intro holds the players before they can shoot again.
+pub const ROUND_INTRO_SECS: f32 = 2.0;
+
+/// Round wins in a best-of-N match. A single round match is a best of 1.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeriesScore {
+    pub best_of: usize,
+    pub player_1_rounds: usize,
+    pub player_2_rounds: usize,
+}
+
+impl Default for SeriesScore {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl SeriesScore {
+    pub fn new(best_of: usize) -> Self {
+        Self {
+            best_of,
+            player_1_rounds: 0,
+            player_2_rounds: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn is_series(&self) -> bool {
+        self.best_of > 1
+    }
+
+    /// The round being played, counting from 1.
+    pub fn round(&self) -> usize {
+        self.player_1_rounds + self.player_2_rounds + 1
+    }
+
+    pub fn rounds_to_win(&self) -> usize {
+        self.best_of / 2 + 1
+    }
+
+    /// Counts a finished round and returns the winner of the series once it is
+    /// decided. A drawn round ends the series as a draw, as it always ended a
+    /// single round match.
+    pub fn record_round(&mut self, round_winner: usize) -> Option<usize> {
+        match round_winner {
+            1 => self.player_1_rounds += 1,
+            2 => self.player_2_rounds += 1,
+            _ => return Some(0),
+        }
+
+        if self.player_1_rounds >= self.rounds_to_win() {
+            Some(1)
+        } else if self.player_2_rounds >= self.rounds_to_win() {
+            Some(2)
+        } else {
+            None
+        }
+    }
+
+    pub fn label(&self) -> String {
+        format!(
+            "Best of {}   {} - {}",
+            self.best_of, self.player_1_rounds, self.player_2_rounds
+        )
+    }
+}
+
+/// Who took the round once a player is out of hearts. 0 is a draw.
+pub fn round_winner(player_1_hearts: usize, player_2_hearts: usize) -> Option<usize> {
+    match (player_1_hearts, player_2_hearts) {
+        (0, 0) => Some(0),
+        (0, _) => Some(2),
+        (_, 0) => Some(1),
+        _ => None,
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct RoundIntro {
+    pub timer: Timer,
+    pub active: bool,
+}
+
+impl RoundIntro {
+    pub fn new(secs: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(secs, TimerMode::Once),
+            active: false,
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.timer.reset();
+        self.active = true;
+    }
+
+    pub fn reset(&mut self) {
+        self.active = false;
+        self.timer.reset();
+    }
+}
+
+pub fn is_round_intro(round_intro: Res<RoundIntro>) -> bool {
+    round_intro.active
+}
+
+#[derive(Component)]
+pub struct SeriesHud;
+
+#[derive(Component)]
+pub struct RoundIntroText;
+
+pub fn reset_series_score(mut series_score: ResMut<SeriesScore>) {
+    series_score.reset();
+}
+
+/// Keeps the score in step with the server. Between rounds the hearts refill
+/// and the next round is introduced.
+pub fn handle_series_messages(
+    mut server_message: EventReader<ServerMessageReceived>,
+    player_selection: Res<PlayerSelection>,
+    mut series_score: ResMut<SeriesScore>,
+    mut player_hearts_status: ResMut<PlayerHertsStatus>,
+    mut round_intro: ResMut<RoundIntro>,
+) {
+    for ServerMessageReceived { channel, message } in server_message.read() {
+        match message {
+            ServerMessage::IsGameReadyToStart {
+                match_id, settings, ..
+            } if *channel == 0 && *match_id == player_selection.1 => {
+                *series_score = SeriesScore::new(settings.sanitized().best_of);
+            }
+            ServerMessage::SpectatingMatch {
+                match_id, series, ..
+            } if *channel == 0 && *match_id == player_selection.1 => {
+                *series_score = *series;
+            }
+            ServerMessage::RoundOver {
+                match_id, series, ..
+            } if *channel == 2 && *match_id == player_selection.1 => {
+                *series_score = *series;
+                player_hearts_status.reset();
+                round_intro.start();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Both texts are always there, they only show up in a series.
+pub fn spawn_series_hud(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    series_score: Res<SeriesScore>,
+    mut round_intro: ResMut<RoundIntro>,
+) {
+    commands.spawn((
+        SeriesHud,
+        Text2d::new(""),
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextFont {
+            font: asset_server.load("fonts/pixeloid_mono.ttf"),
+            font_size: 28.,
+            ..Default::default()
+        },
+        Transform::from_xyz(0., GRID_SIZE * 7.5, 100.),
+    ));
+
+    commands.spawn((
+        RoundIntroText,
+        Text2d::new(""),
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextFont {
+            font: asset_server.load("fonts/pixeloid_mono_bold.ttf"),
+            font_size: 64.,
+            ..Default::default()
+        },
+        Transform::from_xyz(0., GRID_SIZE * 2., 100.),
+        Visibility::Hidden,
+    ));
+
+    if series_score.is_series() {
+        round_intro.start();
+    } else {
+        round_intro.reset();
+    }
+}
+
+pub fn update_series_hud(
+    time: Res<Time>,
+    series_score: Res<SeriesScore>,
+    mut round_intro: ResMut<RoundIntro>,
+    mut hud_query: Query<&mut Text2d, (With<SeriesHud>, Without<RoundIntroText>)>,
+    mut intro_query: Query<(&mut Text2d, &mut Visibility), With<RoundIntroText>>,
+) {
+    if round_intro.active {
+        round_intro.timer.tick(time.delta());
+
+        if round_intro.timer.finished() {
+            round_intro.active = false;
+        }
+    }
+
+    let hud = if series_score.is_series() {
+        series_score.label()
+    } else {
+        String::new()
+    };
+
+    for mut text in hud_query.iter_mut() {
+        if text.0 != hud {
+            *text = hud.clone().into();
+        }
+    }
+
+    for (mut text, mut visibility) in intro_query.iter_mut() {
+        *visibility = if round_intro.active {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+
+        let intro = format!("Round {}", series_score.round());
+
+        if text.0 != intro {
+            *text = intro.into();
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn despawn_series_hud(
+    mut commands: Commands,
+    query: Query<Entity, Or<(With<SeriesHud>, With<RoundIntroText>)>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
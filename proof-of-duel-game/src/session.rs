@@ -0,0 +1,60 @@
+use std::{
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+pub const SESSION_FILE: &str = "session.json";
+
+/// The identity from the last verified Civic login, kept on disk so a relaunch
+/// doesn't need another trip through the browser until the token expires.
+/// The token itself is checked again on every launch, the other fields are
+/// only what it said last time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub public_key: String,
+    pub username: String,
+    pub expires_at: i64,
+    #[serde(default)]
+    pub id_token: String,
+}
+
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+impl Session {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= unix_now()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_vec_pretty(self).expect("Session is serializable");
+        fs::write(path, json)
+    }
+
+    /// Loads the saved session, dropping it if it has expired or can't be read.
+    pub fn load(path: &Path) -> Option<Self> {
+        let session = fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Self>(&bytes).ok());
+
+        match session {
+            Some(session) if !session.is_expired() => Some(session),
+            Some(_) => {
+                Self::clear(path);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn clear(path: &Path) {
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,310 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use bevy::{
+    audio::Volume,
+    prelude::*,
+    window::{PresentMode, PrimaryWindow, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    MUSIC_VOLUME,
+    shooting::{KeyPool, keycode},
+    sounds::music::BackgroundMusic,
+};
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// The window's resize constraints, as (width, height).
+pub const MIN_RESOLUTION: (u32, u32) = (1280, 640);
+pub const MAX_RESOLUTION: (u32, u32) = (1920, 1080);
+
+/// Window sizes offered on the display page, all inside the window's resize constraints.
+pub const RESOLUTIONS: [(u32, u32); 3] = [(1280, 720), (1600, 900), (1920, 1080)];
+
+/// Every combo symbol any key pool can deal, in the order the controls page lists them.
+pub fn combo_symbols() -> Vec<KeyCode> {
+    let mut symbols: Vec<KeyCode> = Vec::new();
+
+    for key in KeyPool::ALL.iter().flat_map(|pool| pool.keys()) {
+        if !symbols.contains(key) {
+            symbols.push(*key);
+        }
+    }
+
+    symbols
+}
+
+/// The player's own settings. They are applied live as they change and saved
+/// to `SETTINGS_FILE` from the settings screen.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserSettings {
+    /// Combo symbol to the key that plays it, by `KeyCode` name. Symbols
+    /// without an entry are played with their own key.
+    pub key_bindings: BTreeMap<String, String>,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub fullscreen: bool,
+    pub resolution: (u32, u32),
+    pub vsync: bool,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        Self {
+            key_bindings: BTreeMap::new(),
+            music_volume: MUSIC_VOLUME,
+            sfx_volume: 1.0,
+            fullscreen: false,
+            resolution: RESOLUTIONS[0],
+            vsync: true,
+        }
+    }
+}
+
+impl UserSettings {
+    pub fn load(path: &Path) -> Self {
+        fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Self>(&bytes).ok())
+            .unwrap_or_default()
+            .sanitized()
+    }
+
+    /// Settings as they come out of the file, with the volumes and the window
+    /// size kept in range.
+    pub fn sanitized(self) -> Self {
+        let (width, height) = self.resolution;
+
+        Self {
+            music_volume: self.music_volume.clamp(0.0, 1.0),
+            sfx_volume: self.sfx_volume.clamp(0.0, 1.0),
+            resolution: (
+                width.clamp(MIN_RESOLUTION.0, MAX_RESOLUTION.0),
+                height.clamp(MIN_RESOLUTION.1, MAX_RESOLUTION.1),
+            ),
+            ..self
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_vec_pretty(self).expect("UserSettings is serializable");
+        fs::write(path, json)
+    }
+
+    /// The key that plays `symbol`.
+    pub fn key_for(&self, symbol: KeyCode) -> KeyCode {
+        self.key_bindings
+            .get(&keycode::key_name(symbol))
+            .and_then(|name| keycode::key_from_name(name))
+            .unwrap_or(symbol)
+    }
+
+    /// Binds `key` to `symbol`. A symbol that already used `key` takes over
+    /// `symbol`'s old key, so no two symbols ever share one.
+    pub fn bind(&mut self, symbol: KeyCode, key: KeyCode) {
+        let old_key = self.key_for(symbol);
+
+        if let Some(other) = combo_symbols()
+            .into_iter()
+            .find(|other| *other != symbol && self.key_for(*other) == key)
+        {
+            self.set_binding(other, old_key);
+        }
+
+        self.set_binding(symbol, key);
+    }
+
+    fn set_binding(&mut self, symbol: KeyCode, key: KeyCode) {
+        if symbol == key {
+            self.key_bindings.remove(&keycode::key_name(symbol));
+        } else {
+            self.key_bindings
+                .insert(keycode::key_name(symbol), keycode::key_name(key));
+        }
+    }
+
+    pub fn reset_bindings(&mut self) {
+        self.key_bindings.clear();
+    }
+
+    pub fn next_music_volume(&mut self) {
+        self.music_volume = next_volume(self.music_volume);
+    }
+
+    pub fn next_sfx_volume(&mut self) {
+        self.sfx_volume = next_volume(self.sfx_volume);
+    }
+
+    pub fn next_resolution(&mut self) {
+        let index = RESOLUTIONS
+            .iter()
+            .position(|resolution| *resolution == self.resolution)
+            .map_or(0, |index| (index + 1) % RESOLUTIONS.len());
+
+        self.resolution = RESOLUTIONS[index];
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+        } else {
+            WindowMode::Windowed
+        }
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+}
+
+/// Steps a volume up by 10%, wrapping from full back to muted.
+fn next_volume(volume: f32) -> f32 {
+    let step = (volume * 10.0).round() as u32 + 1;
+
+    if step > 10 { 0.0 } else { step as f32 / 10.0 }
+}
+
+pub fn apply_audio_settings(
+    user_settings: Res<UserSettings>,
+    mut music_query: Query<&mut AudioSink, With<BackgroundMusic>>,
+) {
+    if !user_settings.is_changed() {
+        return;
+    }
+
+    for mut sink in music_query.iter_mut() {
+        sink.set_volume(Volume::Linear(user_settings.music_volume));
+    }
+}
+
+/// Only touches the window when a display setting actually changed, so a
+/// volume change doesn't undo a manual resize.
+#[allow(clippy::type_complexity)]
+pub fn apply_display_settings(
+    user_settings: Res<UserSettings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    mut applied: Local<Option<(bool, (u32, u32), bool)>>,
+) {
+    let display = (
+        user_settings.fullscreen,
+        user_settings.resolution,
+        user_settings.vsync,
+    );
+
+    if *applied == Some(display) {
+        return;
+    }
+
+    let Ok(mut window) = window_query.single_mut() else {
+        return;
+    };
+
+    // The window was created with these settings.
+    let Some((fullscreen, resolution, vsync)) = applied.replace(display) else {
+        return;
+    };
+
+    if fullscreen != user_settings.fullscreen {
+        window.mode = user_settings.window_mode();
+    }
+
+    if resolution != user_settings.resolution {
+        let (width, height) = user_settings.resolution;
+        window.resolution.set(width as f32, height as f32);
+    }
+
+    if vsync != user_settings.vsync {
+        window.present_mode = user_settings.present_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binding_a_taken_key_swaps_the_two_symbols() {
+        let mut settings = UserSettings::default();
+
+        settings.bind(KeyCode::KeyQ, KeyCode::KeyW);
+
+        assert_eq!(settings.key_for(KeyCode::KeyQ), KeyCode::KeyW);
+        assert_eq!(settings.key_for(KeyCode::KeyW), KeyCode::KeyQ);
+        assert_eq!(settings.key_for(KeyCode::KeyE), KeyCode::KeyE);
+    }
+
+    #[test]
+    fn binding_a_symbol_back_to_its_own_key_clears_the_swap() {
+        let mut settings = UserSettings::default();
+
+        settings.bind(KeyCode::KeyQ, KeyCode::KeyW);
+        settings.bind(KeyCode::KeyQ, KeyCode::KeyQ);
+
+        assert!(settings.key_bindings.is_empty());
+    }
+
+    #[test]
+    fn binding_a_free_key_leaves_the_other_symbols_alone() {
+        let mut settings = UserSettings::default();
+
+        settings.bind(KeyCode::KeyQ, KeyCode::KeyZ);
+
+        assert_eq!(settings.key_for(KeyCode::KeyQ), KeyCode::KeyZ);
+        assert_eq!(settings.key_bindings.len(), 1);
+
+        let keys: Vec<_> = combo_symbols()
+            .into_iter()
+            .map(|symbol| settings.key_for(symbol))
+            .collect();
+        let mut unique = keys.clone();
+        unique.sort_by_key(|key| keycode::key_name(*key));
+        unique.dedup();
+
+        assert_eq!(unique.len(), keys.len());
+    }
+
+    #[test]
+    fn volume_steps_by_a_tenth_and_wraps_to_muted() {
+        assert_eq!(next_volume(0.0), 0.1);
+        assert_eq!(next_volume(0.5), 0.6);
+        assert_eq!(next_volume(0.9), 1.0);
+        assert_eq!(next_volume(1.0), 0.0);
+        // Off-step volumes snap to the next tenth.
+        assert_eq!(next_volume(0.33), 0.4);
+    }
+
+    #[test]
+    fn loaded_settings_are_kept_in_range() {
+        let path = std::env::temp_dir().join(format!("settings-{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            r#"{ "resolution": [800, 4000], "music_volume": 3.0, "vsync": false }"#,
+        )
+        .unwrap();
+
+        let settings = UserSettings::load(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(settings.resolution, (1280, 1080));
+        assert_eq!(settings.music_volume, 1.0);
+        assert!(!settings.vsync);
+    }
+
+    #[test]
+    fn offered_resolutions_fit_the_window_constraints() {
+        for resolution in RESOLUTIONS {
+            let settings = UserSettings {
+                resolution,
+                ..Default::default()
+            };
+
+            assert_eq!(settings.sanitized().resolution, resolution);
+        }
+    }
+}
@@ -196,3 +196,30 @@ pub const ALL_KEYS: [KeyCode; 194] = [
     KeyCode::F34,
     KeyCode::F35,
 ];
+
+/// The glyph drawn on a shooting key, for any key a pool can hold.
+pub fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::ArrowUp => "^".to_string(),
+        KeyCode::ArrowDown => "v".to_string(),
+        KeyCode::ArrowLeft => "<".to_string(),
+        KeyCode::ArrowRight => ">".to_string(),
+        KeyCode::Space => "_".to_string(),
+        _ => {
+            let name = format!("{:?}", key);
+            name.strip_prefix("Key")
+                .or_else(|| name.strip_prefix("Digit"))
+                .unwrap_or(&name)
+                .to_string()
+        }
+    }
+}
+
+/// The name a key is saved under in the settings file.
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+pub fn key_from_name(name: &str) -> Option<KeyCode> {
+    ALL_KEYS.into_iter().find(|key| key_name(*key) == name)
+}
@@ -1,73 +1,248 @@
 use crate::{
     ClientChannel, ClientMessage, GRID_SIZE,
+    input::{self, ComboPress, InputDevice},
+    local_duel::LocalDuelKey,
     player::{PlayerSelection, ShootingLock},
-    shooting::keycode::ALL_KEYS,
+    practice::{PracticeMatch, PracticeShot},
+    replay::{ReplayRecorder, ReplayedKeyPress},
+    rhythm::{BeatClock, BeatGrade, ComboMeter, InputCalibration},
+    series::SERIES_LENGTHS,
+    settings::UserSettings,
+    shooting::sequence::KeySequence,
 };
 use bevy::prelude::*;
 use bevy_aseprite_ultra::prelude::*;
 use bevy_quinnet::client::QuinnetClient;
-use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 
 pub mod keycode;
+pub mod sequence;
+
+pub const MIN_COMBO_LENGTH: usize = 3;
+pub const MAX_COMBO_LENGTH: usize = 10;
+
+const KEY_SPACING: f32 = 7. + 64.;
+
+/// The keys a match draws its combos from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum KeyPool {
+    #[default]
+    Qwer,
+    Wasd,
+    Arrows,
+    Hard,
+    Uiop,
+}
+
+impl KeyPool {
+    pub const ALL: [KeyPool; 5] = [
+        KeyPool::Qwer,
+        KeyPool::Wasd,
+        KeyPool::Arrows,
+        KeyPool::Hard,
+        KeyPool::Uiop,
+    ];
+
+    pub fn keys(&self) -> &'static [KeyCode] {
+        match self {
+            KeyPool::Qwer => &[KeyCode::KeyQ, KeyCode::KeyW, KeyCode::KeyE, KeyCode::KeyR],
+            KeyPool::Wasd => &[KeyCode::KeyW, KeyCode::KeyA, KeyCode::KeyS, KeyCode::KeyD],
+            KeyPool::Arrows => &[
+                KeyCode::ArrowUp,
+                KeyCode::ArrowLeft,
+                KeyCode::ArrowDown,
+                KeyCode::ArrowRight,
+            ],
+            KeyPool::Hard => &[
+                KeyCode::KeyQ,
+                KeyCode::KeyW,
+                KeyCode::KeyE,
+                KeyCode::KeyR,
+                KeyCode::KeyA,
+                KeyCode::KeyS,
+                KeyCode::KeyD,
+                KeyCode::KeyF,
+            ],
+            KeyPool::Uiop => &[KeyCode::KeyU, KeyCode::KeyI, KeyCode::KeyO, KeyCode::KeyP],
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            KeyPool::Qwer => "QWER",
+            KeyPool::Wasd => "WASD",
+            KeyPool::Arrows => "Arrows",
+            KeyPool::Hard => "Hard",
+            KeyPool::Uiop => "UIOP",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|pool| pool == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+/// Chosen by the host before the match starts. The server hands them to the
+/// joining player along with the start signal, so both sides play the same rules.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchSettings {
+    pub key_pool: KeyPool,
+    pub combo_length: usize,
+    /// Rounds in the series, one of `SERIES_LENGTHS`.
+    #[serde(default = "single_round")]
+    pub best_of: usize,
+    /// Listed in the lobby browser while the match waits for an opponent.
+    #[serde(default)]
+    pub public: bool,
+}
+
+fn single_round() -> usize {
+    1
+}
+
+impl Default for MatchSettings {
+    fn default() -> Self {
+        Self {
+            key_pool: KeyPool::Qwer,
+            combo_length: 5,
+            best_of: 1,
+            public: false,
+        }
+    }
+}
+
+impl MatchSettings {
+    /// Settings as they come off the wire, with the combo length kept in range.
+    pub fn sanitized(self) -> Self {
+        Self {
+            combo_length: self.combo_length.clamp(MIN_COMBO_LENGTH, MAX_COMBO_LENGTH),
+            best_of: if SERIES_LENGTHS.contains(&self.best_of) {
+                self.best_of
+            } else {
+                1
+            },
+            ..self
+        }
+    }
+
+    pub fn next_best_of(&mut self) {
+        let index = SERIES_LENGTHS
+            .iter()
+            .position(|best_of| *best_of == self.best_of)
+            .map_or(0, |index| (index + 1) % SERIES_LENGTHS.len());
 
-pub const KEY_POOL: [KeyCode; 4] = [KeyCode::KeyQ, KeyCode::KeyW, KeyCode::KeyE, KeyCode::KeyR];
+        self.best_of = SERIES_LENGTHS[index];
+    }
+
+    pub fn next_combo_length(&mut self) {
+        self.combo_length = if self.combo_length >= MAX_COMBO_LENGTH {
+            MIN_COMBO_LENGTH
+        } else {
+            self.combo_length + 1
+        };
+    }
+}
 
 #[derive(Resource, Debug, Clone)]
 pub struct ShootingStates {
-    pub data: [ShootingData; 5],
+    pub settings: MatchSettings,
+    pub sequence: KeySequence,
+    pub data: Vec<ShootingData>,
     pub current_key_index: usize,
     pub wrong_count: usize,
 }
 
 impl Default for ShootingStates {
     fn default() -> Self {
-        let data = [(); 5].map(|_| ShootingData {
-            key: KEY_POOL[rand::rng().random_range(0..KEY_POOL.len())],
-            is_pressed_correct: false,
-        });
-
-        ShootingStates {
-            data,
+        let mut shooting_states = ShootingStates {
+            settings: MatchSettings::default(),
+            sequence: KeySequence::new(rand::random()),
+            data: Vec::new(),
             current_key_index: 0,
             wrong_count: 0,
-        }
+        };
+        shooting_states.reset();
+        shooting_states
     }
 }
 
 impl ShootingStates {
+    /// Takes on the rules and key seed of the match about to start.
+    pub fn configure(&mut self, settings: MatchSettings, seed: u64) {
+        self.settings = settings.sanitized();
+        self.sequence = KeySequence::new(seed);
+        self.reset();
+    }
+
     pub fn is_last_key(&self) -> bool {
-        self.current_key_index == 5
+        self.current_key_index == self.data.len()
     }
 
     pub fn reset_current_key_index(&mut self) {
         self.current_key_index = 0;
     }
 
+    pub fn keys_left(&self) -> usize {
+        self.data.len() - self.current_key_index
+    }
+
+    /// Counts the combo as typed in full, whatever keys are left.
+    pub fn complete_combo(&mut self) {
+        self.current_key_index = self.data.len();
+    }
+
     pub fn next_key(&mut self) {
-        self.current_key_index = (self.current_key_index + 1).clamp(0, 5);
+        self.current_key_index = (self.current_key_index + 1).clamp(0, self.data.len());
     }
 
     pub fn wrong_key_increment(&mut self) {
         self.wrong_count += 1;
     }
 
-    pub fn randomize_keys(&mut self) {
+    pub fn next_combo(&mut self) {
+        self.data = self
+            .sequence
+            .next_combo(&self.settings)
+            .into_iter()
+            .map(|key| ShootingData {
+                key,
+                is_pressed_correct: false,
+            })
+            .collect();
+    }
+
+    /// Deals the combo just missed again, without moving on in the sequence.
+    pub fn retry_combo(&mut self) {
         for data in self.data.iter_mut() {
-            data.key = KEY_POOL[rand::rng().random_range(0..KEY_POOL.len())];
             data.is_pressed_correct = false;
         }
     }
 
     pub fn reset(&mut self) {
-        self.data = [(); 5].map(|_| ShootingData {
-            key: KEY_POOL[rand::rng().random_range(0..KEY_POOL.len())],
-            is_pressed_correct: false,
-        });
+        self.sequence.restart();
+        self.next_combo();
         self.current_key_index = 0;
         self.wrong_count = 0;
     }
 }
 
+/// Where a key row is centred: where the default five keys always sat, or
+/// under its own player in a local duel.
+pub fn key_row_center_x(local_duel: bool, player: usize) -> f32 {
+    match (local_duel, player) {
+        (false, _) => (-GRID_SIZE * 5.) + 2. * KEY_SPACING,
+        (true, 1) => -(GRID_SIZE * 10.),
+        (true, _) => GRID_SIZE * 10.,
+    }
+}
+
+fn shooting_key_position(index: usize, combo_length: usize, center_x: f32) -> Vec3 {
+    let offset = index as f32 - (combo_length as f32 - 1.) / 2.;
+
+    Vec3::new(center_x + offset * KEY_SPACING, -(GRID_SIZE * 1.), 100.)
+}
+
 #[derive(Component, Debug, Clone)]
 pub struct ShootingKeyIndex(pub usize);
 
@@ -80,8 +255,33 @@ pub struct ShootingData {
 #[derive(Component)]
 pub struct ShootingKey;
 
+/// The combo symbol a key's label shows, redrawn when the input device changes.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ShootingKeyGlyph(pub KeyCode);
+
+/// Redraws the given player's key row: the next combo after a shot, the same
+/// one again after a wrong key.
 #[derive(Event)]
-pub struct ResetKeysEvent;
+pub struct ResetKeysEvent {
+    pub player: usize,
+    pub retry: bool,
+}
+
+impl ResetKeysEvent {
+    pub fn next_combo(player: usize) -> Self {
+        Self {
+            player,
+            retry: false,
+        }
+    }
+
+    pub fn retry(player: usize) -> Self {
+        Self {
+            player,
+            retry: true,
+        }
+    }
+}
 
 #[derive(Event, Default)]
 pub struct CheckShootingKeyEvent(pub usize);
@@ -91,22 +291,24 @@ pub struct ShootingEvent {
     pub player: usize,
 }
 
-pub fn spawn_shooting_keys(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    shooting_states: Res<ShootingStates>,
+/// Spawns one row of combo keys centred on `center_x`. `marker` goes on every
+/// key so a second row can be told apart from the first.
+pub fn spawn_key_row(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    shooting_states: &ShootingStates,
+    glyph: impl Fn(KeyCode) -> String,
+    center_x: f32,
+    marker: impl Bundle + Clone,
 ) {
     for (i, data) in shooting_states.data.iter().enumerate() {
-        let pos = Vec3::new(
-            (-GRID_SIZE * 5.) + (i as f32 * (7. + 64.)),
-            -(GRID_SIZE * 1.),
-            100.,
-        );
+        let pos = shooting_key_position(i, shooting_states.data.len(), center_x);
 
         commands
             .spawn((
                 ShootingKey,
                 ShootingKeyIndex(i),
+                marker.clone(),
                 AseAnimation {
                     aseprite: asset_server.load("sprites/Key.aseprite"),
                     animation: Animation::tag("idle").with_speed(1.),
@@ -115,16 +317,9 @@ pub fn spawn_shooting_keys(
                 Sprite::default(),
             ))
             .with_children(|parent| {
-                let key_label = match data.key {
-                    KeyCode::KeyQ => "Q",
-                    KeyCode::KeyW => "W",
-                    KeyCode::KeyE => "E",
-                    KeyCode::KeyR => "R",
-                    _ => "",
-                };
-
                 parent.spawn((
-                    Text2d::new(key_label),
+                    ShootingKeyGlyph(data.key),
+                    Text2d::new(glyph(data.key)),
                     TextColor(Color::WHITE),
                     TextLayout::new_with_justify(JustifyText::Center),
                     TextFont {
@@ -138,6 +333,55 @@ pub fn spawn_shooting_keys(
     }
 }
 
+/// Flashes the key at `index` of a row with the "Correct" or "InCorrect" tag.
+pub fn play_key_animation<'a>(
+    keys: impl Iterator<Item = (Mut<'a, AseAnimation>, &'a ShootingKeyIndex)>,
+    index: usize,
+    tag: &str,
+) {
+    for (mut shooting_key_anim, key_index) in keys {
+        if key_index.0 == index {
+            shooting_key_anim.animation = Animation::tag(tag)
+                .with_speed(1.)
+                .with_repeat(AnimationRepeat::Loop);
+        }
+    }
+}
+
+/// The device key glyphs are drawn for. A local duel is played on the keyboard.
+fn glyph_device(input_device: InputDevice, practice_match: &PracticeMatch) -> InputDevice {
+    if practice_match.local_duel {
+        InputDevice::Keyboard
+    } else {
+        input_device
+    }
+}
+
+pub fn spawn_shooting_keys(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    shooting_states: Res<ShootingStates>,
+    input_device: Res<InputDevice>,
+    user_settings: Res<UserSettings>,
+    practice_match: Res<PracticeMatch>,
+) {
+    spawn_key_row(
+        &mut commands,
+        &asset_server,
+        &shooting_states,
+        |key| {
+            input::combo_glyph(
+                shooting_states.settings.key_pool,
+                key,
+                glyph_device(*input_device, &practice_match),
+                &user_settings,
+            )
+        },
+        key_row_center_x(practice_match.local_duel, 1),
+        (),
+    );
+}
+
 pub fn despawn_shooting_keys(
     mut commands: Commands,
     shooting_keys_query: Query<Entity, With<ShootingKey>>,
@@ -147,45 +391,92 @@ pub fn despawn_shooting_keys(
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn shooting_key_input(
-    mut shooting_key_query: Query<(&mut AseAnimation, &ShootingKeyIndex), With<ShootingKey>>,
+    mut shooting_key_query: Query<
+        (&mut AseAnimation, &ShootingKeyIndex),
+        (With<ShootingKey>, Without<LocalDuelKey>),
+    >,
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
     mut shooting_states: ResMut<ShootingStates>,
     mut reset_key_event: EventWriter<ResetKeysEvent>,
     player_slecrion: Res<PlayerSelection>,
     mut client: ResMut<QuinnetClient>,
     mut shooting_lock: ResMut<ShootingLock>,
+    practice_match: Res<PracticeMatch>,
+    mut practice_shot: EventWriter<PracticeShot>,
+    beat_clock: Res<BeatClock>,
+    calibration: Res<InputCalibration>,
+    mut combo_meter: ResMut<ComboMeter>,
+    user_settings: Res<UserSettings>,
+    mut replay_recorder: ResMut<ReplayRecorder>,
 ) {
     let current_key_index = shooting_states.current_key_index;
+    let key_pool = shooting_states.settings.key_pool;
+
+    // On a shared keyboard the other player's keys are none of our business.
+    let press = |expected: KeyCode| {
+        if practice_match.local_duel {
+            input::pool_press(key_pool, expected, &keyboard_input, &user_settings)
+        } else {
+            input::combo_press(
+                key_pool,
+                expected,
+                &keyboard_input,
+                &gamepads,
+                &user_settings,
+            )
+        }
+    };
+
+    if let Some(data) = shooting_states.data.get_mut(current_key_index)
+        && let Some(press) = press(data.key)
+    {
+        if !practice_match.active
+            && let Some(replay) = &mut replay_recorder.0
+        {
+            replay.record_key_press(data.key, press == ComboPress::Correct);
+        }
 
-    if let Some(data) = shooting_states.data.get_mut(current_key_index) {
-        if keyboard_input.any_just_pressed(ALL_KEYS) {
-            if keyboard_input.just_pressed(data.key) {
-                for (mut shooting_key_anim, key_index) in shooting_key_query.iter_mut() {
-                    if key_index.0 == current_key_index {
-                        shooting_key_anim.animation = Animation::tag("Correct")
-                            .with_speed(1.)
-                            .with_repeat(AnimationRepeat::Loop);
-                    }
-                }
+        if press == ComboPress::Correct {
+            play_key_animation(shooting_key_query.iter_mut(), current_key_index, "Correct");
 
-                data.is_pressed_correct = true;
-                shooting_states.next_key();
-            } else {
-                for (mut shooting_key_anim, key_index) in shooting_key_query.iter_mut() {
-                    if key_index.0 == current_key_index {
-                        shooting_key_anim.animation = Animation::tag("InCorrect")
-                            .with_speed(1.)
-                            .with_repeat(AnimationRepeat::Loop);
-                    }
-                }
+            if !practice_match.local_duel
+                && let Some(grade) = beat_clock.grade(&calibration)
+            {
+                combo_meter.record(grade);
+            }
+
+            data.is_pressed_correct = true;
+            shooting_states.next_key();
+
+            // A full combo meter fires the shot without its last key.
+            if shooting_states.keys_left() == 1 && combo_meter.spend_overdrive() {
+                shooting_states.complete_combo();
+            }
+        } else {
+            play_key_animation(
+                shooting_key_query.iter_mut(),
+                current_key_index,
+                "InCorrect",
+            );
 
-                shooting_states.wrong_key_increment();
-                shooting_states.reset_current_key_index();
-                reset_key_event.write(ResetKeysEvent);
+            if !practice_match.local_duel {
+                combo_meter.record(BeatGrade::Miss);
             }
 
-            if shooting_states.is_last_key() {
+            shooting_states.wrong_key_increment();
+            shooting_states.reset_current_key_index();
+            reset_key_event.write(ResetKeysEvent::retry(player_slecrion.0));
+        }
+
+        if shooting_states.is_last_key() {
+            if practice_match.active {
+                practice_shot.write(PracticeShot {
+                    player: player_slecrion.0,
+                });
+            } else {
                 shooting_lock.lock();
 
                 if shooting_lock.is_locked() {
@@ -199,66 +490,177 @@ pub fn shooting_key_input(
                 }
 
                 shooting_lock.unlock();
-
-                shooting_states.reset_current_key_index();
             }
+
+            shooting_states.reset_current_key_index();
+        }
+    }
+}
+
+/// Plays the recorded player's key presses back onto their key row. The shot
+/// itself comes from the replayed server messages, like it did live.
+#[allow(clippy::type_complexity)]
+pub fn replayed_key_input(
+    mut replayed_key_press: EventReader<ReplayedKeyPress>,
+    mut shooting_key_query: Query<
+        (&mut AseAnimation, &ShootingKeyIndex),
+        (With<ShootingKey>, Without<LocalDuelKey>),
+    >,
+    mut shooting_states: ResMut<ShootingStates>,
+    mut reset_key_event: EventWriter<ResetKeysEvent>,
+    player_selection: Res<PlayerSelection>,
+) {
+    for press in replayed_key_press.read() {
+        let current_key_index = shooting_states.current_key_index;
+
+        let Some(data) = shooting_states.data.get_mut(current_key_index) else {
+            continue;
+        };
+
+        if press.correct {
+            play_key_animation(shooting_key_query.iter_mut(), current_key_index, "Correct");
+
+            data.is_pressed_correct = true;
+            shooting_states.next_key();
+        } else {
+            play_key_animation(
+                shooting_key_query.iter_mut(),
+                current_key_index,
+                "InCorrect",
+            );
+
+            shooting_states.wrong_key_increment();
+            shooting_states.reset_current_key_index();
+            reset_key_event.write(ResetKeysEvent::retry(player_selection.0));
+        }
+
+        if shooting_states.is_last_key() {
+            shooting_states.reset_current_key_index();
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_new_shooting_keys(
     mut reset_key_event: EventReader<ResetKeysEvent>,
     mut commands: Commands,
-    shooting_keys_query: Query<Entity, With<ShootingKey>>,
+    shooting_keys_query: Query<Entity, (With<ShootingKey>, Without<LocalDuelKey>)>,
     asset_server: Res<AssetServer>,
     mut shooting_states: ResMut<ShootingStates>,
+    input_device: Res<InputDevice>,
+    user_settings: Res<UserSettings>,
+    player_selection: Res<PlayerSelection>,
+    practice_match: Res<PracticeMatch>,
 ) {
-    for _ in reset_key_event.read() {
+    for event in reset_key_event
+        .read()
+        .filter(|event| event.player == player_selection.0)
+    {
         for entity in shooting_keys_query.iter() {
             commands.entity(entity).despawn();
         }
 
-        shooting_states.randomize_keys();
-
-        for (i, data) in shooting_states.data.iter().enumerate() {
-            let pos = Vec3::new(
-                (-GRID_SIZE * 5.) + (i as f32 * (7. + 64.)),
-                -(GRID_SIZE * 1.),
-                100.,
-            );
+        // An overdrive shot leaves the replayed row short of its last key.
+        shooting_states.reset_current_key_index();
 
-            commands
-                .spawn((
-                    ShootingKey,
-                    ShootingKeyIndex(i),
-                    AseAnimation {
-                        aseprite: asset_server.load("sprites/Key.aseprite"),
-                        animation: Animation::tag("idle").with_speed(1.),
-                    },
-                    Transform::from_translation(pos),
-                    Sprite::default(),
-                ))
-                .with_children(|parent| {
-                    let key_label = match data.key {
-                        KeyCode::KeyQ => "Q",
-                        KeyCode::KeyW => "W",
-                        KeyCode::KeyE => "E",
-                        KeyCode::KeyR => "R",
-                        _ => "",
-                    };
-
-                    parent.spawn((
-                        Text2d::new(key_label),
-                        TextColor(Color::WHITE),
-                        TextLayout::new_with_justify(JustifyText::Center),
-                        TextFont {
-                            font: asset_server.load("fonts/pixeloid_mono.ttf"),
-                            font_size: 36.,
-                            ..Default::default()
-                        },
-                        Transform::from_xyz(0., 0., 200.),
-                    ));
-                });
+        if event.retry {
+            shooting_states.retry_combo();
+        } else {
+            shooting_states.next_combo();
         }
+
+        spawn_key_row(
+            &mut commands,
+            &asset_server,
+            &shooting_states,
+            |key| {
+                input::combo_glyph(
+                    shooting_states.settings.key_pool,
+                    key,
+                    glyph_device(*input_device, &practice_match),
+                    &user_settings,
+                )
+            },
+            key_row_center_x(practice_match.local_duel, 1),
+            (),
+        );
+    }
+}
+
+pub fn update_shooting_key_glyphs(
+    input_device: Res<InputDevice>,
+    shooting_states: Res<ShootingStates>,
+    user_settings: Res<UserSettings>,
+    practice_match: Res<PracticeMatch>,
+    mut glyph_query: Query<(&mut Text2d, &ShootingKeyGlyph)>,
+) {
+    if !input_device.is_changed() || practice_match.local_duel {
+        return;
+    }
+
+    for (mut text, glyph) in glyph_query.iter_mut() {
+        *text = input::combo_glyph(
+            shooting_states.settings.key_pool,
+            glyph.0,
+            *input_device,
+            &user_settings,
+        )
+        .into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_retried_combo_keeps_its_keys_and_place_in_the_sequence() {
+        let mut shooting_states = ShootingStates::default();
+        shooting_states.configure(MatchSettings::default(), 5);
+
+        let keys: Vec<_> = shooting_states.data.iter().map(|data| data.key).collect();
+        let combo_index = shooting_states.sequence.combo_index;
+
+        shooting_states.data[0].is_pressed_correct = true;
+        shooting_states.next_key();
+        shooting_states.wrong_key_increment();
+        shooting_states.reset_current_key_index();
+        shooting_states.retry_combo();
+
+        assert_eq!(shooting_states.sequence.combo_index, combo_index);
+        assert_eq!(
+            shooting_states
+                .data
+                .iter()
+                .map(|data| data.key)
+                .collect::<Vec<_>>(),
+            keys
+        );
+        assert!(
+            shooting_states
+                .data
+                .iter()
+                .all(|data| !data.is_pressed_correct)
+        );
+        assert_eq!(shooting_states.current_key_index, 0);
+    }
+
+    #[test]
+    fn a_fired_combo_moves_on_to_the_next() {
+        let mut shooting_states = ShootingStates::default();
+        shooting_states.configure(MatchSettings::default(), 5);
+
+        let combo_index = shooting_states.sequence.combo_index;
+        shooting_states.next_combo();
+
+        assert_eq!(shooting_states.sequence.combo_index, combo_index + 1);
+        assert_eq!(
+            shooting_states
+                .data
+                .iter()
+                .map(|data| data.key)
+                .collect::<Vec<_>>(),
+            sequence::combo_keys(5, combo_index, &shooting_states.settings)
+        );
     }
 }
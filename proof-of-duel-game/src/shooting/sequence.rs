@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+
+use crate::shooting::MatchSettings;
+
+/// The combos of one match, drawn from the seed the server hands out. Combo N
+/// only depends on the seed and N, so both duelists face the same keys in the
+/// same order and a transcript is enough to rebuild them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeySequence {
+    pub seed: u64,
+    pub combo_index: u64,
+}
+
+impl KeySequence {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            combo_index: 0,
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.combo_index = 0;
+    }
+
+    /// Draws the next combo and moves on to the one after it. Only called once
+    /// a combo has been fired, a missed combo is dealt again as it was.
+    pub fn next_combo(&mut self, settings: &MatchSettings) -> Vec<KeyCode> {
+        let combo = combo_keys(self.seed, self.combo_index, settings);
+        self.combo_index += 1;
+        combo
+    }
+}
+
+/// The keys of combo `combo_index` in a match seeded with `seed`.
+pub fn combo_keys(seed: u64, combo_index: u64, settings: &MatchSettings) -> Vec<KeyCode> {
+    let keys = settings.key_pool.keys();
+    let mut state = seed ^ combo_index.wrapping_mul(0xD1B5_4A32_D192_ED03);
+
+    (0..settings.combo_length)
+        .map(|_| keys[(split_mix64(&mut state) % keys.len() as u64) as usize])
+        .collect()
+}
+
+/// SplitMix64, spelled out here so the sequence never changes with the `rand`
+/// version and old transcripts keep replaying to the same keys.
+fn split_mix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shooting::KeyPool;
+
+    fn settings(key_pool: KeyPool, combo_length: usize) -> MatchSettings {
+        MatchSettings {
+            key_pool,
+            combo_length,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn same_seed_and_index_give_the_same_combo() {
+        let settings = settings(KeyPool::Hard, 8);
+
+        for combo_index in 0..32 {
+            assert_eq!(
+                combo_keys(42, combo_index, &settings),
+                combo_keys(42, combo_index, &settings)
+            );
+        }
+    }
+
+    #[test]
+    fn combos_are_pinned_to_the_seed() {
+        let settings = settings(KeyPool::Qwer, 5);
+
+        // Changing these means old transcripts no longer replay to the keys
+        // that were actually played.
+        assert_eq!(
+            combo_keys(0, 0, &settings),
+            vec![
+                KeyCode::KeyR,
+                KeyCode::KeyQ,
+                KeyCode::KeyR,
+                KeyCode::KeyQ,
+                KeyCode::KeyR
+            ]
+        );
+    }
+
+    #[test]
+    fn different_seeds_give_different_combos() {
+        let settings = settings(KeyPool::Hard, 10);
+
+        assert_ne!(combo_keys(1, 0, &settings), combo_keys(2, 0, &settings));
+    }
+
+    #[test]
+    fn combos_use_the_pool_and_length() {
+        for key_pool in KeyPool::ALL {
+            let settings = settings(key_pool, 7);
+            let combo = combo_keys(7, 3, &settings);
+
+            assert_eq!(combo.len(), 7);
+            assert!(combo.iter().all(|key| key_pool.keys().contains(key)));
+        }
+    }
+
+    #[test]
+    fn sequence_follows_combo_keys_and_restarts() {
+        let settings = settings(KeyPool::Wasd, 4);
+        let mut sequence = KeySequence::new(99);
+
+        let drawn: Vec<_> = (0..5).map(|_| sequence.next_combo(&settings)).collect();
+        for (combo_index, combo) in drawn.iter().enumerate() {
+            assert_eq!(*combo, combo_keys(99, combo_index as u64, &settings));
+        }
+
+        sequence.restart();
+        assert_eq!(sequence.next_combo(&settings), drawn[0]);
+    }
+}
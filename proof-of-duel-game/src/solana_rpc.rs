@@ -0,0 +1,291 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use bevy::prelude::*;
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tungstenite::{Message, stream::MaybeTlsStream};
+
+use crate::{
+    bridge::{BridgeMessage, BridgeSender},
+    stats::PlayerRecord,
+};
+
+pub const PROGRAM_ID: &str = "GsetEEa4YtiaFcQP4NnqM2vBtJrtbFjKBgfdszMK8ePC";
+
+const PLAYER_SEED: &[u8] = b"player";
+const PLAYER_ACCOUNT_LEN: usize = 8 + 8 + 8;
+
+const RECONNECT_BASE: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(60);
+
+/// Reads `Player` accounts of the on-chain program straight from a Solana RPC node.
+#[derive(Debug, Clone)]
+pub struct SolanaRpc {
+    pub rpc_url: String,
+    pub ws_url: String,
+    pub program_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountInfoResult {
+    value: Option<AccountInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountInfo {
+    data: (String, String),
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountNotification {
+    params: AccountNotificationParams,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountNotificationParams {
+    result: AccountInfoResult,
+}
+
+fn decode_pubkey(value: &str) -> Option<[u8; 32]> {
+    bs58::decode(value).into_vec().ok()?.try_into().ok()
+}
+
+fn is_on_curve(bytes: &[u8; 32]) -> bool {
+    CompressedEdwardsY(*bytes).decompress().is_some()
+}
+
+/// The same derivation as `Pubkey::find_program_address`.
+pub fn find_program_address(seeds: &[&[u8]], program_id: &[u8; 32]) -> Option<[u8; 32]> {
+    (0..=u8::MAX).rev().find_map(|bump| {
+        let mut hasher = Sha256::new();
+        for seed in seeds {
+            hasher.update(seed);
+        }
+        hasher.update([bump]);
+        hasher.update(program_id);
+        hasher.update(b"ProgramDerivedAddress");
+
+        let address: [u8; 32] = hasher.finalize().into();
+        (!is_on_curve(&address)).then_some(address)
+    })
+}
+
+fn account_discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("account:{name}").as_bytes());
+    hash[..8].try_into().expect("sha256 is 32 bytes")
+}
+
+/// Decodes a base64 `Player` account into `(win, loss)`.
+pub fn decode_player(data: &str) -> Option<(u32, u32)> {
+    let bytes = STANDARD.decode(data).ok()?;
+
+    if bytes.len() < PLAYER_ACCOUNT_LEN || bytes[..8] != account_discriminator("Player") {
+        return None;
+    }
+
+    let win = u64::from_le_bytes(bytes[8..16].try_into().ok()?);
+    let loss = u64::from_le_bytes(bytes[16..24].try_into().ok()?);
+
+    Some((
+        u32::try_from(win).unwrap_or(u32::MAX),
+        u32::try_from(loss).unwrap_or(u32::MAX),
+    ))
+}
+
+/// `None` when the wallet has no `Player` account yet.
+fn decode_account(value: Option<AccountInfo>) -> Result<Option<(u32, u32)>, String> {
+    match value {
+        None => Ok(None),
+        Some(account) => decode_player(&account.data.0)
+            .map(Some)
+            .ok_or_else(|| "account is not a Player account".to_string()),
+    }
+}
+
+impl SolanaRpc {
+    pub fn player_address(&self, wallet: &str) -> Option<String> {
+        let wallet = decode_pubkey(wallet)?;
+        let program_id = decode_pubkey(&self.program_id)?;
+        let address = find_program_address(&[PLAYER_SEED, &wallet], &program_id)?;
+
+        Some(bs58::encode(address).into_string())
+    }
+
+    /// Fetches the player's `(win, loss)` record with `getAccountInfo`, `None`
+    /// if the account hasn't been initialized.
+    pub fn get_player_record(&self, wallet: &str) -> Result<Option<(u32, u32)>, String> {
+        let address = self
+            .player_address(wallet)
+            .ok_or_else(|| format!("{wallet} is not a valid wallet"))?;
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getAccountInfo",
+            "params": [address, { "encoding": "base64", "commitment": "confirmed" }],
+        });
+
+        let response: RpcResponse<AccountInfoResult> = ureq::post(&self.rpc_url)
+            .send_json(&request)
+            .map_err(|e| e.to_string())?
+            .body_mut()
+            .read_json()
+            .map_err(|e| e.to_string())?;
+
+        match (response.result, response.error) {
+            (Some(result), _) => decode_account(result.value),
+            (None, Some(error)) => Err(error.to_string()),
+            (None, None) => Err("empty RPC response".to_string()),
+        }
+    }
+
+    /// Watches the player's account with `accountSubscribe` on a background
+    /// thread and sends every change over the bridge until `stop` is set. A
+    /// dropped connection is reopened with capped backoff and the account is
+    /// read again, so changes made while it was down aren't missed.
+    pub fn subscribe_player(&self, wallet: String, bridge: BridgeSender, stop: Arc<AtomicBool>) {
+        let Some(address) = self.player_address(&wallet) else {
+            error!("❌ {} is not a valid wallet", wallet);
+            return;
+        };
+        let rpc = self.clone();
+
+        std::thread::spawn(move || {
+            let mut delay = RECONNECT_BASE;
+            let mut reconnecting = false;
+
+            while !stop.load(Ordering::Relaxed) {
+                let subscription =
+                    rpc.watch_player(&address, &wallet, &bridge, &stop, reconnecting);
+
+                match subscription {
+                    SubscriptionEnd::Stopped => return,
+                    SubscriptionEnd::Failed(e) => {
+                        warn!(
+                            "Could not subscribe to {}: {}, retrying in {:?}",
+                            address, e, delay
+                        );
+                    }
+                    SubscriptionEnd::Lost(e) => {
+                        warn!("Subscription to {} closed: {}, reconnecting", address, e);
+                        delay = RECONNECT_BASE;
+                    }
+                }
+
+                reconnecting = true;
+                sleep_unless_stopped(delay, &stop);
+                delay = (delay * 2).min(RECONNECT_MAX);
+            }
+        });
+    }
+
+    /// One websocket connection's worth of `subscribe_player`. After a
+    /// reconnect the current record is fetched once to catch up.
+    fn watch_player(
+        &self,
+        address: &str,
+        wallet: &str,
+        bridge: &BridgeSender,
+        stop: &AtomicBool,
+        catch_up: bool,
+    ) -> SubscriptionEnd {
+        let (mut socket, _) = match tungstenite::connect(&self.ws_url) {
+            Ok(connection) => connection,
+            Err(e) => return SubscriptionEnd::Failed(e.to_string()),
+        };
+
+        // Wake up regularly so a stopped subscription doesn't linger.
+        let timeout = Some(Duration::from_secs(1));
+        let _ = match socket.get_ref() {
+            MaybeTlsStream::Plain(stream) => stream.set_read_timeout(timeout),
+            MaybeTlsStream::Rustls(stream) => stream.get_ref().set_read_timeout(timeout),
+            _ => Ok(()),
+        };
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "accountSubscribe",
+            "params": [address, { "encoding": "base64", "commitment": "confirmed" }],
+        });
+
+        if let Err(e) = socket.send(Message::text(request.to_string())) {
+            return SubscriptionEnd::Failed(e.to_string());
+        }
+
+        if catch_up && let Ok(Some((win, loss))) = self.get_player_record(wallet) {
+            bridge.send(BridgeMessage::StatsFetched {
+                public_key: wallet.to_string(),
+                record: Some(PlayerRecord::Found { win, loss }),
+            });
+        }
+
+        while !stop.load(Ordering::Relaxed) {
+            let message = match socket.read() {
+                Ok(message) => message,
+                Err(tungstenite::Error::Io(e)) if is_timeout(&e) => continue,
+                Err(e) => return SubscriptionEnd::Lost(e.to_string()),
+            };
+
+            let Message::Text(text) = message else {
+                continue;
+            };
+
+            let Ok(notification) = serde_json::from_str::<AccountNotification>(&text) else {
+                continue;
+            };
+
+            if let Ok(Some((win, loss))) = decode_account(notification.params.result.value) {
+                bridge.send(BridgeMessage::StatsFetched {
+                    public_key: wallet.to_string(),
+                    record: Some(PlayerRecord::Found { win, loss }),
+                });
+            }
+        }
+
+        let _ = socket.close(None);
+        SubscriptionEnd::Stopped
+    }
+}
+
+/// How a websocket connection in `subscribe_player` came to an end.
+enum SubscriptionEnd {
+    Stopped,
+    /// Never got as far as subscribing.
+    Failed(String),
+    /// Was subscribed until the connection dropped.
+    Lost(String),
+}
+
+/// Sleeps for `duration`, waking up early once `stop` is set.
+fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) {
+    let step = Duration::from_millis(250);
+    let mut slept = Duration::ZERO;
+
+    while slept < duration && !stop.load(Ordering::Relaxed) {
+        std::thread::sleep(step);
+        slept += step;
+    }
+}
+
+fn is_timeout(error: &std::io::Error) -> bool {
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+    )
+}
@@ -1,23 +1,82 @@
-use bevy::{audio::Volume, prelude::*};
+use bevy::{
+    audio::{AudioSink, Volume},
+    prelude::*,
+};
 
-use crate::MUSIC_VOLUME;
+use crate::{rhythm::BeatClock, settings::UserSettings};
+
+/// A music track and the tempo the beat clock follows while it plays.
+/// `offset_secs` is where the first beat falls in the file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MusicTrack {
+    pub path: &'static str,
+    pub bpm: f32,
+    pub offset_secs: f32,
+}
+
+pub const BG_MUSIC: MusicTrack = MusicTrack {
+    path: "musics/bro_turned_into_prog_mode.ogg",
+    bpm: 120.0,
+    offset_secs: 0.0,
+};
 
 #[derive(Component)]
 pub struct BackgroundMusic;
 
-pub fn play_bg_music(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn spawn_bg_music(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    track: MusicTrack,
+    user_settings: &UserSettings,
+) {
     commands.spawn((
         BackgroundMusic,
-        AudioPlayer::new(asset_server.load("musics/bro_turned_into_prog_mode.ogg")),
-        PlaybackSettings::ONCE.with_volume(Volume::Linear(MUSIC_VOLUME)),
+        AudioPlayer::new(asset_server.load(track.path)),
+        PlaybackSettings::ONCE.with_volume(Volume::Linear(user_settings.music_volume)),
     ));
 }
 
+pub fn play_bg_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut beat_clock: ResMut<BeatClock>,
+    user_settings: Res<UserSettings>,
+) {
+    spawn_bg_music(&mut commands, &asset_server, BG_MUSIC, &user_settings);
+    beat_clock.start(BG_MUSIC);
+}
+
+/// Starts the track over once it ends, and the beat clock with it. The sink
+/// can't report its position, so restarting both together keeps the beat grid
+/// on the music where `PlaybackSettings::LOOP` would let the clock drift.
+pub fn loop_bg_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut beat_clock: ResMut<BeatClock>,
+    user_settings: Res<UserSettings>,
+    music_query: Query<(Entity, &AudioSink), With<BackgroundMusic>>,
+) {
+    let Some(track) = beat_clock.track else {
+        return;
+    };
+
+    for (entity, sink) in music_query.iter() {
+        if sink.empty() {
+            commands.entity(entity).despawn();
+            spawn_bg_music(&mut commands, &asset_server, track, &user_settings);
+            beat_clock.start(track);
+        }
+    }
+}
+
 pub fn stop_playing_bg_music(
     mut commands: Commands,
     background_music_query: Query<Entity, With<BackgroundMusic>>,
+    mut beat_clock: ResMut<BeatClock>,
 ) {
     for entity in background_music_query.iter() {
         commands.entity(entity).despawn();
     }
+
+    beat_clock.stop();
 }
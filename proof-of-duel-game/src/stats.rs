@@ -1,39 +1,143 @@
-use std::sync::{Arc, LazyLock, RwLock};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
 
-use axum::{Json, http::StatusCode, response::IntoResponse};
+use axum::{Extension, Json, http::StatusCode, response::IntoResponse};
 use bevy::{prelude::*, tasks::AsyncComputeTaskPool};
 use serde::{Deserialize, Serialize};
 
-use crate::ui::profile::{DuelInfoPayload, ProfileData};
+use crate::{
+    bridge::{BridgeMessage, BridgeSender, OpponentStatsLoaded, StatsFetched, StatsUpdated},
+    config::GameConfig,
+    duel_profile::{DuelProfile, DuelProfileStatus},
+    player::{MatchPlayers, PlayerSelection},
+    solana_rpc::SolanaRpc,
+    ui::profile::{DuelInfoPayload, DuelInfoResponse, ProfileData},
+};
 
-#[derive(Default)]
-struct StatsState {
+#[derive(Resource, Default, Clone)]
+pub struct StatsData {
     pub win: u32,
     pub loss: u32,
 }
 
-static STATS_STATE: LazyLock<Arc<RwLock<StatsState>>> =
-    LazyLock::new(|| Arc::new(RwLock::new(StatsState::default())));
+impl StatsData {
+    pub fn reset(&mut self) {
+        self.win = 0;
+        self.loss = 0;
+    }
+}
 
+/// The on-chain win/loss record of the current opponent, if they have a wallet.
 #[derive(Resource, Default, Clone)]
-pub struct StatsData {
-    pub win: u32,
-    pub loss: u32,
+pub struct OpponentStats {
+    pub wallet: String,
+    pub record: Option<(u32, u32)>,
+}
+
+impl OpponentStats {
+    pub fn reset(&mut self) {
+        self.wallet.clear();
+        self.record = None;
+    }
+}
+
+/// What a stats lookup found for a wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerRecord {
+    Found {
+        win: u32,
+        loss: u32,
+    },
+    /// The wallet has no `Player` account on-chain yet.
+    Missing,
+}
+
+impl PlayerRecord {
+    pub fn win_loss(&self) -> (u32, u32) {
+        match self {
+            PlayerRecord::Found { win, loss } => (*win, *loss),
+            PlayerRecord::Missing => (0, 0),
+        }
+    }
+}
+
+const STATS_REFRESH_SECS: f32 = 3.0;
+const STATS_MAX_BACKOFF_SECS: f32 = 60.0;
+const STATS_OFFLINE_AFTER_FAILURES: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StatsFreshness {
+    #[default]
+    Fresh,
+    /// The last fetch failed, the numbers shown are from an earlier one.
+    Stale,
+    /// Several fetches in a row failed.
+    Offline,
 }
 
+impl StatsFreshness {
+    /// How fresh the shown stats are after `failures` failed fetches in a row.
+    fn after_failures(failures: u32) -> Self {
+        match failures {
+            0 => StatsFreshness::Fresh,
+            failures if failures < STATS_OFFLINE_AFTER_FAILURES => StatsFreshness::Stale,
+            _ => StatsFreshness::Offline,
+        }
+    }
+}
+
+/// How long to wait before the next fetch after `failures` failed ones:
+/// doubling from the refresh interval, up to `STATS_MAX_BACKOFF_SECS`.
+fn stats_backoff_secs(failures: u32) -> f32 {
+    (STATS_REFRESH_SECS * 2f32.powi(failures.min(8) as i32)).min(STATS_MAX_BACKOFF_SECS)
+}
+
+/// Polls `/api/duel-info` for the logged-in player's record, backing off
+/// exponentially while the frontend can't be reached.
 #[derive(Resource)]
-pub struct StatsStateWatcher {
+pub struct StatsClient {
     pub timer: Timer,
+    pub failures: u32,
+    pub in_flight: bool,
+    pub freshness: StatsFreshness,
 }
 
-impl Default for StatsStateWatcher {
+impl Default for StatsClient {
     fn default() -> Self {
         Self {
-            timer: Timer::from_seconds(3.0, TimerMode::Repeating),
+            timer: Timer::from_seconds(STATS_REFRESH_SECS, TimerMode::Once),
+            failures: 0,
+            in_flight: false,
+            freshness: StatsFreshness::default(),
         }
     }
 }
 
+impl StatsClient {
+    /// Fetches on the next frame instead of waiting out the timer.
+    pub fn refresh_now(&mut self) {
+        let duration = self.timer.duration();
+        self.timer.set_elapsed(duration);
+    }
+
+    fn succeeded(&mut self) {
+        self.failures = 0;
+        self.schedule();
+    }
+
+    fn failed(&mut self) {
+        self.failures += 1;
+        self.schedule();
+    }
+
+    fn schedule(&mut self) {
+        self.freshness = StatsFreshness::after_failures(self.failures);
+        self.timer = Timer::from_seconds(stats_backoff_secs(self.failures), TimerMode::Once);
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StatsPayload {
     pub win: u32,
@@ -46,66 +150,383 @@ pub struct StatsResponse {
     pub loss: u32,
 }
 
-pub async fn update_stats(Json(stats_payload): Json<StatsPayload>) -> impl IntoResponse {
-    let mut stats_state = STATS_STATE.write().unwrap();
-    stats_state.win = stats_payload.win;
-    stats_state.loss = stats_payload.loss;
+pub async fn update_stats(
+    Extension(bridge): Extension<BridgeSender>,
+    Json(stats_payload): Json<StatsPayload>,
+) -> impl IntoResponse {
+    bridge.send(BridgeMessage::StatsUpdated {
+        win: stats_payload.win,
+        loss: stats_payload.loss,
+    });
 
     (StatusCode::OK, "OK").into_response()
 }
 
-pub fn get_stats_scheduler(
+/// Where stats are read from: the frontend's `/api/duel-info`, or the chain
+/// itself when a Solana RPC is configured.
+#[derive(Debug, Clone)]
+pub enum StatsSource {
+    Api(String),
+    Rpc(SolanaRpc),
+}
+
+impl StatsSource {
+    pub fn from_config(config: &GameConfig) -> Self {
+        match config.solana_rpc() {
+            Some(rpc) => StatsSource::Rpc(rpc),
+            None => StatsSource::Api(config.api_url("/api/duel-info")),
+        }
+    }
+
+    pub fn get_record(&self, public_key: &str) -> Option<PlayerRecord> {
+        match self {
+            StatsSource::Api(url) => {
+                let response = ureq::post(url).send_json(DuelInfoPayload {
+                    public_key: public_key.to_string(),
+                    notify: false,
+                });
+
+                let (status, body) = match response {
+                    Ok(mut response) => (
+                        response.status().as_u16(),
+                        response.body_mut().read_to_string().unwrap_or_default(),
+                    ),
+                    Err(ureq::Error::StatusCode(status)) => (status, String::new()),
+                    Err(e) => {
+                        error!("❌ Error sending to RPC: {:?}", e);
+                        return None;
+                    }
+                };
+
+                match parse_duel_info(status, &body) {
+                    Ok(record) => Some(record),
+                    Err(e) => {
+                        error!("❌ {}", e);
+                        None
+                    }
+                }
+            }
+            StatsSource::Rpc(rpc) => match rpc.get_player_record(public_key) {
+                Ok(Some((win, loss))) => Some(PlayerRecord::Found { win, loss }),
+                Ok(None) => Some(PlayerRecord::Missing),
+                Err(e) => {
+                    error!("❌ Player account could not be read: {}", e);
+                    None
+                }
+            },
+        }
+    }
+}
+
+/// Reads an `/api/duel-info` answer: the record on a 200, no `Player`
+/// account on a 404.
+fn parse_duel_info(status: u16, body: &str) -> Result<PlayerRecord, String> {
+    match status {
+        200 => serde_json::from_str::<DuelInfoResponse>(body)
+            .map(|record| PlayerRecord::Found {
+                win: record.win,
+                loss: record.loss,
+            })
+            .map_err(|e| format!("Duel info could not be read: {e}")),
+        404 => Ok(PlayerRecord::Missing),
+        status => Err(format!("Duel info failed to load: {status}")),
+    }
+}
+
+/// Where the duel profile stands once a lookup found `record`.
+fn profile_status_after(record: PlayerRecord, status: &DuelProfileStatus) -> DuelProfileStatus {
+    match (record, status) {
+        (PlayerRecord::Found { .. }, DuelProfileStatus::Creating | DuelProfileStatus::Created) => {
+            DuelProfileStatus::Created
+        }
+        (PlayerRecord::Found { .. }, _) => DuelProfileStatus::Ready,
+        // Keep showing progress until the transaction lands.
+        (PlayerRecord::Missing, DuelProfileStatus::Creating) => DuelProfileStatus::Creating,
+        (PlayerRecord::Missing, DuelProfileStatus::Failed(e)) => {
+            DuelProfileStatus::Failed(e.clone())
+        }
+        (PlayerRecord::Missing, _) => DuelProfileStatus::Missing,
+    }
+}
+
+pub fn fetch_stats(
     time: Res<Time>,
-    mut watcher: ResMut<StatsStateWatcher>,
+    mut stats_client: ResMut<StatsClient>,
     profile_data: Res<ProfileData>,
+    bridge: Res<BridgeSender>,
+    config: Res<GameConfig>,
 ) {
-    if !profile_data.logged_in {
+    if !profile_data.is_online() || stats_client.in_flight {
         return;
     }
 
-    if !watcher.timer.tick(time.delta()).finished() {
+    if !stats_client.timer.tick(time.delta()).finished() {
         return;
     }
 
+    stats_client.in_flight = true;
+
     let thread_pool = AsyncComputeTaskPool::get();
     let public_key = profile_data.public_key.clone();
+    let bridge = bridge.clone();
+    let source = StatsSource::from_config(&config);
 
     thread_pool
         .spawn(async move {
-            let url = "http://localhost:3000/api/duel-info";
-            match ureq::post(url).send_json(DuelInfoPayload { public_key }) {
-                Ok(response) if response.status() == 200 => {
-                    info!("✅ Duel loss recorded successfully");
-                }
-                Ok(response) => {
-                    error!("❌ Duel loss failed to record: {}", response.status());
-                }
-                Err(e) => {
-                    error!("❌ Error sending to RPC: {:?}", e);
-                }
-            }
+            let record = source.get_record(&public_key);
+            bridge.send(BridgeMessage::StatsFetched { public_key, record });
         })
         .detach();
 }
 
-pub fn poll_stats_state(
-    time: Res<Time>,
-    mut watcher: ResMut<StatsStateWatcher>,
+pub fn apply_stats_fetched(
+    mut stats_fetched: EventReader<StatsFetched>,
+    mut stats_client: ResMut<StatsClient>,
     mut stats_data: ResMut<StatsData>,
+    mut duel_profile: ResMut<DuelProfile>,
     profile_data: Res<ProfileData>,
 ) {
-    if !profile_data.logged_in {
+    for event in stats_fetched.read() {
+        stats_client.in_flight = false;
+
+        // The player logged out or switched accounts while this was in flight.
+        if !profile_data.is_online() || event.public_key != profile_data.public_key {
+            stats_client.refresh_now();
+            continue;
+        }
+
+        let Some(record) = event.record else {
+            stats_client.failed();
+            continue;
+        };
+
+        stats_client.succeeded();
+
+        duel_profile.status = profile_status_after(record, &duel_profile.status);
+
+        let (win, loss) = record.win_loss();
+        if win != stats_data.win || loss != stats_data.loss {
+            stats_data.win = win;
+            stats_data.loss = loss;
+        }
+    }
+}
+
+pub fn refresh_stats(mut stats_client: ResMut<StatsClient>) {
+    stats_client.refresh_now();
+}
+
+pub fn apply_stats_updated(
+    mut stats_updated: EventReader<StatsUpdated>,
+    mut stats_data: ResMut<StatsData>,
+    profile_data: Res<ProfileData>,
+) {
+    for event in stats_updated.read() {
+        if !profile_data.is_online() {
+            continue;
+        }
+
+        if event.win != stats_data.win || event.loss != stats_data.loss {
+            stats_data.win = event.win;
+            stats_data.loss = event.loss;
+        }
+    }
+}
+
+pub fn fetch_opponent_stats(
+    match_players: Res<MatchPlayers>,
+    player_selection: Res<PlayerSelection>,
+    mut opponent_stats: ResMut<OpponentStats>,
+    profile_data: Res<ProfileData>,
+    bridge: Res<BridgeSender>,
+    config: Res<GameConfig>,
+) {
+    if !match_players.is_changed() || !profile_data.is_online() {
+        return;
+    }
+
+    let Some(opponent) = match_players.opponent_of(player_selection.0) else {
+        return;
+    };
+
+    if opponent.wallet.is_empty() || opponent.wallet == opponent_stats.wallet {
+        return;
+    }
+
+    opponent_stats.wallet = opponent.wallet.clone();
+    opponent_stats.record = None;
+
+    let thread_pool = AsyncComputeTaskPool::get();
+    let public_key = opponent.wallet.clone();
+    let bridge = bridge.clone();
+    let source = StatsSource::from_config(&config);
+
+    thread_pool
+        .spawn(async move {
+            if let Some(record) = source.get_record(&public_key) {
+                let (win, loss) = record.win_loss();
+                bridge.send(BridgeMessage::OpponentStatsLoaded {
+                    wallet: public_key,
+                    win,
+                    loss,
+                });
+            }
+        })
+        .detach();
+}
+
+pub fn apply_opponent_stats(
+    mut opponent_stats_loaded: EventReader<OpponentStatsLoaded>,
+    mut opponent_stats: ResMut<OpponentStats>,
+) {
+    for event in opponent_stats_loaded.read() {
+        // Ignore late answers for an opponent from a previous match.
+        if event.wallet == opponent_stats.wallet {
+            opponent_stats.record = Some((event.win, event.loss));
+        }
+    }
+}
+
+/// The live `accountSubscribe` feed for the logged-in player's account.
+#[derive(Resource, Default)]
+pub struct StatsSubscription {
+    pub public_key: String,
+    pub stop: Option<Arc<AtomicBool>>,
+}
+
+impl StatsSubscription {
+    fn stop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            stop.store(true, Ordering::Relaxed);
+        }
+        self.public_key.clear();
+    }
+}
+
+/// Keeps a subscription open for whoever is logged in, when reading from a Solana RPC.
+pub fn subscribe_stats(
+    mut subscription: ResMut<StatsSubscription>,
+    profile_data: Res<ProfileData>,
+    bridge: Res<BridgeSender>,
+    config: Res<GameConfig>,
+) {
+    if !profile_data.is_changed() {
         return;
     }
 
-    if !watcher.timer.tick(time.delta()).finished() {
+    if !profile_data.is_online() {
+        subscription.stop();
         return;
     }
 
-    let stats_state = STATS_STATE.read().unwrap();
+    if subscription.public_key == profile_data.public_key {
+        return;
+    }
+
+    let Some(rpc) = config.solana_rpc() else {
+        return;
+    };
+
+    subscription.stop();
+
+    let stop = Arc::new(AtomicBool::new(false));
+    rpc.subscribe_player(
+        profile_data.public_key.clone(),
+        bridge.clone(),
+        stop.clone(),
+    );
+
+    subscription.public_key = profile_data.public_key.clone();
+    subscription.stop = Some(stop);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let delays: Vec<_> = (0..=6).map(stats_backoff_secs).collect();
+
+        assert_eq!(delays, [3.0, 6.0, 12.0, 24.0, 48.0, 60.0, 60.0]);
+        assert_eq!(stats_backoff_secs(u32::MAX), STATS_MAX_BACKOFF_SECS);
+    }
+
+    #[test]
+    fn goes_offline_after_three_failures() {
+        let freshness: Vec<_> = (0..=4).map(StatsFreshness::after_failures).collect();
+
+        assert_eq!(
+            freshness,
+            [
+                StatsFreshness::Fresh,
+                StatsFreshness::Stale,
+                StatsFreshness::Stale,
+                StatsFreshness::Offline,
+                StatsFreshness::Offline,
+            ]
+        );
+    }
+
+    #[test]
+    fn a_success_resets_the_backoff() {
+        let mut client = StatsClient::default();
+
+        for _ in 0..3 {
+            client.failed();
+        }
+
+        assert_eq!(client.freshness, StatsFreshness::Offline);
+        assert_eq!(client.timer.duration().as_secs_f32(), 24.0);
+
+        client.succeeded();
+
+        assert_eq!(client.failures, 0);
+        assert_eq!(client.freshness, StatsFreshness::Fresh);
+        assert_eq!(client.timer.duration().as_secs_f32(), STATS_REFRESH_SECS);
+    }
+
+    #[test]
+    fn duel_info_parses_into_a_record() {
+        let record = parse_duel_info(200, r#"{ "win": 7, "loss": 3 }"#).unwrap();
+
+        assert_eq!(record, PlayerRecord::Found { win: 7, loss: 3 });
+        assert_eq!(record.win_loss(), (7, 3));
+    }
+
+    #[test]
+    fn duel_info_without_an_account_is_missing() {
+        let record = parse_duel_info(404, "").unwrap();
+
+        assert_eq!(record, PlayerRecord::Missing);
+        assert_eq!(record.win_loss(), (0, 0));
+    }
+
+    #[test]
+    fn bad_duel_info_is_a_failure() {
+        assert!(parse_duel_info(200, r#"{ "win": 7 }"#).is_err());
+        assert!(parse_duel_info(500, "").is_err());
+    }
+
+    #[test]
+    fn a_profile_being_created_waits_for_its_account() {
+        let found = PlayerRecord::Found { win: 0, loss: 0 };
 
-    if stats_state.win != stats_data.win || stats_state.loss != stats_data.loss {
-        stats_data.win = stats_state.win;
-        stats_data.loss = stats_state.loss;
+        assert_eq!(
+            profile_status_after(PlayerRecord::Missing, &DuelProfileStatus::Creating),
+            DuelProfileStatus::Creating
+        );
+        assert_eq!(
+            profile_status_after(found, &DuelProfileStatus::Creating),
+            DuelProfileStatus::Created
+        );
+        assert_eq!(
+            profile_status_after(found, &DuelProfileStatus::Unknown),
+            DuelProfileStatus::Ready
+        );
+        assert_eq!(
+            profile_status_after(PlayerRecord::Missing, &DuelProfileStatus::Unknown),
+            DuelProfileStatus::Missing
+        );
     }
 }
@@ -0,0 +1,413 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::shooting::MatchSettings;
+
+pub const TOURNAMENT_DIR: &str = "tournaments";
+
+pub const MIN_ENTRANTS: usize = 2;
+pub const MAX_ENTRANTS: usize = 16;
+pub const MAX_CODE_LEN: usize = 16;
+
+/// Codes end up in file names, so they are kept to letters, digits, `-` and `_`.
+pub fn is_valid_code(code: &str) -> bool {
+    !code.is_empty()
+        && code.len() <= MAX_CODE_LEN
+        && code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TournamentError {
+    AlreadyStarted,
+    Full,
+    NotEnoughEntrants,
+}
+
+impl fmt::Display for TournamentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TournamentError::AlreadyStarted => write!(f, "Tournament has already started"),
+            TournamentError::Full => write!(f, "Tournament is full"),
+            TournamentError::NotEnoughEntrants => {
+                write!(f, "At least {MIN_ENTRANTS} players have to register")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TournamentError {}
+
+/// One duel of the bracket. A player slot stays empty until the match that
+/// feeds it is decided, or for good when it is a bye.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BracketMatch {
+    pub player_1: Option<String>,
+    pub player_2: Option<String>,
+    pub winner: Option<String>,
+    /// The session the duel is being played in, set by the server.
+    pub match_id: Option<Uuid>,
+}
+
+impl BracketMatch {
+    pub fn is_ready(&self) -> bool {
+        self.player_1.is_some() && self.player_2.is_some() && self.winner.is_none()
+    }
+
+    pub fn label(&self) -> String {
+        let name = |player: &Option<String>| player.clone().unwrap_or_else(|| "-".to_string());
+        let mark = |player: &Option<String>| {
+            if player.is_some() && *player == self.winner {
+                "> "
+            } else {
+                ""
+            }
+        };
+
+        format!(
+            "{}{}\n{}{}",
+            mark(&self.player_1),
+            name(&self.player_1),
+            mark(&self.player_2),
+            name(&self.player_2)
+        )
+    }
+}
+
+/// A single-elimination bracket. Entrants are known by their names, which
+/// the server keeps unique.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Bracket {
+    pub code: String,
+    pub settings: MatchSettings,
+    pub entrants: Vec<String>,
+    /// Round by round, the final last. Empty until the tournament starts.
+    pub rounds: Vec<Vec<BracketMatch>>,
+    pub champion: Option<String>,
+}
+
+impl Bracket {
+    pub fn new(code: String, settings: MatchSettings) -> Self {
+        Self {
+            code,
+            settings: settings.sanitized(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_started(&self) -> bool {
+        !self.rounds.is_empty()
+    }
+
+    /// Adds an entrant and returns the name they play under, numbered when
+    /// someone already took theirs.
+    pub fn register(&mut self, name: &str) -> Result<String, TournamentError> {
+        if self.is_started() {
+            return Err(TournamentError::AlreadyStarted);
+        }
+
+        if self.entrants.len() >= MAX_ENTRANTS {
+            return Err(TournamentError::Full);
+        }
+
+        let base = if name.is_empty() { "Guest" } else { name };
+        let mut name = base.to_string();
+        let mut number = 2;
+
+        while self.entrants.contains(&name) {
+            name = format!("{base} ({number})");
+            number += 1;
+        }
+
+        self.entrants.push(name.clone());
+        Ok(name)
+    }
+
+    /// Seeds the entrants in registration order, the first seed meeting the
+    /// last. Top seeds get the byes when the field isn't a power of two.
+    pub fn start(&mut self) -> Result<(), TournamentError> {
+        if self.is_started() {
+            return Err(TournamentError::AlreadyStarted);
+        }
+
+        if self.entrants.len() < MIN_ENTRANTS {
+            return Err(TournamentError::NotEnoughEntrants);
+        }
+
+        let size = self.entrants.len().next_power_of_two();
+        let seed = |index: usize| self.entrants.get(index).cloned();
+
+        let first_round: Vec<BracketMatch> = (0..size / 2)
+            .map(|slot| BracketMatch {
+                player_1: seed(slot),
+                player_2: seed(size - 1 - slot),
+                ..Default::default()
+            })
+            .collect();
+
+        let mut rounds = vec![first_round];
+        let mut matches = size / 4;
+
+        while matches > 0 {
+            rounds.push(vec![BracketMatch::default(); matches]);
+            matches /= 2;
+        }
+
+        self.rounds = rounds;
+
+        for slot in 0..self.rounds[0].len() {
+            let bracket_match = &self.rounds[0][slot];
+
+            if bracket_match.player_2.is_none()
+                && let Some(player) = bracket_match.player_1.clone()
+            {
+                self.record_winner(0, slot, player);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Every duel that has both players and hasn't been decided, as (round, slot).
+    pub fn ready_matches(&self) -> Vec<(usize, usize)> {
+        self.rounds
+            .iter()
+            .enumerate()
+            .flat_map(|(round, matches)| {
+                matches
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, bracket_match)| bracket_match.is_ready())
+                    .map(move |(slot, _)| (round, slot))
+            })
+            .collect()
+    }
+
+    /// Decides a duel and moves its winner on, or crowns them after the final.
+    pub fn record_winner(&mut self, round: usize, slot: usize, winner: String) {
+        let Some(bracket_match) = self
+            .rounds
+            .get_mut(round)
+            .and_then(|matches| matches.get_mut(slot))
+        else {
+            return;
+        };
+
+        bracket_match.winner = Some(winner.clone());
+
+        match self
+            .rounds
+            .get_mut(round + 1)
+            .and_then(|matches| matches.get_mut(slot / 2))
+        {
+            Some(next_match) if slot.is_multiple_of(2) => next_match.player_1 = Some(winner),
+            Some(next_match) => next_match.player_2 = Some(winner),
+            None => self.champion = Some(winner),
+        }
+    }
+
+    pub fn has_match(&self, match_id: Uuid) -> bool {
+        self.rounds
+            .iter()
+            .flatten()
+            .any(|bracket_match| bracket_match.match_id == Some(match_id))
+    }
+
+    pub fn round_label(&self, round: usize) -> String {
+        match self.rounds.len() - round {
+            1 => "Final".to_string(),
+            2 => "Semifinals".to_string(),
+            _ => format!("Round {}", round + 1),
+        }
+    }
+
+    pub fn path_in(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.json", self.code))
+    }
+
+    pub fn save(&self, dir: &Path) -> std::io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+
+        let path = self.path_in(dir);
+        let json = serde_json::to_vec_pretty(self).expect("Bracket is serializable");
+        fs::write(&path, json)?;
+
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bracket(entrants: usize) -> Bracket {
+        let mut bracket = Bracket::new("CUP".to_string(), MatchSettings::default());
+
+        for number in 1..=entrants {
+            bracket.register(&format!("P{number}")).unwrap();
+        }
+
+        bracket
+    }
+
+    fn players(bracket_match: &BracketMatch) -> (Option<&str>, Option<&str>) {
+        (
+            bracket_match.player_1.as_deref(),
+            bracket_match.player_2.as_deref(),
+        )
+    }
+
+    /// Plays every ready duel, the first player always winning, until a
+    /// champion is crowned. Returns how many duels were played.
+    fn play_out(bracket: &mut Bracket) -> usize {
+        let mut played = 0;
+
+        while bracket.champion.is_none() {
+            let ready = bracket.ready_matches();
+            assert!(!ready.is_empty(), "bracket stalled without a champion");
+
+            for (round, slot) in ready {
+                let winner = bracket.rounds[round][slot].player_1.clone().unwrap();
+                bracket.record_winner(round, slot, winner);
+                played += 1;
+            }
+        }
+
+        played
+    }
+
+    #[test]
+    fn register_numbers_taken_names() {
+        let mut bracket = bracket(0);
+
+        assert_eq!(bracket.register("Ann").unwrap(), "Ann");
+        assert_eq!(bracket.register("Ann").unwrap(), "Ann (2)");
+        assert_eq!(bracket.register("Ann").unwrap(), "Ann (3)");
+        assert_eq!(bracket.register("").unwrap(), "Guest");
+        assert_eq!(bracket.register("").unwrap(), "Guest (2)");
+    }
+
+    #[test]
+    fn register_refuses_a_full_or_started_tournament() {
+        let mut full = bracket(MAX_ENTRANTS);
+        assert_eq!(full.register("Late"), Err(TournamentError::Full));
+
+        let mut started = bracket(2);
+        started.start().unwrap();
+        assert_eq!(
+            started.register("Late"),
+            Err(TournamentError::AlreadyStarted)
+        );
+        assert_eq!(started.start(), Err(TournamentError::AlreadyStarted));
+    }
+
+    #[test]
+    fn start_needs_two_entrants() {
+        let mut bracket = bracket(1);
+
+        assert_eq!(bracket.start(), Err(TournamentError::NotEnoughEntrants));
+        assert!(!bracket.is_started());
+    }
+
+    #[test]
+    fn two_entrants_play_only_the_final() {
+        let mut bracket = bracket(2);
+        bracket.start().unwrap();
+
+        assert_eq!(bracket.rounds.len(), 1);
+        assert_eq!(players(&bracket.rounds[0][0]), (Some("P1"), Some("P2")));
+        assert_eq!(bracket.ready_matches(), [(0, 0)]);
+        assert_eq!(bracket.round_label(0), "Final");
+
+        bracket.record_winner(0, 0, "P2".to_string());
+
+        assert_eq!(bracket.champion.as_deref(), Some("P2"));
+        assert!(bracket.ready_matches().is_empty());
+    }
+
+    #[test]
+    fn three_entrants_give_the_top_seed_a_bye() {
+        let mut bracket = bracket(3);
+        bracket.start().unwrap();
+
+        assert_eq!(bracket.rounds.len(), 2);
+        assert_eq!(players(&bracket.rounds[0][0]), (Some("P1"), None));
+        assert_eq!(bracket.rounds[0][0].winner.as_deref(), Some("P1"));
+        assert_eq!(players(&bracket.rounds[0][1]), (Some("P2"), Some("P3")));
+        assert_eq!(players(&bracket.rounds[1][0]), (Some("P1"), None));
+        assert_eq!(bracket.ready_matches(), [(0, 1)]);
+        assert_eq!(bracket.round_label(0), "Semifinals");
+        assert_eq!(bracket.round_label(1), "Final");
+    }
+
+    #[test]
+    fn five_entrants_give_the_top_three_seeds_byes() {
+        let mut bracket = bracket(5);
+        bracket.start().unwrap();
+
+        let first_round: Vec<_> = bracket.rounds[0].iter().map(players).collect();
+        assert_eq!(
+            first_round,
+            [
+                (Some("P1"), None),
+                (Some("P2"), None),
+                (Some("P3"), None),
+                (Some("P4"), Some("P5")),
+            ]
+        );
+        assert_eq!(players(&bracket.rounds[1][0]), (Some("P1"), Some("P2")));
+        assert_eq!(players(&bracket.rounds[1][1]), (Some("P3"), None));
+        assert_eq!(bracket.ready_matches(), [(0, 3), (1, 0)]);
+        assert_eq!(bracket.round_label(0), "Round 1");
+    }
+
+    #[test]
+    fn winners_move_on_to_half_their_slot() {
+        let mut bracket = bracket(8);
+        bracket.start().unwrap();
+
+        bracket.record_winner(0, 2, "P6".to_string());
+        bracket.record_winner(0, 3, "P4".to_string());
+
+        assert_eq!(bracket.rounds[0][2].winner.as_deref(), Some("P6"));
+        assert_eq!(players(&bracket.rounds[1][1]), (Some("P6"), Some("P4")));
+        assert_eq!(players(&bracket.rounds[1][0]), (None, None));
+        assert!(bracket.ready_matches().contains(&(1, 1)));
+        assert!(bracket.champion.is_none());
+    }
+
+    #[test]
+    fn sixteen_entrants_seed_first_against_last_and_play_to_a_champion() {
+        let mut bracket = bracket(16);
+        bracket.start().unwrap();
+
+        let sizes: Vec<_> = bracket.rounds.iter().map(Vec::len).collect();
+        assert_eq!(sizes, [8, 4, 2, 1]);
+        assert_eq!(players(&bracket.rounds[0][0]), (Some("P1"), Some("P16")));
+        assert_eq!(players(&bracket.rounds[0][7]), (Some("P8"), Some("P9")));
+
+        let labels: Vec<_> = (0..4).map(|round| bracket.round_label(round)).collect();
+        assert_eq!(labels, ["Round 1", "Round 2", "Semifinals", "Final"]);
+
+        assert_eq!(play_out(&mut bracket), 15);
+        assert_eq!(bracket.champion.as_deref(), Some("P1"));
+    }
+
+    #[test]
+    fn brackets_with_byes_play_to_a_champion() {
+        for (entrants, duels) in [(3, 2), (5, 4), (11, 10)] {
+            let mut bracket = bracket(entrants);
+            bracket.start().unwrap();
+
+            assert_eq!(play_out(&mut bracket), duels);
+            assert_eq!(bracket.champion.as_deref(), Some("P1"));
+        }
+    }
+}
@@ -0,0 +1,492 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    STARTING_HEARTS, ServerMessage,
+    series::{self, SeriesScore},
+    shooting::MatchSettings,
+};
+
+pub const TRANSCRIPT_DIR: &str = "transcripts";
+
+/// Every `ServerMessage` the server sent for one match, in send order.
+/// The sha256 of this transcript is what gets committed on-chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchTranscript {
+    pub match_id: Uuid,
+    /// How many rematches the session had been through when this game started.
+    #[serde(default)]
+    pub rematch: u32,
+    pub messages: Vec<ServerMessage>,
+}
+
+#[derive(Debug)]
+pub enum TranscriptError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    WrongMatch { index: usize },
+    HeartsMismatch { index: usize },
+    UnexpectedShot { index: usize },
+    WrongWinner { index: usize, expected: usize },
+    RoundsMismatch { index: usize },
+    MessageAfterGameOver { index: usize },
+    NoGameOver,
+}
+
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscriptError::Io(e) => write!(f, "could not read transcript: {e}"),
+            TranscriptError::Parse(e) => write!(f, "could not parse transcript: {e}"),
+            TranscriptError::WrongMatch { index } => {
+                write!(f, "message #{index} belongs to another match")
+            }
+            TranscriptError::HeartsMismatch { index } => {
+                write!(
+                    f,
+                    "message #{index} reports hearts that don't follow from the shots"
+                )
+            }
+            TranscriptError::UnexpectedShot { index } => {
+                write!(
+                    f,
+                    "message #{index} is a shot without a matching hearts update"
+                )
+            }
+            TranscriptError::WrongWinner { index, expected } => {
+                write!(
+                    f,
+                    "message #{index} declares the wrong winner, expected {expected}"
+                )
+            }
+            TranscriptError::RoundsMismatch { index } => {
+                write!(
+                    f,
+                    "message #{index} reports a series score that doesn't follow from the rounds"
+                )
+            }
+            TranscriptError::MessageAfterGameOver { index } => {
+                write!(f, "message #{index} was sent after the game was over")
+            }
+            TranscriptError::NoGameOver => write!(f, "transcript never reaches game over"),
+        }
+    }
+}
+
+impl std::error::Error for TranscriptError {}
+
+impl MatchTranscript {
+    pub fn new(match_id: Uuid) -> Self {
+        Self::for_rematch(match_id, 0)
+    }
+
+    pub fn for_rematch(match_id: Uuid, rematch: u32) -> Self {
+        Self {
+            match_id,
+            rematch,
+            messages: Vec::new(),
+        }
+    }
+
+    /// The id this game is saved and committed on-chain under.
+    pub fn game_id(&self) -> Uuid {
+        game_id(self.match_id, self.rematch)
+    }
+
+    pub fn record(&mut self, message: &ServerMessage) {
+        self.messages.push(message.clone());
+    }
+
+    /// The rules and key seed the match started with, enough to rebuild
+    /// every combo with `shooting::sequence::combo_keys`.
+    pub fn key_sequence(&self) -> Option<(MatchSettings, u64)> {
+        self.messages.iter().find_map(|message| match message {
+            ServerMessage::IsGameReadyToStart {
+                settings, key_seed, ..
+            } => Some((*settings, *key_seed)),
+            _ => None,
+        })
+    }
+
+    pub fn commitment(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.game_id().as_bytes());
+        hasher.update(serde_json::to_vec(&self.messages).expect("ServerMessage is serializable"));
+        hasher.finalize().into()
+    }
+
+    /// Re-plays the shots in the transcript and checks that every hearts update,
+    /// every round of a series and the final `GameOver` follow from them.
+    /// Returns the winner.
+    pub fn replay(&self) -> Result<usize, TranscriptError> {
+        let mut player_1_hearts = STARTING_HEARTS;
+        let mut player_2_hearts = STARTING_HEARTS;
+        let mut last_shooter: Option<usize> = None;
+        let mut winner: Option<usize> = None;
+        let mut series = SeriesScore::default();
+
+        for (index, message) in self.messages.iter().enumerate() {
+            if winner.is_some() {
+                return Err(TranscriptError::MessageAfterGameOver { index });
+            }
+
+            if message_match_id(message).is_some_and(|id| id != self.match_id) {
+                return Err(TranscriptError::WrongMatch { index });
+            }
+
+            match message {
+                ServerMessage::IsGameReadyToStart { settings, .. } => {
+                    series = SeriesScore::new(settings.sanitized().best_of);
+                }
+                ServerMessage::UpdateHeartsStatus {
+                    who_is_hit,
+                    player_1_hearts: reported_1,
+                    player_2_hearts: reported_2,
+                    ..
+                } => {
+                    if *who_is_hit == 1 {
+                        player_2_hearts = player_2_hearts.saturating_sub(1);
+                    } else {
+                        player_1_hearts = player_1_hearts.saturating_sub(1);
+                    }
+
+                    if *reported_1 != player_1_hearts || *reported_2 != player_2_hearts {
+                        return Err(TranscriptError::HeartsMismatch { index });
+                    }
+
+                    last_shooter = Some(*who_is_hit);
+                }
+                ServerMessage::ShootingCommand { player_number, .. } => {
+                    let shooter = last_shooter.take();
+
+                    if shooter != Some(*player_number) {
+                        return Err(TranscriptError::UnexpectedShot { index });
+                    }
+                }
+                ServerMessage::RoundOver {
+                    round_winner: declared,
+                    series: reported,
+                    ..
+                } => {
+                    let Some(expected) = series::round_winner(player_1_hearts, player_2_hearts)
+                    else {
+                        return Err(TranscriptError::HeartsMismatch { index });
+                    };
+
+                    if *declared != expected {
+                        return Err(TranscriptError::WrongWinner { index, expected });
+                    }
+
+                    // A decided series ends with `GameOver`, not another round.
+                    if series.record_round(expected).is_some() || *reported != series {
+                        return Err(TranscriptError::RoundsMismatch { index });
+                    }
+
+                    player_1_hearts = STARTING_HEARTS;
+                    player_2_hearts = STARTING_HEARTS;
+                }
+                ServerMessage::GameOver {
+                    winner: declared, ..
+                } => {
+                    let Some(round_winner) = series::round_winner(player_1_hearts, player_2_hearts)
+                    else {
+                        return Err(TranscriptError::HeartsMismatch { index });
+                    };
+
+                    let Some(expected) = series.record_round(round_winner) else {
+                        return Err(TranscriptError::RoundsMismatch { index });
+                    };
+
+                    if *declared != expected {
+                        return Err(TranscriptError::WrongWinner { index, expected });
+                    }
+
+                    winner = Some(expected);
+                }
+                _ => {}
+            }
+        }
+
+        winner.ok_or(TranscriptError::NoGameOver)
+    }
+
+    pub fn path_in(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.json", self.game_id()))
+    }
+
+    pub fn save(&self, dir: &Path) -> std::io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+
+        let path = self.path_in(dir);
+        let json = serde_json::to_vec_pretty(self).expect("ServerMessage is serializable");
+        fs::write(&path, json)?;
+
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, TranscriptError> {
+        let bytes = fs::read(path).map_err(TranscriptError::Io)?;
+        serde_json::from_slice(&bytes).map_err(TranscriptError::Parse)
+    }
+}
+
+/// A rematch is played in the same session, under the same match id, so each
+/// game of it gets an id of its own for its transcript, replay and on-chain
+/// commit. The first game keeps the match id.
+pub fn game_id(match_id: Uuid, rematch: u32) -> Uuid {
+    if rematch == 0 {
+        return match_id;
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(match_id.as_bytes());
+    hasher.update(rematch.to_le_bytes());
+    let hash: [u8; 32] = hasher.finalize().into();
+
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    uuid::Builder::from_custom_bytes(bytes).into_uuid()
+}
+
+fn message_match_id(message: &ServerMessage) -> Option<Uuid> {
+    match message {
+        ServerMessage::MatchCreated { match_id, .. }
+        | ServerMessage::JoinedMatch { match_id, .. }
+        | ServerMessage::PlayerCountingUpdate { match_id }
+        | ServerMessage::IsGameReadyToStart { match_id, .. }
+        | ServerMessage::ShootingCommand { match_id, .. }
+        | ServerMessage::UpdateHeartsStatus { match_id, .. }
+        | ServerMessage::GameOver { match_id, .. }
+        | ServerMessage::MatchCommitted { match_id, .. }
+        | ServerMessage::ResultSyncPending { match_id }
+        | ServerMessage::ResultCommitted { match_id }
+        | ServerMessage::ResultSynced { match_id }
+        | ServerMessage::SpectatingMatch { match_id, .. }
+        | ServerMessage::RoundOver { match_id, .. }
+        | ServerMessage::RematchRequested { match_id, .. }
+        | ServerMessage::RematchDeclined { match_id }
+        | ServerMessage::OpponentLeft { match_id }
+        | ServerMessage::RematchStarting { match_id, .. } => Some(*match_id),
+        ServerMessage::MatchJoinError { .. }
+        | ServerMessage::WalletChallenge { .. }
+        | ServerMessage::TournamentUpdated { .. }
+        | ServerMessage::TournamentRegistered { .. }
+        | ServerMessage::TournamentRejected { .. }
+        | ServerMessage::OpenMatches { .. } => None,
+    }
+}
+
+pub fn to_hex(hash: &[u8; 32]) -> String {
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub fn from_hex(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+
+    let mut hash = [0u8; 32];
+    for (i, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A one-round match player 1 wins by landing every shot.
+    fn won_by_player_1(match_id: Uuid) -> MatchTranscript {
+        let mut transcript = MatchTranscript::new(match_id);
+
+        transcript.record(&ServerMessage::IsGameReadyToStart {
+            match_id,
+            is_ready: true,
+            players: Vec::new(),
+            settings: MatchSettings::default(),
+            key_seed: 7,
+        });
+
+        for hearts_left in (0..STARTING_HEARTS).rev() {
+            transcript.record(&ServerMessage::UpdateHeartsStatus {
+                match_id,
+                who_is_hit: 1,
+                player_1_hearts: STARTING_HEARTS,
+                player_2_hearts: hearts_left,
+            });
+            transcript.record(&ServerMessage::ShootingCommand {
+                match_id,
+                player_number: 1,
+            });
+        }
+
+        transcript.record(&ServerMessage::GameOver {
+            match_id,
+            winner: 1,
+        });
+
+        transcript
+    }
+
+    #[test]
+    fn commitment_is_stable_and_covers_every_message() {
+        let match_id = Uuid::new_v4();
+        let transcript = won_by_player_1(match_id);
+
+        assert_eq!(
+            transcript.commitment(),
+            won_by_player_1(match_id).commitment()
+        );
+
+        let mut tampered = transcript.clone();
+        tampered.messages.pop();
+        assert_ne!(transcript.commitment(), tampered.commitment());
+
+        let other_match = MatchTranscript {
+            match_id: Uuid::new_v4(),
+            rematch: 0,
+            messages: transcript.messages.clone(),
+        };
+        assert_ne!(transcript.commitment(), other_match.commitment());
+
+        let rematch = MatchTranscript {
+            rematch: 1,
+            ..transcript.clone()
+        };
+        assert_ne!(transcript.commitment(), rematch.commitment());
+    }
+
+    #[test]
+    fn every_rematch_gets_its_own_game_id() {
+        let match_id = Uuid::new_v4();
+
+        assert_eq!(game_id(match_id, 0), match_id);
+        assert_eq!(game_id(match_id, 1), game_id(match_id, 1));
+        assert_ne!(game_id(match_id, 1), match_id);
+        assert_ne!(game_id(match_id, 1), game_id(match_id, 2));
+        assert_ne!(game_id(match_id, 1), game_id(Uuid::new_v4(), 1));
+    }
+
+    #[test]
+    fn a_rematch_transcript_is_saved_next_to_the_first_game() {
+        let dir = Path::new("transcripts");
+        let match_id = Uuid::new_v4();
+
+        assert_ne!(
+            MatchTranscript::new(match_id).path_in(dir),
+            MatchTranscript::for_rematch(match_id, 1).path_in(dir)
+        );
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        let hash = won_by_player_1(Uuid::new_v4()).commitment();
+
+        assert_eq!(from_hex(&to_hex(&hash)), Some(hash));
+        assert_eq!(from_hex("abc"), None);
+        assert_eq!(from_hex(&"zz".repeat(32)), None);
+    }
+
+    #[test]
+    fn replay_finds_the_winner() {
+        let transcript = won_by_player_1(Uuid::new_v4());
+
+        assert_eq!(transcript.replay().unwrap(), 1);
+        assert_eq!(transcript.key_sequence().map(|(_, seed)| seed), Some(7));
+    }
+
+    #[test]
+    fn replay_rejects_a_forged_winner() {
+        let match_id = Uuid::new_v4();
+        let mut transcript = won_by_player_1(match_id);
+        *transcript.messages.last_mut().unwrap() = ServerMessage::GameOver {
+            match_id,
+            winner: 2,
+        };
+
+        assert!(matches!(
+            transcript.replay(),
+            Err(TranscriptError::WrongWinner { expected: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn replay_rejects_inconsistent_hearts() {
+        let match_id = Uuid::new_v4();
+        let mut transcript = won_by_player_1(match_id);
+        transcript.messages[1] = ServerMessage::UpdateHeartsStatus {
+            match_id,
+            who_is_hit: 1,
+            player_1_hearts: STARTING_HEARTS,
+            player_2_hearts: STARTING_HEARTS - 2,
+        };
+
+        assert!(matches!(
+            transcript.replay(),
+            Err(TranscriptError::HeartsMismatch { index: 1 })
+        ));
+    }
+
+    #[test]
+    fn replay_rejects_shots_without_a_hit() {
+        let match_id = Uuid::new_v4();
+        let mut transcript = won_by_player_1(match_id);
+        transcript.messages.insert(
+            1,
+            ServerMessage::ShootingCommand {
+                match_id,
+                player_number: 2,
+            },
+        );
+
+        assert!(matches!(
+            transcript.replay(),
+            Err(TranscriptError::UnexpectedShot { index: 1 })
+        ));
+    }
+
+    #[test]
+    fn replay_rejects_messages_from_another_match() {
+        let mut transcript = won_by_player_1(Uuid::new_v4());
+        transcript.messages[2] = ServerMessage::ShootingCommand {
+            match_id: Uuid::new_v4(),
+            player_number: 1,
+        };
+
+        assert!(matches!(
+            transcript.replay(),
+            Err(TranscriptError::WrongMatch { index: 2 })
+        ));
+    }
+
+    #[test]
+    fn replay_needs_a_game_over() {
+        let mut transcript = won_by_player_1(Uuid::new_v4());
+        transcript.messages.pop();
+
+        assert!(matches!(
+            transcript.replay(),
+            Err(TranscriptError::NoGameOver)
+        ));
+    }
+
+    #[test]
+    fn saved_transcript_loads_back() {
+        let dir = std::env::temp_dir().join(format!("transcripts-{}", Uuid::new_v4()));
+        let transcript = won_by_player_1(Uuid::new_v4());
+
+        let path = transcript.save(&dir).unwrap();
+        let loaded = MatchTranscript::load(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.commitment(), transcript.commitment());
+    }
+}
@@ -0,0 +1,257 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+
+use crate::{
+    input,
+    rhythm::{BeatClock, CALIBRATION_FILE, InputCalibration},
+    ui::main_menu::MainMenuState,
+};
+
+const CALIBRATION_TAPS: usize = 8;
+
+#[derive(Component)]
+pub struct CalibrationUI;
+
+#[derive(Component)]
+pub struct CalibrationText;
+
+#[derive(Component)]
+pub struct BeatPulse;
+
+/// Raw offsets of the latest taps, before any calibration is applied.
+#[derive(Resource, Debug, Default)]
+pub struct CalibrationTaps(pub Vec<f32>);
+
+impl CalibrationTaps {
+    pub fn measured_offset_ms(&self) -> Option<f32> {
+        if self.0.len() < CALIBRATION_TAPS {
+            return None;
+        }
+
+        Some(self.0.iter().sum::<f32>() / self.0.len() as f32 * 1000.0)
+    }
+
+    pub fn reset(&mut self) {
+        self.0.clear();
+    }
+}
+
+pub fn spawn_calibration_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut calibration_taps: ResMut<CalibrationTaps>,
+) {
+    let font = asset_server.load("fonts/pixeloid_mono.ttf");
+    let font_bold = asset_server.load("fonts/pixeloid_mono_bold.ttf");
+
+    calibration_taps.reset();
+
+    commands
+        .spawn((
+            CalibrationUI,
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::SpaceEvenly,
+                ..Default::default()
+            },
+            BackgroundColor(Color::NONE),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Calibrate Timing"),
+                TextColor(Color::WHITE),
+                TextLayout::new_with_justify(JustifyText::Center),
+                TextFont {
+                    font: font_bold.clone(),
+                    font_size: 64.,
+                    ..Default::default()
+                },
+            ));
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                BeatPulse,
+                Text::new("Beat"),
+                TextColor(Color::WHITE),
+                TextLayout::new_with_justify(JustifyText::Center),
+                TextFont {
+                    font: font_bold.clone(),
+                    font_size: 64.,
+                    ..Default::default()
+                },
+            ));
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                CalibrationText,
+                Text::new("Waiting for the music..."),
+                TextColor(Color::WHITE),
+                TextLayout::new_with_justify(JustifyText::Center),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 28.,
+                    ..Default::default()
+                },
+            ));
+        })
+        .with_children(|parent| {
+            ["Save", "Back"].iter().for_each(|label| {
+                parent
+                    .spawn((
+                        Name::new(label.to_string()),
+                        Button,
+                        Node {
+                            width: Val::Px(502.),
+                            height: Val::Px(88.),
+                            position_type: PositionType::Relative,
+                            border: UiRect {
+                                left: Val::Px(2.),
+                                right: Val::Px(2.),
+                                top: Val::Px(2.),
+                                bottom: Val::Px(2.),
+                            },
+                            ..Default::default()
+                        },
+                        BorderColor(Color::WHITE),
+                        BackgroundColor(Color::WHITE.with_alpha(0.0)),
+                    ))
+                    .with_children(|parent| {
+                        parent
+                            .spawn(Node {
+                                width: Val::Percent(100.),
+                                height: Val::Percent(100.),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    Text::new(label.to_string()),
+                                    TextColor(Color::WHITE),
+                                    TextLayout::new_with_justify(JustifyText::Center),
+                                    TextFont {
+                                        font: font.clone(),
+                                        font_size: 48.,
+                                        ..Default::default()
+                                    },
+                                ));
+                            });
+                    });
+            })
+        });
+}
+
+/// Every key press is a tap, measured against the nearest beat.
+pub fn calibration_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    beat_clock: Res<BeatClock>,
+    mut calibration_taps: ResMut<CalibrationTaps>,
+) {
+    if !input::any_input_just_pressed(&keyboard_input, &gamepads) {
+        return;
+    }
+
+    let Some(offset) = beat_clock.offset_from_beat() else {
+        return;
+    };
+
+    calibration_taps.0.push(offset);
+
+    if calibration_taps.0.len() > CALIBRATION_TAPS {
+        calibration_taps.0.remove(0);
+    }
+}
+
+pub fn update_calibration_text(
+    beat_clock: Res<BeatClock>,
+    calibration_taps: Res<CalibrationTaps>,
+    calibration: Res<InputCalibration>,
+    mut text_query: Query<&mut Text, With<CalibrationText>>,
+    mut pulse_query: Query<&mut TextColor, With<BeatPulse>>,
+) {
+    let Some(progress) = beat_clock.beat_progress() else {
+        return;
+    };
+
+    for mut color in pulse_query.iter_mut() {
+        *color = TextColor(Color::WHITE.with_alpha(1.0 - progress));
+    }
+
+    let label = match calibration_taps.measured_offset_ms() {
+        Some(offset_ms) => format!(
+            "Measured offset: {:+.0} ms (saved: {:+.0} ms)",
+            offset_ms, calibration.offset_ms
+        ),
+        None => format!(
+            "Press any key or button on the beat: {}/{}",
+            calibration_taps.0.len(),
+            CALIBRATION_TAPS
+        ),
+    };
+
+    for mut text in text_query.iter_mut() {
+        *text = Text::new(label.clone());
+    }
+}
+
+pub fn calibration_button_pressed_handler(
+    button_query: Query<(&Interaction, &Name), Changed<Interaction>>,
+    mut next_main_menu_state: ResMut<NextState<MainMenuState>>,
+    mut calibration: ResMut<InputCalibration>,
+    calibration_taps: Res<CalibrationTaps>,
+) {
+    for (interaction, name) in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match name.as_str() {
+            "Save" => {
+                let Some(offset_ms) = calibration_taps.measured_offset_ms() else {
+                    continue;
+                };
+
+                calibration.offset_ms = offset_ms;
+
+                if let Err(e) = calibration.save(Path::new(CALIBRATION_FILE)) {
+                    error!("❌ Could not save calibration: {:?}", e);
+                }
+
+                next_main_menu_state.set(MainMenuState::Practice);
+            }
+            "Back" => {
+                next_main_menu_state.set(MainMenuState::Practice);
+            }
+            _ => return,
+        }
+    }
+}
+
+pub fn calibration_ui_interaction(
+    mut button_query: Query<(&Interaction, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    for (interaction, mut color) in button_query.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = BackgroundColor(Color::srgba(0.8, 0.8, 0.8, 0.15));
+            }
+            Interaction::Hovered => {
+                *color = BackgroundColor(Color::srgba(0.8, 0.8, 0.8, 0.07));
+            }
+            Interaction::None => {
+                *color = BackgroundColor(Color::NONE);
+            }
+        }
+    }
+}
+
+pub fn despawn_calibration_ui(mut commands: Commands, query: Query<Entity, With<CalibrationUI>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
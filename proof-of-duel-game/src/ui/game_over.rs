@@ -1,12 +1,22 @@
-use bevy::{prelude::*, tasks::AsyncComputeTaskPool};
-use serde::{Deserialize, Serialize};
+use bevy::prelude::*;
+use bevy_quinnet::client::QuinnetClient;
+use uuid::Uuid;
 
 use crate::{
-    GameState, LoggedInState,
-    connection::ConnectionState,
-    player::{PlayerHertsStatus, PlayerSelection, PlayersCounting, ShootingLock},
+    ClientChannel, ClientMessage, GameState, LoggedInState, ServerMessage,
+    config::GameConfig,
+    connection::{ConnectionState, ServerMessageReceived},
+    player::{MatchPlayers, PlayerHertsStatus, PlayerSelection, PlayersCounting, ShootingLock},
+    practice::PracticeMatch,
+    replay::ReplayPlayback,
+    series::SeriesScore,
     shooting::ShootingStates,
-    ui::{main_menu::MainMenuState, play_now_ui::GameStartTimer, profile::ProfileData},
+    stats::OpponentStats,
+    tournament::Bracket,
+    ui::{
+        join_game::IsSpectator, main_menu::MainMenuState, play_now_ui::GameStartTimer,
+        profile::ProfileData,
+    },
 };
 
 #[derive(Component)]
@@ -23,113 +33,118 @@ impl WhoIsWinner {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DuelWinPayload {
-    pub public_key: String,
+#[derive(Resource, Debug, Clone, Default)]
+pub struct MatchCommitment {
+    /// What the result is committed under, differs from the match id after a
+    /// rematch.
+    pub game_id: Option<Uuid>,
+    pub transcript_hash: Option<String>,
+    pub sync_pending: bool,
+    /// Committed on-chain but not finalized, it doesn't count yet.
+    pub in_dispute_window: bool,
+}
+
+impl MatchCommitment {
+    pub fn reset(&mut self) {
+        self.game_id = None;
+        self.transcript_hash = None;
+        self.sync_pending = false;
+        self.in_dispute_window = false;
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DuelLossPayload {
-    pub public_key: String,
+#[derive(Component)]
+pub struct CommitmentText;
+
+/// Where the rematch offer stands since the match ended.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RematchStatus {
+    #[default]
+    None,
+    /// We asked, the opponent hasn't answered yet.
+    Requested,
+    OpponentRequested,
+    Declined,
+    OpponentLeft,
+    /// Both players accepted, the countdown is running.
+    Starting,
+}
+
+impl RematchStatus {
+    pub fn reset(&mut self) {
+        *self = RematchStatus::None;
+    }
+
+    /// Whether a rematch can still be asked for or accepted.
+    pub fn is_open(&self) -> bool {
+        matches!(self, RematchStatus::None | RematchStatus::OpponentRequested)
+    }
 }
 
+#[derive(Component)]
+pub struct RematchText;
+
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_game_over_ui(
     mut commands: Commands,
     who_is_winner: Res<WhoIsWinner>,
     asset_server: Res<AssetServer>,
     player_selection: Res<PlayerSelection>,
-    player_auth_data: Res<ProfileData>,
+    profile_data: Res<ProfileData>,
+    practice_match: Res<PracticeMatch>,
+    replay_playback: Res<ReplayPlayback>,
+    is_spectator: Res<IsSpectator>,
+    series_score: Res<SeriesScore>,
+    bracket: Res<Bracket>,
 ) {
-    let thread_pool = AsyncComputeTaskPool::get();
-
+    let font = asset_server.load("fonts/pixeloid_mono.ttf");
     let font_bold = asset_server.load("fonts/pixeloid_mono_bold.ttf");
-    let whos_winner = if who_is_winner.player_number == 1 && player_selection.0 == 1 {
-        let public_key = player_auth_data.public_key.clone();
 
-        thread_pool
-            .spawn(async move {
-                let url = "http://localhost:3000/api/duel-win";
+    // Results are committed on-chain by the server and only count once the
+    // dispute window has passed, so nothing is reported from here.
+    let whos_winner = if who_is_winner.player_number == 0 {
+        "It's a Draw!".to_string()
+    } else if practice_match.local_duel || replay_playback.is_playing() || is_spectator.0 {
+        format!("Player {} Wins!", who_is_winner.player_number)
+    } else if who_is_winner.player_number == player_selection.0 {
+        "You Win!".to_string()
+    } else {
+        "You Lose!".to_string()
+    };
 
-                match ureq::post(url).send_json(DuelWinPayload { public_key }) {
-                    Ok(response) if response.status() == 200 => {
-                        info!("✅ Duel win recorded successfully");
-                    }
-                    Ok(response) => {
-                        error!("❌ Duel win failed to record: {}", response.status());
-                    }
-                    Err(e) => {
-                        error!("❌ Error sending to RPC: {:?}", e);
-                    }
-                }
-            })
-            .detach();
-
-        "You Win!"
-    } else if who_is_winner.player_number == 2 && player_selection.0 == 2 {
-        let public_key = player_auth_data.public_key.clone();
-
-        thread_pool
-            .spawn(async move {
-                let url = "http://localhost:3000/api/duel-win";
-                match ureq::post(url).send_json(DuelWinPayload { public_key }) {
-                    Ok(response) if response.status() == 200 => {
-                        info!("✅ Duel win recorded successfully");
-                    }
-                    Ok(response) => {
-                        error!("❌ Duel win failed to record: {}", response.status());
-                    }
-                    Err(e) => {
-                        error!("❌ Error sending to RPC: {:?}", e);
-                    }
-                }
-            })
-            .detach();
-
-        "You Win!"
-    } else if who_is_winner.player_number == 1 && player_selection.0 == 2 {
-        let public_key = player_auth_data.public_key.clone();
-
-        thread_pool
-            .spawn(async move {
-                let url = "http://localhost:3000/api/duel-loss";
-                match ureq::post(url).send_json(DuelLossPayload { public_key }) {
-                    Ok(response) if response.status() == 200 => {
-                        info!("✅ Duel loss recorded successfully");
-                    }
-                    Ok(response) => {
-                        error!("❌ Duel loss failed to record: {}", response.status());
-                    }
-                    Err(e) => {
-                        error!("❌ Error sending to RPC: {:?}", e);
-                    }
-                }
-            })
-            .detach();
-
-        "You Lose!"
-    } else if who_is_winner.player_number == 2 && player_selection.0 == 1 {
-        let public_key = player_auth_data.public_key.clone();
-
-        thread_pool
-            .spawn(async move {
-                let url = "http://localhost:3000/api/duel-loss";
-                match ureq::post(url).send_json(DuelLossPayload { public_key }) {
-                    Ok(response) if response.status() == 200 => {
-                        info!("✅ Duel loss recorded successfully");
-                    }
-                    Ok(response) => {
-                        error!("❌ Duel loss failed to record: {}", response.status());
-                    }
-                    Err(e) => {
-                        error!("❌ Error sending to RPC: {:?}", e);
-                    }
-                }
-            })
-            .detach();
+    let series_label = if series_score.is_series() {
+        series_score.label()
+    } else {
+        String::new()
+    };
+
+    // Only a match played against someone over the network can be rematched,
+    // and a tournament duel is followed by the next one in the bracket instead.
+    let rematch_display = if practice_match.active
+        || replay_playback.is_playing()
+        || is_spectator.0
+        || bracket.has_match(player_selection.1)
+    {
+        Display::None
+    } else {
+        Display::Flex
+    };
 
-        "You Lose!"
+    // Practice and guest matches aren't committed on-chain, so there is nothing to dispute.
+    let (commitment_label, dispute_display) = if replay_playback.is_playing() {
+        ("Replay", Display::None)
+    } else if is_spectator.0 {
+        ("Spectated match", Display::None)
+    } else if practice_match.local_duel {
+        ("Local duel, no stats recorded", Display::None)
+    } else if practice_match.active {
+        ("Practice match, no stats recorded", Display::None)
+    } else if profile_data.guest {
+        ("Guest match, result saved on this device", Display::None)
+    } else if profile_data.public_key.is_empty() {
+        ("No wallet, no stats recorded", Display::None)
     } else {
-        "It's a Draw!"
+        ("Waiting for result commitment...", Display::Flex)
     };
 
     commands
@@ -169,6 +184,118 @@ pub fn spawn_game_over_ui(
                     ));
                 });
         })
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(series_label),
+                TextColor(Color::WHITE),
+                TextLayout::new_with_justify(JustifyText::Center),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 32.,
+                    ..Default::default()
+                },
+            ));
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                CommitmentText,
+                Text::new(commitment_label),
+                TextColor(Color::WHITE),
+                TextLayout::new_with_justify(JustifyText::Center),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 24.,
+                    ..Default::default()
+                },
+            ));
+        })
+        .with_children(|parent_1| {
+            parent_1
+                .spawn((
+                    Button,
+                    Name::new("Dispute".to_string()),
+                    Node {
+                        width: Val::Px(502.),
+                        height: Val::Px(88.),
+                        display: dispute_display,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        position_type: PositionType::Relative,
+                        border: UiRect {
+                            left: Val::Px(2.),
+                            right: Val::Px(2.),
+                            top: Val::Px(2.),
+                            bottom: Val::Px(2.),
+                        },
+                        ..Default::default()
+                    },
+                    BorderColor(Color::WHITE),
+                ))
+                .with_children(|parent_2| {
+                    parent_2.spawn((
+                        Text::new("Dispute Result"),
+                        TextColor(Color::WHITE),
+                        TextLayout::new_with_justify(JustifyText::Center),
+                        TextFont {
+                            font: font_bold.clone(),
+                            font_size: 36.,
+                            ..Default::default()
+                        },
+                    ));
+                });
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                RematchText,
+                Text::new(""),
+                Node {
+                    display: rematch_display,
+                    ..Default::default()
+                },
+                TextColor(Color::WHITE),
+                TextLayout::new_with_justify(JustifyText::Center),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 24.,
+                    ..Default::default()
+                },
+            ));
+        })
+        .with_children(|parent_1| {
+            parent_1
+                .spawn((
+                    Button,
+                    Name::new("Rematch".to_string()),
+                    Node {
+                        width: Val::Px(502.),
+                        height: Val::Px(88.),
+                        display: rematch_display,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        position_type: PositionType::Relative,
+                        border: UiRect {
+                            left: Val::Px(2.),
+                            right: Val::Px(2.),
+                            top: Val::Px(2.),
+                            bottom: Val::Px(2.),
+                        },
+                        ..Default::default()
+                    },
+                    BorderColor(Color::WHITE),
+                ))
+                .with_children(|parent_2| {
+                    parent_2.spawn((
+                        Text::new("Rematch"),
+                        TextColor(Color::WHITE),
+                        TextLayout::new_with_justify(JustifyText::Center),
+                        TextFont {
+                            font: font_bold.clone(),
+                            font_size: 36.,
+                            ..Default::default()
+                        },
+                    ));
+                });
+        })
         .with_children(|parent_1| {
             parent_1
                 .spawn((
@@ -206,6 +333,164 @@ pub fn spawn_game_over_ui(
         });
 }
 
+pub fn update_commitment_text(
+    match_commitment: Res<MatchCommitment>,
+    mut query: Query<&mut Text, With<CommitmentText>>,
+) {
+    if !match_commitment.is_changed() {
+        return;
+    }
+
+    let Some(transcript_hash) = &match_commitment.transcript_hash else {
+        return;
+    };
+
+    let short_hash = transcript_hash.get(..16).unwrap_or(transcript_hash);
+    let sync_status = if match_commitment.sync_pending {
+        "\nResult pending sync"
+    } else if match_commitment.in_dispute_window {
+        "\nResult in dispute window"
+    } else {
+        ""
+    };
+
+    for mut text in query.iter_mut() {
+        *text = Text::new(format!("Result hash: {}...{}", short_hash, sync_status));
+    }
+}
+
+pub fn update_rematch_text(
+    rematch_status: Res<RematchStatus>,
+    game_start_timer: Res<GameStartTimer>,
+    mut query: Query<&mut Text, With<RematchText>>,
+) {
+    let label = match *rematch_status {
+        // A tournament's next duel can start straight from here.
+        RematchStatus::None if game_start_timer.active => format!(
+            "Next duel starts in: {}",
+            game_start_timer.timer.remaining_secs().ceil()
+        ),
+        RematchStatus::None => String::new(),
+        RematchStatus::Requested => "Rematch requested, waiting for your opponent...".to_string(),
+        RematchStatus::OpponentRequested => {
+            "Your opponent wants a rematch, press Rematch to accept".to_string()
+        }
+        RematchStatus::Declined => "Your opponent declined the rematch".to_string(),
+        RematchStatus::OpponentLeft => "Your opponent left the match".to_string(),
+        RematchStatus::Starting => format!(
+            "Rematch starts in: {}",
+            game_start_timer.timer.remaining_secs().ceil()
+        ),
+    };
+
+    for mut text in query.iter_mut() {
+        if text.0 != label {
+            *text = Text::new(label.clone());
+        }
+    }
+}
+
+pub fn reset_rematch_status(mut rematch_status: ResMut<RematchStatus>) {
+    rematch_status.reset();
+}
+
+/// Runs before `handle_server_messages`, so the `IsGameReadyToStart` that
+/// follows a `RematchStarting` already finds the match reset.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_rematch_messages(
+    mut server_message: EventReader<ServerMessageReceived>,
+    mut rematch_status: ResMut<RematchStatus>,
+    player_selection: Res<PlayerSelection>,
+    mut player_hearts_status: ResMut<PlayerHertsStatus>,
+    mut game_start_timer: ResMut<GameStartTimer>,
+    mut who_is_winner: ResMut<WhoIsWinner>,
+    mut shooting_lock: ResMut<ShootingLock>,
+    mut match_commitment: ResMut<MatchCommitment>,
+) {
+    for ServerMessageReceived { channel, message } in server_message.read() {
+        if *channel != 0 {
+            continue;
+        }
+
+        match *message {
+            ServerMessage::RematchRequested {
+                match_id,
+                player_number,
+            } if match_id == player_selection.1
+                && player_number != player_selection.0
+                && *rematch_status == RematchStatus::None =>
+            {
+                *rematch_status = RematchStatus::OpponentRequested;
+            }
+            ServerMessage::RematchDeclined { match_id } if match_id == player_selection.1 => {
+                *rematch_status = RematchStatus::Declined;
+            }
+            ServerMessage::OpponentLeft { match_id } if match_id == player_selection.1 => {
+                *rematch_status = RematchStatus::OpponentLeft;
+            }
+            ServerMessage::RematchStarting { match_id, .. } if match_id == player_selection.1 => {
+                player_hearts_status.reset();
+                game_start_timer.reset();
+                who_is_winner.reset();
+                shooting_lock.reset();
+                match_commitment.reset();
+
+                *rematch_status = RematchStatus::Starting;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Sends the rematch request, and declines one on the way back to the main
+/// menu so the opponent isn't left waiting.
+pub fn rematch_button_pressed_handler(
+    button_query: Query<(&Interaction, &Name), Changed<Interaction>>,
+    mut client: ResMut<QuinnetClient>,
+    player_selection: Res<PlayerSelection>,
+    mut rematch_status: ResMut<RematchStatus>,
+    practice_match: Res<PracticeMatch>,
+    replay_playback: Res<ReplayPlayback>,
+    is_spectator: Res<IsSpectator>,
+) {
+    if practice_match.active || replay_playback.is_playing() || is_spectator.0 {
+        return;
+    }
+
+    for (interaction, name) in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let match_id = player_selection.1;
+
+        match name.as_str() {
+            "Rematch" if rematch_status.is_open() => {
+                let _ = client.connection_mut().send_message_on(
+                    ClientChannel::Lobby,
+                    ClientMessage::RematchRequest { match_id },
+                );
+
+                if *rematch_status == RematchStatus::None {
+                    *rematch_status = RematchStatus::Requested;
+                }
+            }
+            "Back to Main Menu"
+                if !matches!(
+                    *rematch_status,
+                    RematchStatus::Declined | RematchStatus::OpponentLeft
+                ) =>
+            {
+                let _ = client.connection_mut().send_message_on(
+                    ClientChannel::Lobby,
+                    ClientMessage::DeclineRematch { match_id },
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
 pub fn game_over_ui_interaction(
     mut button_query: Query<(&Interaction, &mut BackgroundColor), Changed<Interaction>>,
 ) {
@@ -224,6 +509,7 @@ pub fn game_over_ui_interaction(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn game_over_button_pressed_handler(
     button_query: Query<(&Interaction, &Name), Changed<Interaction>>,
     mut next_game_state: ResMut<NextState<GameState>>,
@@ -237,6 +523,10 @@ pub fn game_over_button_pressed_handler(
     mut who_is_winner: ResMut<WhoIsWinner>,
     mut shooting_lock: ResMut<ShootingLock>,
     mut next_logged_in_state: ResMut<NextState<LoggedInState>>,
+    mut match_commitment: ResMut<MatchCommitment>,
+    mut match_players: ResMut<MatchPlayers>,
+    mut opponent_stats: ResMut<OpponentStats>,
+    config: Res<GameConfig>,
 ) {
     for (interaction, name) in button_query.iter() {
         if *interaction != Interaction::Pressed {
@@ -244,6 +534,17 @@ pub fn game_over_button_pressed_handler(
         }
 
         match name.as_str() {
+            "Dispute" => {
+                let url = format!(
+                    "{}?match_id={}",
+                    config.api_url("/dispute"),
+                    match_commitment.game_id.unwrap_or(player_selection.1)
+                );
+                match webbrowser::open(&url) {
+                    Ok(_) => info!("Opening browser to dispute page..."),
+                    Err(e) => error!("❌ Could not open the browser to the dispute page: {:?}", e),
+                }
+            }
             "Back to Main Menu" => {
                 connection_state.set(ConnectionState::Idle);
 
@@ -254,6 +555,9 @@ pub fn game_over_button_pressed_handler(
                 shooting_states.reset();
                 who_is_winner.reset();
                 shooting_lock.reset();
+                match_commitment.reset();
+                match_players.reset();
+                opponent_stats.reset();
 
                 main_menu_state.set(MainMenuState::MainMenu);
                 next_game_state.set(GameState::MainMenu);
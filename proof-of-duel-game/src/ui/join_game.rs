@@ -4,8 +4,12 @@ use bevy_quinnet::client::QuinnetClient;
 use uuid::Uuid;
 
 use crate::{
-    ClientChannel, ClientMessage, GameState, LoggedInState, connection::ConnectionState,
-    player::PlayerSelection, ui::main_menu::MainMenuState,
+    ClientChannel, ClientMessage, GameState, LoggedInState,
+    connection::ConnectionState,
+    lobby::LobbyBrowser,
+    player::PlayerSelection,
+    ui::{main_menu::MainMenuState, play_now_ui::PlayNowUI},
+    wallet::{self, MatchRequest, PendingMatchRequest},
 };
 
 #[derive(Component)]
@@ -26,6 +30,17 @@ pub struct MatchIdTextInput;
 #[derive(Resource, Default)]
 pub struct MatchIdInput(pub String);
 
+/// Holds the rows of the lobby browser, rebuilt whenever the page changes.
+#[derive(Component)]
+pub struct LobbyList;
+
+#[derive(Component)]
+pub struct LobbyPageText;
+
+/// A row of the lobby browser, pressing it joins the match.
+#[derive(Component)]
+pub struct OpenMatchButton(pub Uuid);
+
 #[derive(Resource)]
 pub struct BackspaceTimer(pub Timer);
 
@@ -44,14 +59,36 @@ impl IsHost {
     }
 }
 
+/// Watching a match instead of playing it. The join screen then asks for the
+/// match to spectate.
+#[derive(Resource, Default)]
+pub struct IsSpectator(pub bool);
+
+impl IsSpectator {
+    pub fn reset(&mut self) {
+        self.0 = false;
+    }
+}
+
+pub fn is_spectating(is_spectator: Res<IsSpectator>) -> bool {
+    is_spectator.0
+}
+
 pub fn spawn_join_game_ui(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     match_id_input: Res<MatchIdInput>,
+    is_spectator: Res<IsSpectator>,
 ) {
     let font = asset_server.load("fonts/pixeloid_mono.ttf");
     let font_bold = asset_server.load("fonts/pixeloid_mono_bold.ttf");
 
+    let (title, action) = if is_spectator.0 {
+        ("Spectate", "Watch")
+    } else {
+        ("Join Game", "Join")
+    };
+
     commands
         .spawn((
             JoinGameUI,
@@ -79,7 +116,7 @@ pub fn spawn_join_game_ui(
                 })
                 .with_children(|parent| {
                     parent.spawn((
-                        Text::new("Join Game".to_string()),
+                        Text::new(title.to_string()),
                         TextColor(Color::WHITE),
                         TextLayout::new_with_justify(JustifyText::Center),
                         TextFont {
@@ -170,10 +207,73 @@ pub fn spawn_join_game_ui(
                         });
                 });
         })
+        .with_children(|parent| {
+            // Spectators are after matches that are already full, which are never listed.
+            if is_spectator.0 {
+                return;
+            }
+
+            parent
+                .spawn(Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(4.),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(Node {
+                            display: Display::Flex,
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(16.),
+                            ..Default::default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new("Open Matches"),
+                                TextColor(Color::WHITE),
+                                TextFont {
+                                    font: font.clone(),
+                                    font_size: 28.,
+                                    ..Default::default()
+                                },
+                            ));
+
+                            spawn_lobby_page_button(parent, "Previous Page", "<", font.clone());
+
+                            parent.spawn((
+                                LobbyPageText,
+                                Text::new("1/1"),
+                                TextColor(Color::WHITE),
+                                TextFont {
+                                    font: font.clone(),
+                                    font_size: 28.,
+                                    ..Default::default()
+                                },
+                            ));
+
+                            spawn_lobby_page_button(parent, "Next Page", ">", font.clone());
+                        });
+
+                    parent.spawn((
+                        LobbyList,
+                        Node {
+                            width: Val::Px(900.),
+                            height: Val::Px(180.),
+                            display: Display::Flex,
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                    ));
+                });
+        })
         .with_children(|parent| {
             parent
                 .spawn((
-                    Name::new("Join"),
+                    Name::new(action),
                     Button,
                     Node {
                         width: Val::Px(360.),
@@ -201,7 +301,7 @@ pub fn spawn_join_game_ui(
                         })
                         .with_children(|parent| {
                             parent.spawn((
-                                Text::new("Join".to_string()),
+                                Text::new(action.to_string()),
                                 TextColor(Color::WHITE),
                                 TextLayout::new_with_justify(JustifyText::Center),
                                 TextFont {
@@ -283,14 +383,14 @@ pub fn update_match_id_input(
     }
 
     // Handle paste (Ctrl + V)
-    if keyboard_input.pressed(KeyCode::ControlLeft) && keyboard_input.just_pressed(KeyCode::KeyV) {
-        if let Ok(mut clipboard) = Clipboard::new() {
-            if let Ok(clip_text) = clipboard.get_text() {
-                for c in clip_text.chars() {
-                    if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
-                        buffer.0.push(c);
-                    }
-                }
+    if keyboard_input.pressed(KeyCode::ControlLeft)
+        && keyboard_input.just_pressed(KeyCode::KeyV)
+        && let Ok(mut clipboard) = Clipboard::new()
+        && let Ok(clip_text) = clipboard.get_text()
+    {
+        for c in clip_text.chars() {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                buffer.0.push(c);
             }
         }
     }
@@ -316,6 +416,164 @@ pub fn update_match_id_input(
     }
 }
 
+fn spawn_lobby_page_button(
+    parent: &mut ChildSpawnerCommands,
+    name: &str,
+    label: &str,
+    font: Handle<Font>,
+) {
+    parent
+        .spawn((
+            Name::new(name.to_string()),
+            Button,
+            Node {
+                width: Val::Px(48.),
+                height: Val::Px(36.),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                border: UiRect::all(Val::Px(2.)),
+                ..Default::default()
+            },
+            BorderColor(Color::WHITE),
+            BackgroundColor(Color::WHITE.with_alpha(0.0)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(label.to_string()),
+                TextColor(Color::WHITE),
+                TextFont {
+                    font,
+                    font_size: 28.,
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
+/// Lists the current page of open matches, one clickable row per match.
+pub fn spawn_open_matches(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    lobby_browser: Res<LobbyBrowser>,
+    list_query: Query<(Entity, Ref<LobbyList>)>,
+    mut page_text_query: Query<&mut Text, With<LobbyPageText>>,
+) {
+    let font = asset_server.load("fonts/pixeloid_mono.ttf");
+
+    for (entity, list) in list_query.iter() {
+        if !lobby_browser.is_changed() && !list.is_added() {
+            continue;
+        }
+
+        for mut text in page_text_query.iter_mut() {
+            *text = Text::new(format!(
+                "{}/{}",
+                lobby_browser.page + 1,
+                lobby_browser.page_count
+            ));
+        }
+
+        commands
+            .entity(entity)
+            .despawn_related::<Children>()
+            .with_children(|parent| {
+                if lobby_browser.matches.is_empty() {
+                    parent.spawn((
+                        Text::new("No public matches right now"),
+                        TextColor(Color::WHITE),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 24.,
+                            ..Default::default()
+                        },
+                    ));
+                    return;
+                }
+
+                for open_match in &lobby_browser.matches {
+                    parent
+                        .spawn((
+                            OpenMatchButton(open_match.match_id),
+                            Name::new("Open Match"),
+                            Button,
+                            Node {
+                                width: Val::Percent(100.),
+                                height: Val::Px(36.),
+                                display: Display::Flex,
+                                flex_direction: FlexDirection::Row,
+                                align_items: AlignItems::Center,
+                                padding: UiRect::horizontal(Val::Px(12.)),
+                                ..Default::default()
+                            },
+                            BackgroundColor(Color::WHITE.with_alpha(0.0)),
+                        ))
+                        .with_children(|parent| {
+                            for (label, width) in [
+                                (open_match.host_name.clone(), 340.),
+                                (open_match.rating_label(), 180.),
+                                (open_match.settings_label(), 240.),
+                                (open_match.age_label(), 100.),
+                            ] {
+                                parent.spawn((
+                                    Text::new(label),
+                                    TextColor(Color::WHITE),
+                                    TextFont {
+                                        font: font.clone(),
+                                        font_size: 24.,
+                                        ..Default::default()
+                                    },
+                                    Node {
+                                        width: Val::Px(width),
+                                        overflow: Overflow::clip(),
+                                        ..Default::default()
+                                    },
+                                ));
+                            }
+                        });
+                }
+            });
+    }
+}
+
+/// Pages through the lobby browser and joins the match of a pressed row.
+pub fn lobby_button_pressed_handler(
+    button_query: Query<(&Interaction, &Name, Option<&OpenMatchButton>), Changed<Interaction>>,
+    mut next_main_menu_state: ResMut<NextState<MainMenuState>>,
+    mut next_logged_in_state: ResMut<NextState<LoggedInState>>,
+    mut player_selection: ResMut<PlayerSelection>,
+    mut lobby_browser: ResMut<LobbyBrowser>,
+    mut client: ResMut<QuinnetClient>,
+    mut pending_match_request: ResMut<PendingMatchRequest>,
+) {
+    for (interaction, name, open_match_button) in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        if let Some(OpenMatchButton(match_id)) = open_match_button {
+            wallet::request_match(
+                &mut client,
+                &mut pending_match_request,
+                MatchRequest::Join(*match_id),
+            );
+
+            player_selection.1 = *match_id;
+
+            next_main_menu_state.set(MainMenuState::PlayNow);
+            next_logged_in_state.set(LoggedInState::InGame);
+            return;
+        }
+
+        match name.as_str() {
+            "Previous Page" => lobby_browser.previous_page(),
+            "Next Page" => lobby_browser.next_page(),
+            _ => continue,
+        }
+
+        lobby_browser.request(&mut client);
+    }
+}
+
 pub fn join_game_ui_interaction(
     mut button_query: Query<(&Interaction, &mut BackgroundColor), Changed<Interaction>>,
 ) {
@@ -334,6 +592,7 @@ pub fn join_game_ui_interaction(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn join_game_button_pressed_handler(
     button_query: Query<(&Interaction, &Name), Changed<Interaction>>,
     mut next_game_state: ResMut<NextState<GameState>>,
@@ -343,6 +602,7 @@ pub fn join_game_button_pressed_handler(
     mut buffer: ResMut<MatchIdInput>,
     mut client: ResMut<QuinnetClient>,
     mut next_logged_in_state: ResMut<NextState<LoggedInState>>,
+    mut pending_match_request: ResMut<PendingMatchRequest>,
 ) {
     for (interaction, name) in button_query.iter() {
         if *interaction != Interaction::Pressed {
@@ -355,14 +615,25 @@ pub fn join_game_button_pressed_handler(
 
         match name.as_str() {
             "Join" => {
+                wallet::request_match(
+                    &mut client,
+                    &mut pending_match_request,
+                    MatchRequest::Join(match_id),
+                );
+
+                player_selection.1 = match_id;
+
+                next_main_menu_state.set(MainMenuState::PlayNow);
+                next_logged_in_state.set(LoggedInState::InGame);
+            }
+            "Watch" => {
+                // Spectators don't need a wallet, they never play.
                 let _ = client.connection_mut().send_message_on(
                     ClientChannel::Lobby,
-                    ClientMessage::JoinMatchRequest {
-                        match_id,
-                        player_wallet: "".to_string(),
-                    },
+                    ClientMessage::SpectateMatchRequest { match_id },
                 );
 
+                player_selection.0 = 0;
                 player_selection.1 = match_id;
 
                 next_main_menu_state.set(MainMenuState::PlayNow);
@@ -383,9 +654,10 @@ pub fn join_game_button_pressed_handler(
     }
 }
 
+#[allow(clippy::type_complexity)]
 pub fn match_not_found_error(
     mut commands: Commands,
-    join_game_ui_query: Query<Entity, With<JoinGameUI>>,
+    join_game_ui_query: Query<Entity, Or<(With<JoinGameUI>, With<PlayNowUI>)>>,
     asset_server: Res<AssetServer>,
     mut match_not_found_error_event: EventReader<MatchNotFoundError>,
 ) {
@@ -493,6 +765,10 @@ pub fn reset_is_host(mut is_host: ResMut<IsHost>) {
     is_host.reset();
 }
 
+pub fn reset_is_spectator(mut is_spectator: ResMut<IsSpectator>) {
+    is_spectator.reset();
+}
+
 pub fn despawn_join_game_ui(
     mut commands: Commands,
     join_game_ui_query: Query<Entity, With<JoinGameUI>>,
@@ -2,8 +2,15 @@ use bevy::prelude::*;
 
 use crate::{
     LoggedInState,
+    bridge::LoggedOut,
+    config::GameConfig,
     connection::ConnectionState,
-    ui::join_game::{IsHost, MatchIdInput},
+    guest,
+    stats::StatsData,
+    ui::{
+        join_game::{IsHost, IsSpectator, MatchIdInput},
+        profile::ProfileData,
+    },
 };
 
 #[derive(Component)]
@@ -15,13 +22,45 @@ pub enum MainMenuState {
     MainMenu,
     PlayNow,
     JoinGame,
+    Practice,
+    Calibration,
+    Settings,
+    Replays,
+    Tournament,
     None,
 }
 
-const MAIN_MENU_BEFORE_LOGGEDIN_LIST: [&str; 2] = ["Login", "Quit"];
-const MAIN_MENU_AFTER_LOGGEDIN_LIST: [&str; 3] = ["Play Now", "Join", "Quit"];
+const MAIN_MENU_BEFORE_LOGGEDIN_LIST: [&str; 4] = ["Login", "Play as Guest", "Settings", "Quit"];
+const MAIN_MENU_OFFLINE_LIST: [&str; 3] = ["Play as Guest", "Settings", "Quit"];
+const MAIN_MENU_AFTER_LOGGEDIN_LIST: [&str; 10] = [
+    "Play Now",
+    "Join",
+    "Spectate",
+    "Tournament",
+    "Practice",
+    "Replays",
+    "Settings",
+    "Switch Account",
+    "Logout",
+    "Quit",
+];
+const MAIN_MENU_GUEST_LIST: [&str; 9] = [
+    "Play Now",
+    "Join",
+    "Spectate",
+    "Tournament",
+    "Practice",
+    "Replays",
+    "Settings",
+    "Logout",
+    "Quit",
+];
 
-pub fn spawn_main_menu_before_logged_in(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn spawn_main_menu_before_logged_in(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+) {
     let font = asset_server.load("fonts/pixeloid_mono.ttf");
     let font_bold = asset_server.load("fonts/pixeloid_mono_bold.ttf");
 
@@ -64,7 +103,13 @@ pub fn spawn_main_menu_before_logged_in(mut commands: Commands, asset_server: Re
                 });
         })
         .with_children(|parent| {
-            MAIN_MENU_BEFORE_LOGGEDIN_LIST.iter().for_each(|label| {
+            let labels: &[&str] = if config.offline {
+                &MAIN_MENU_OFFLINE_LIST
+            } else {
+                &MAIN_MENU_BEFORE_LOGGEDIN_LIST
+            };
+
+            labels.iter().for_each(|label| {
                 parent
                     .spawn((
                         Name::new(label.to_string()),
@@ -110,7 +155,11 @@ pub fn spawn_main_menu_before_logged_in(mut commands: Commands, asset_server: Re
         });
 }
 
-pub fn spawn_main_menu_after_logged_in(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn spawn_main_menu_after_logged_in(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    profile_data: Res<ProfileData>,
+) {
     let font = asset_server.load("fonts/pixeloid_mono.ttf");
     let font_bold = asset_server.load("fonts/pixeloid_mono_bold.ttf");
 
@@ -153,7 +202,13 @@ pub fn spawn_main_menu_after_logged_in(mut commands: Commands, asset_server: Res
                 });
         })
         .with_children(|parent| {
-            MAIN_MENU_AFTER_LOGGEDIN_LIST.iter().for_each(|label| {
+            let labels: &[&str] = if profile_data.guest {
+                &MAIN_MENU_GUEST_LIST
+            } else {
+                &MAIN_MENU_AFTER_LOGGEDIN_LIST
+            };
+
+            labels.iter().for_each(|label| {
                 parent
                     .spawn((
                         Name::new(label.to_string()),
@@ -199,13 +254,19 @@ pub fn spawn_main_menu_after_logged_in(mut commands: Commands, asset_server: Res
         });
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn main_menu_button_pressed_handler(
     button_query: Query<(&Interaction, &Name), Changed<Interaction>>,
     mut next_main_menu_state: ResMut<NextState<MainMenuState>>,
     mut next_connection_state: ResMut<NextState<ConnectionState>>,
     mut is_host: ResMut<IsHost>,
+    mut is_spectator: ResMut<IsSpectator>,
     mut match_id_input: ResMut<MatchIdInput>,
     mut next_logged_in_state: ResMut<NextState<LoggedInState>>,
+    mut logged_out: EventWriter<LoggedOut>,
+    mut profile_data: ResMut<ProfileData>,
+    mut stats_data: ResMut<StatsData>,
+    config: Res<GameConfig>,
 ) {
     for (interaction, name) in button_query.iter() {
         if *interaction != Interaction::Pressed {
@@ -214,10 +275,14 @@ pub fn main_menu_button_pressed_handler(
 
         match name.as_str() {
             "Login" => {
-                if webbrowser::open("http://localhost:3000").is_ok() {
+                if webbrowser::open(&config.api_base_url).is_ok() {
                     println!("Opening browser to login page...");
                 }
             }
+            "Play as Guest" => {
+                guest::start_guest_session(&mut profile_data, &mut stats_data);
+                next_logged_in_state.set(LoggedInState::LoggedIn);
+            }
             "Play Now" => {
                 is_host.0 = true;
                 next_main_menu_state.set(MainMenuState::PlayNow);
@@ -228,6 +293,36 @@ pub fn main_menu_button_pressed_handler(
                 match_id_input.0.clear();
                 next_main_menu_state.set(MainMenuState::JoinGame);
             }
+            "Spectate" => {
+                is_host.0 = false;
+                is_spectator.0 = true;
+                match_id_input.0.clear();
+                next_main_menu_state.set(MainMenuState::JoinGame);
+            }
+            "Tournament" => {
+                is_host.0 = false;
+                next_main_menu_state.set(MainMenuState::Tournament);
+                next_logged_in_state.set(LoggedInState::InGame);
+            }
+            "Practice" => {
+                next_main_menu_state.set(MainMenuState::Practice);
+            }
+            "Replays" => {
+                next_main_menu_state.set(MainMenuState::Replays);
+            }
+            "Settings" => {
+                next_main_menu_state.set(MainMenuState::Settings);
+            }
+            "Switch Account" => {
+                // The current account stays logged in until the new login arrives.
+                match webbrowser::open(&config.api_url("/switch-account")) {
+                    Ok(_) => info!("Opening browser to switch account..."),
+                    Err(e) => error!("❌ Could not open the browser to switch account: {:?}", e),
+                }
+            }
+            "Logout" => {
+                logged_out.write(LoggedOut);
+            }
             "Quit" => {
                 next_connection_state.set(ConnectionState::Idle);
                 std::process::exit(0)
@@ -1,5 +1,10 @@
+pub mod calibration;
 pub mod game_over;
 pub mod join_game;
 pub mod main_menu;
 pub mod play_now_ui;
+pub mod practice_ui;
 pub mod profile;
+pub mod replays_ui;
+pub mod settings_ui;
+pub mod tournament_ui;
@@ -7,7 +7,9 @@ use crate::{
     ClientChannel, ClientMessage, GameState, LoggedInState,
     connection::ConnectionState,
     player::{PlayerSelection, PlayersCounting},
+    shooting::{MatchSettings, ShootingStates},
     ui::{join_game::IsHost, main_menu::MainMenuState},
+    wallet::{self, MatchRequest, PendingMatchRequest},
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +24,32 @@ pub struct PlayNowText;
 #[derive(Component)]
 pub struct CopyMatchIdButton;
 
+/// The host's key pool, combo length, series and visibility buttons, gone once
+/// the match is set.
+#[derive(Component)]
+pub struct MatchSettingsButton;
+
+#[derive(Component, Debug, Clone, Copy)]
+pub enum MatchSettingsText {
+    KeyPool,
+    ComboLength,
+    Series,
+    Visibility,
+}
+
+impl MatchSettingsText {
+    pub fn label(&self, settings: &MatchSettings) -> String {
+        match self {
+            MatchSettingsText::KeyPool => format!("Keys: {}", settings.key_pool.label()),
+            MatchSettingsText::ComboLength => format!("Combo: {}", settings.combo_length),
+            MatchSettingsText::Series if settings.best_of == 1 => "Single Round".to_string(),
+            MatchSettingsText::Series => format!("Best of {}", settings.best_of),
+            MatchSettingsText::Visibility if settings.public => "Public".to_string(),
+            MatchSettingsText::Visibility => "Private".to_string(),
+        }
+    }
+}
+
 #[derive(Resource)]
 pub struct GameStartTimer {
     pub timer: Timer,
@@ -42,7 +70,12 @@ impl GameStartTimer {
     }
 }
 
-pub fn spawn_play_now_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn spawn_play_now_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    is_host: Res<IsHost>,
+    match_settings: Res<MatchSettings>,
+) {
     let font = asset_server.load("fonts/pixeloid_mono.ttf");
     let font_bold = asset_server.load("fonts/pixeloid_mono_bold.ttf");
 
@@ -85,6 +118,72 @@ pub fn spawn_play_now_ui(mut commands: Commands, asset_server: Res<AssetServer>)
                     ));
                 });
         })
+        .with_children(|parent| {
+            if !is_host.0 {
+                return;
+            }
+
+            parent
+                .spawn(Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(24.),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    [
+                        ("Key Pool", MatchSettingsText::KeyPool),
+                        ("Combo Length", MatchSettingsText::ComboLength),
+                        ("Series", MatchSettingsText::Series),
+                    ]
+                    .into_iter()
+                    .for_each(|(name, text)| {
+                        parent
+                            .spawn((
+                                MatchSettingsButton,
+                                Name::new(name),
+                                Button,
+                                Node {
+                                    width: Val::Px(360.),
+                                    height: Val::Px(88.),
+                                    position_type: PositionType::Relative,
+                                    border: UiRect {
+                                        left: Val::Px(2.),
+                                        right: Val::Px(2.),
+                                        top: Val::Px(2.),
+                                        bottom: Val::Px(2.),
+                                    },
+                                    ..Default::default()
+                                },
+                                BorderColor(Color::WHITE),
+                                BackgroundColor(Color::WHITE.with_alpha(0.0)),
+                            ))
+                            .with_children(|parent| {
+                                parent
+                                    .spawn(Node {
+                                        width: Val::Percent(100.),
+                                        height: Val::Percent(100.),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..Default::default()
+                                    })
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            text,
+                                            Text::new(text.label(&match_settings)),
+                                            TextColor(Color::WHITE),
+                                            TextLayout::new_with_justify(JustifyText::Center),
+                                            TextFont {
+                                                font: font.clone(),
+                                                font_size: 40.,
+                                                ..Default::default()
+                                            },
+                                        ));
+                                    });
+                            });
+                    });
+                });
+        })
         .with_children(|parent| {
             parent
                 .spawn((
@@ -129,6 +228,59 @@ pub fn spawn_play_now_ui(mut commands: Commands, asset_server: Res<AssetServer>)
                         });
                 });
 
+            // Public matches show up in the lobby browser of the Join Game screen.
+            if is_host.0 {
+                parent
+                    .spawn((
+                        MatchSettingsButton,
+                        Name::new("Visibility"),
+                        Button,
+                        Node {
+                            width: Val::Px(502.),
+                            height: Val::Px(88.),
+                            position_type: PositionType::Relative,
+                            border: UiRect {
+                                left: Val::Px(2.),
+                                right: Val::Px(2.),
+                                top: Val::Px(2.),
+                                bottom: Val::Px(2.),
+                            },
+                            margin: UiRect {
+                                left: Val::Px(0.),
+                                right: Val::Px(0.),
+                                top: Val::Px(24.),
+                                bottom: Val::Px(0.),
+                            },
+                            ..Default::default()
+                        },
+                        BorderColor(Color::WHITE),
+                        BackgroundColor(Color::WHITE.with_alpha(0.0)),
+                    ))
+                    .with_children(|parent| {
+                        parent
+                            .spawn(Node {
+                                width: Val::Percent(100.),
+                                height: Val::Percent(100.),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    MatchSettingsText::Visibility,
+                                    Text::new(MatchSettingsText::Visibility.label(&match_settings)),
+                                    TextColor(Color::WHITE),
+                                    TextLayout::new_with_justify(JustifyText::Center),
+                                    TextFont {
+                                        font: font.clone(),
+                                        font_size: 48.,
+                                        ..Default::default()
+                                    },
+                                ));
+                            });
+                    });
+            }
+
             parent
                 .spawn((
                     Name::new("Back"),
@@ -183,17 +335,16 @@ pub fn create_room(
     mut client: ResMut<QuinnetClient>,
     player_selection: Res<PlayerSelection>,
     is_host: Res<IsHost>,
+    mut pending_match_request: ResMut<PendingMatchRequest>,
 ) {
     if !is_host.0 {
         return;
     }
 
-    let _ = client.connection_mut().send_message_on(
-        ClientChannel::Lobby,
-        ClientMessage::CreateMatchRequest {
-            match_id: player_selection.1,
-            player_wallet: "".to_string(),
-        },
+    wallet::request_match(
+        &mut client,
+        &mut pending_match_request,
+        MatchRequest::Create(player_selection.1),
     );
 }
 
@@ -209,6 +360,20 @@ pub fn update_play_now_text(
     }
 }
 
+pub fn update_match_settings_text(
+    match_settings: Res<MatchSettings>,
+    mut text_query: Query<(&mut Text, &MatchSettingsText)>,
+) {
+    if !match_settings.is_changed() {
+        return;
+    }
+
+    for (mut text, settings_text) in text_query.iter_mut() {
+        *text = Text::new(settings_text.label(&match_settings));
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn update_game_start_countdown(
     mut commands: Commands,
     time: Res<Time>,
@@ -216,13 +381,14 @@ pub fn update_game_start_countdown(
     mut text_query: Query<&mut Text, With<PlayNowText>>,
     mut next_main_menu_state: ResMut<NextState<MainMenuState>>,
     mut next_game_state: ResMut<NextState<GameState>>,
-    copy_match_id_button_query: Query<Entity, With<CopyMatchIdButton>>,
+    button_query: Query<Entity, Or<(With<CopyMatchIdButton>, With<MatchSettingsButton>)>>,
+    shooting_states: Res<ShootingStates>,
 ) {
     if !countdown.active {
         return;
     }
 
-    for entity in copy_match_id_button_query.iter() {
+    for entity in button_query.iter() {
         commands.entity(entity).despawn();
     }
 
@@ -230,7 +396,12 @@ pub fn update_game_start_countdown(
 
     for mut text in text_query.iter_mut() {
         let remaining = countdown.timer.remaining_secs().ceil();
-        *text = Text::new(format!("Game starts in: {}", remaining));
+        *text = Text::new(format!(
+            "Game starts in: {}\n{} keys, combo of {}",
+            remaining,
+            shooting_states.settings.key_pool.label(),
+            shooting_states.settings.combo_length
+        ));
     }
 
     if countdown.timer.finished() {
@@ -258,6 +429,7 @@ pub fn play_now_ui_interaction(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn play_now_button_pressed_handler(
     button_query: Query<(&Interaction, &Name), Changed<Interaction>>,
     mut next_game_state: ResMut<NextState<GameState>>,
@@ -265,6 +437,8 @@ pub fn play_now_button_pressed_handler(
     mut connection_state: ResMut<NextState<ConnectionState>>,
     mut player_selection: ResMut<PlayerSelection>,
     mut next_logged_in_state: ResMut<NextState<LoggedInState>>,
+    mut match_settings: ResMut<MatchSettings>,
+    mut client: ResMut<QuinnetClient>,
 ) {
     for (interaction, name) in button_query.iter() {
         if *interaction != Interaction::Pressed {
@@ -272,6 +446,22 @@ pub fn play_now_button_pressed_handler(
         }
 
         match name.as_str() {
+            "Key Pool" | "Combo Length" | "Series" | "Visibility" => {
+                match name.as_str() {
+                    "Key Pool" => match_settings.key_pool = match_settings.key_pool.next(),
+                    "Combo Length" => match_settings.next_combo_length(),
+                    "Series" => match_settings.next_best_of(),
+                    _ => match_settings.public = !match_settings.public,
+                }
+
+                let _ = client.connection_mut().send_message_on(
+                    ClientChannel::Lobby,
+                    ClientMessage::UpdateMatchSettings {
+                        match_id: player_selection.1,
+                        settings: *match_settings,
+                    },
+                );
+            }
             "Copy Match ID" => {
                 if let Ok(mut clipboard) = Clipboard::new() {
                     clipboard.set_text(player_selection.1.to_string()).ok();
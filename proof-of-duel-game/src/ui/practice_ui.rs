@@ -0,0 +1,210 @@
+use bevy::prelude::*;
+
+use crate::{
+    GameState, LoggedInState,
+    local_duel::{self, LocalDuelStates},
+    player::{MatchPlayers, PlayerHertsStatus, PlayerSelection},
+    practice::{self, PracticeDifficulty, PracticeMatch},
+    shooting::{MatchSettings, ShootingStates},
+    ui::{main_menu::MainMenuState, profile::ProfileData},
+};
+
+#[derive(Component)]
+pub struct PracticeUI;
+
+pub fn spawn_practice_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/pixeloid_mono.ttf");
+    let font_bold = asset_server.load("fonts/pixeloid_mono_bold.ttf");
+
+    let labels = PracticeDifficulty::ALL
+        .iter()
+        .map(|difficulty| difficulty.label())
+        .chain(["Local Duel", "Calibrate Timing", "Back"]);
+
+    commands
+        .spawn((
+            PracticeUI,
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::SpaceEvenly,
+                ..Default::default()
+            },
+            BackgroundColor(Color::NONE),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn({
+                    Node {
+                        width: Val::Percent(100.),
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        position_type: PositionType::Relative,
+                        ..Default::default()
+                    }
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Practice"),
+                        TextColor(Color::WHITE),
+                        TextLayout::new_with_justify(JustifyText::Center),
+                        TextFont {
+                            font: font_bold.clone(),
+                            font_size: 64.,
+                            ..Default::default()
+                        },
+                    ));
+                    parent.spawn((
+                        Text::new(
+                            "Duel the AI gunslinger or a friend on this keyboard, no stats are recorded",
+                        ),
+                        TextColor(Color::WHITE),
+                        TextLayout::new_with_justify(JustifyText::Center),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 24.,
+                            ..Default::default()
+                        },
+                    ));
+                });
+        })
+        .with_children(|parent| {
+            labels.for_each(|label| {
+                parent
+                    .spawn((
+                        Name::new(label.to_string()),
+                        Button,
+                        Node {
+                            width: Val::Px(502.),
+                            height: Val::Px(88.),
+                            position_type: PositionType::Relative,
+                            border: UiRect {
+                                left: Val::Px(2.),
+                                right: Val::Px(2.),
+                                top: Val::Px(2.),
+                                bottom: Val::Px(2.),
+                            },
+                            ..Default::default()
+                        },
+                        BorderColor(Color::WHITE),
+                        BackgroundColor(Color::WHITE.with_alpha(0.0)),
+                    ))
+                    .with_children(|parent| {
+                        parent
+                            .spawn(Node {
+                                width: Val::Percent(100.),
+                                height: Val::Percent(100.),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..Default::default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    Text::new(label.to_string()),
+                                    TextColor(Color::WHITE),
+                                    TextLayout::new_with_justify(JustifyText::Center),
+                                    TextFont {
+                                        font: font.clone(),
+                                        font_size: 48.,
+                                        ..Default::default()
+                                    },
+                                ));
+                            });
+                    });
+            })
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn practice_button_pressed_handler(
+    button_query: Query<(&Interaction, &Name), Changed<Interaction>>,
+    mut next_main_menu_state: ResMut<NextState<MainMenuState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_logged_in_state: ResMut<NextState<LoggedInState>>,
+    mut practice_match: ResMut<PracticeMatch>,
+    mut player_selection: ResMut<PlayerSelection>,
+    mut match_players: ResMut<MatchPlayers>,
+    mut player_hearts_status: ResMut<PlayerHertsStatus>,
+    mut shooting_states: ResMut<ShootingStates>,
+    mut local_duel_states: ResMut<LocalDuelStates>,
+    match_settings: Res<MatchSettings>,
+    profile_data: Res<ProfileData>,
+) {
+    for (interaction, name) in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match name.as_str() {
+            "Calibrate Timing" => {
+                next_main_menu_state.set(MainMenuState::Calibration);
+                continue;
+            }
+            "Back" => {
+                next_main_menu_state.set(MainMenuState::MainMenu);
+                next_logged_in_state.set(LoggedInState::LoggedIn);
+                continue;
+            }
+            "Local Duel" => {
+                local_duel::start_local_duel(
+                    &mut practice_match,
+                    &mut player_selection,
+                    &mut match_players,
+                    &mut player_hearts_status,
+                    &mut shooting_states,
+                    &mut local_duel_states,
+                    *match_settings,
+                    &profile_data,
+                );
+            }
+            _ => {
+                let Some(difficulty) = PracticeDifficulty::from_label(name.as_str()) else {
+                    continue;
+                };
+
+                practice::start_practice_match(
+                    difficulty,
+                    &mut practice_match,
+                    &mut player_selection,
+                    &mut match_players,
+                    &mut player_hearts_status,
+                    &mut shooting_states,
+                    *match_settings,
+                    &profile_data,
+                );
+            }
+        }
+
+        next_main_menu_state.set(MainMenuState::None);
+        next_game_state.set(GameState::InGame);
+        next_logged_in_state.set(LoggedInState::InGame);
+    }
+}
+
+pub fn practice_ui_interaction(
+    mut button_query: Query<(&Interaction, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    for (interaction, mut color) in button_query.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = BackgroundColor(Color::srgba(0.8, 0.8, 0.8, 0.15));
+            }
+            Interaction::Hovered => {
+                *color = BackgroundColor(Color::srgba(0.8, 0.8, 0.8, 0.07));
+            }
+            Interaction::None => {
+                *color = BackgroundColor(Color::NONE);
+            }
+        }
+    }
+}
+
+pub fn despawn_practice_ui(mut commands: Commands, query: Query<Entity, With<PracticeUI>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,7 +1,11 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::stats::StatsData;
+use crate::{
+    config::GameConfig,
+    duel_profile::{self, DuelProfile, DuelProfileStatus},
+    stats::{StatsClient, StatsData, StatsFreshness},
+};
 
 #[derive(Component)]
 pub struct ProfileUI;
@@ -15,16 +19,46 @@ pub struct WinText;
 #[derive(Component)]
 pub struct LossText;
 
+#[derive(Component)]
+pub struct StatsFreshnessText;
+
+#[derive(Component)]
+pub struct DuelProfileText;
+
+#[derive(Component)]
+pub struct CreateDuelProfileButton;
+
 #[derive(Resource, Default, Clone)]
 pub struct ProfileData {
     pub logged_in: bool,
     pub public_key: String,
     pub username: String,
+    pub expires_at: i64,
+    /// Playing without a login, see `guest`.
+    pub guest: bool,
+}
+
+impl ProfileData {
+    /// Whether the player has a wallet whose stats live on-chain.
+    pub fn is_online(&self) -> bool {
+        self.logged_in && !self.guest
+    }
+
+    pub fn reset(&mut self) {
+        self.logged_in = false;
+        self.guest = false;
+        self.public_key.clear();
+        self.username.clear();
+        self.expires_at = 0;
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DuelInfoPayload {
     pub public_key: String,
+    /// Whether the frontend should also push the record to `/update-stats`,
+    /// which is only wanted for the logged-in player's own stats.
+    pub notify: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -163,6 +197,105 @@ pub fn spawn_profile_ui(mut commands: Commands, asset_server: Res<AssetServer>)
                         },
                     ));
                 });
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    position_type: PositionType::Relative,
+                    padding: UiRect {
+                        left: Val::Px(8.),
+                        right: Val::Px(8.),
+                        top: Val::Px(8.),
+                        bottom: Val::Px(8.),
+                    },
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        StatsFreshnessText,
+                        Text::new(""),
+                        TextColor(Color::srgba(1.0, 222. / 255., 99. / 255., 1.0)),
+                        TextLayout::new_with_justify(JustifyText::Left),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 28.,
+                            ..Default::default()
+                        },
+                    ));
+                });
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    display: Display::Flex,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    position_type: PositionType::Relative,
+                    padding: UiRect {
+                        left: Val::Px(8.),
+                        right: Val::Px(8.),
+                        top: Val::Px(8.),
+                        bottom: Val::Px(8.),
+                    },
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        DuelProfileText,
+                        Text::new(""),
+                        TextColor(Color::srgba(1.0, 222. / 255., 99. / 255., 1.0)),
+                        TextLayout::new_with_justify(JustifyText::Left),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 28.,
+                            ..Default::default()
+                        },
+                    ));
+                });
+        })
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    CreateDuelProfileButton,
+                    Name::new("Create Duel Profile"),
+                    Button,
+                    Node {
+                        display: Display::None,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        position_type: PositionType::Relative,
+                        padding: UiRect {
+                            left: Val::Px(8.),
+                            right: Val::Px(8.),
+                            top: Val::Px(8.),
+                            bottom: Val::Px(8.),
+                        },
+                        border: UiRect {
+                            left: Val::Px(2.),
+                            right: Val::Px(2.),
+                            top: Val::Px(2.),
+                            bottom: Val::Px(2.),
+                        },
+                        ..Default::default()
+                    },
+                    BorderColor(Color::WHITE),
+                    BackgroundColor(Color::NONE),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Create duel profile"),
+                        TextColor(Color::WHITE),
+                        TextLayout::new_with_justify(JustifyText::Left),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 28.,
+                            ..Default::default()
+                        },
+                    ));
+                });
         });
 }
 
@@ -202,3 +335,66 @@ pub fn update_loss(
         *text = format!("Loss: {}", stats_data.loss.to_owned()).into();
     }
 }
+
+pub fn update_stats_freshness(
+    stats_client: Res<StatsClient>,
+    mut query: Query<&mut Text, With<StatsFreshnessText>>,
+    profile_data: Res<ProfileData>,
+) {
+    let label = match stats_client.freshness {
+        _ if !profile_data.logged_in => "",
+        StatsFreshness::Fresh => "",
+        StatsFreshness::Stale => "Stale",
+        StatsFreshness::Offline => "Offline",
+    };
+
+    for mut text in query.iter_mut() {
+        *text = label.into();
+    }
+}
+
+pub fn update_duel_profile(
+    duel_profile: Res<DuelProfile>,
+    profile_data: Res<ProfileData>,
+    mut text_query: Query<&mut Text, With<DuelProfileText>>,
+    mut button_query: Query<&mut Node, With<CreateDuelProfileButton>>,
+) {
+    if !duel_profile.is_changed() && !profile_data.is_changed() {
+        return;
+    }
+
+    let label = match &duel_profile.status {
+        _ if !profile_data.logged_in => String::new(),
+        DuelProfileStatus::Unknown | DuelProfileStatus::Ready => String::new(),
+        DuelProfileStatus::Missing => "No duel profile yet".to_string(),
+        DuelProfileStatus::Creating => "Creating duel profile...".to_string(),
+        DuelProfileStatus::Created => "Duel profile created".to_string(),
+        DuelProfileStatus::Failed(e) => format!("Duel profile failed: {}", e),
+    };
+
+    for mut text in text_query.iter_mut() {
+        *text = label.clone().into();
+    }
+
+    let display = if profile_data.logged_in && duel_profile.can_create() {
+        Display::Flex
+    } else {
+        Display::None
+    };
+
+    for mut node in button_query.iter_mut() {
+        node.display = display;
+    }
+}
+
+pub fn duel_profile_button_pressed_handler(
+    button_query: Query<&Interaction, (Changed<Interaction>, With<CreateDuelProfileButton>)>,
+    mut duel_profile: ResMut<DuelProfile>,
+    config: Res<GameConfig>,
+) {
+    for interaction in button_query.iter() {
+        if *interaction == Interaction::Pressed {
+            duel_profile::create_duel_profile(&mut duel_profile, &config);
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+
+use crate::{
+    GameState, LoggedInState,
+    player::{MatchPlayers, PlayerHertsStatus, PlayerSelection, PlayersCounting},
+    replay::{self, MatchReplay, REPLAY_DIR, ReplayPlayback},
+    series::SeriesScore,
+    shooting::ShootingStates,
+    ui::{
+        game_over::{MatchCommitment, WhoIsWinner},
+        main_menu::MainMenuState,
+        play_now_ui::GameStartTimer,
+    },
+};
+
+/// How many of the newest replays the screen lists.
+const LISTED_REPLAYS: usize = 6;
+
+#[derive(Component)]
+pub struct ReplaysUI;
+
+/// The replay file a button plays.
+#[derive(Component, Debug, Clone)]
+pub struct ReplayFile(pub PathBuf);
+
+pub fn spawn_replays_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/pixeloid_mono.ttf");
+    let font_bold = asset_server.load("fonts/pixeloid_mono_bold.ttf");
+
+    let replays = MatchReplay::list(Path::new(REPLAY_DIR));
+
+    let subtitle = if replays.is_empty() {
+        "No replays yet, finish an online match to record one"
+    } else {
+        "Pick a match to watch it again"
+    };
+
+    let buttons = replays
+        .into_iter()
+        .take(LISTED_REPLAYS)
+        .map(|(path, replay)| (replay.label(), Some(ReplayFile(path))))
+        .chain([("Back".to_string(), None)]);
+
+    commands
+        .spawn((
+            ReplaysUI,
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::SpaceEvenly,
+                ..Default::default()
+            },
+            BackgroundColor(Color::NONE),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn({
+                    Node {
+                        width: Val::Percent(100.),
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        position_type: PositionType::Relative,
+                        ..Default::default()
+                    }
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Replays"),
+                        TextColor(Color::WHITE),
+                        TextLayout::new_with_justify(JustifyText::Center),
+                        TextFont {
+                            font: font_bold.clone(),
+                            font_size: 64.,
+                            ..Default::default()
+                        },
+                    ));
+                    parent.spawn((
+                        Text::new(subtitle),
+                        TextColor(Color::WHITE),
+                        TextLayout::new_with_justify(JustifyText::Center),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 24.,
+                            ..Default::default()
+                        },
+                    ));
+                });
+        })
+        .with_children(|parent| {
+            buttons.for_each(|(label, replay_file)| {
+                let name = if replay_file.is_some() {
+                    "Replay"
+                } else {
+                    "Back"
+                };
+
+                let mut button = parent.spawn((
+                    Name::new(name.to_string()),
+                    Button,
+                    Node {
+                        width: Val::Px(800.),
+                        height: Val::Px(72.),
+                        position_type: PositionType::Relative,
+                        border: UiRect {
+                            left: Val::Px(2.),
+                            right: Val::Px(2.),
+                            top: Val::Px(2.),
+                            bottom: Val::Px(2.),
+                        },
+                        ..Default::default()
+                    },
+                    BorderColor(Color::WHITE),
+                    BackgroundColor(Color::WHITE.with_alpha(0.0)),
+                ));
+
+                if let Some(replay_file) = replay_file {
+                    button.insert(replay_file);
+                }
+
+                button.with_children(|parent| {
+                    parent
+                        .spawn(Node {
+                            width: Val::Percent(100.),
+                            height: Val::Percent(100.),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new(label),
+                                TextColor(Color::WHITE),
+                                TextLayout::new_with_justify(JustifyText::Center),
+                                TextFont {
+                                    font: font.clone(),
+                                    font_size: 32.,
+                                    ..Default::default()
+                                },
+                            ));
+                        });
+                });
+            })
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn replays_button_pressed_handler(
+    button_query: Query<(&Interaction, &Name, Option<&ReplayFile>), Changed<Interaction>>,
+    mut next_main_menu_state: ResMut<NextState<MainMenuState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut next_logged_in_state: ResMut<NextState<LoggedInState>>,
+    mut replay_playback: ResMut<ReplayPlayback>,
+    mut player_selection: ResMut<PlayerSelection>,
+    mut players_counting: ResMut<PlayersCounting>,
+    mut game_start_timer: ResMut<GameStartTimer>,
+    mut player_hearts_status: ResMut<PlayerHertsStatus>,
+    mut who_is_winner: ResMut<WhoIsWinner>,
+    mut match_commitment: ResMut<MatchCommitment>,
+    mut match_players: ResMut<MatchPlayers>,
+    mut shooting_states: ResMut<ShootingStates>,
+    mut series_score: ResMut<SeriesScore>,
+) {
+    for (interaction, name, replay_file) in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        if name.as_str() == "Back" {
+            next_main_menu_state.set(MainMenuState::MainMenu);
+            next_logged_in_state.set(LoggedInState::LoggedIn);
+            continue;
+        }
+
+        let Some(ReplayFile(path)) = replay_file else {
+            continue;
+        };
+
+        let replay = match MatchReplay::load(path) {
+            Ok(replay) => replay,
+            Err(e) => {
+                error!("❌ Could not load replay {:?}: {}", path, e);
+                continue;
+            }
+        };
+
+        let start_secs = replay.start_secs();
+
+        replay_playback.reset();
+        replay_playback.replay = Some(replay);
+
+        replay::restart_replay(
+            start_secs,
+            &mut replay_playback,
+            &mut player_selection,
+            &mut players_counting,
+            &mut game_start_timer,
+            &mut player_hearts_status,
+            &mut who_is_winner,
+            &mut match_commitment,
+            &mut match_players,
+            &mut shooting_states,
+            &mut series_score,
+            &mut next_game_state,
+        );
+
+        next_main_menu_state.set(MainMenuState::None);
+        next_logged_in_state.set(LoggedInState::InGame);
+    }
+}
+
+pub fn replays_ui_interaction(
+    mut button_query: Query<(&Interaction, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    for (interaction, mut color) in button_query.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = BackgroundColor(Color::srgba(0.8, 0.8, 0.8, 0.15));
+            }
+            Interaction::Hovered => {
+                *color = BackgroundColor(Color::srgba(0.8, 0.8, 0.8, 0.07));
+            }
+            Interaction::None => {
+                *color = BackgroundColor(Color::NONE);
+            }
+        }
+    }
+}
+
+pub fn despawn_replays_ui(mut commands: Commands, query: Query<Entity, With<ReplaysUI>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,408 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+
+use crate::{
+    LoggedInState,
+    settings::{self, UserSettings},
+    shooting::keycode::{self, ALL_KEYS},
+    ui::main_menu::MainMenuState,
+};
+
+#[derive(Component)]
+pub struct SettingsUI;
+
+/// Holds the buttons of the page being shown, rebuilt when the page changes.
+#[derive(Component)]
+pub struct SettingsContent;
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SettingsPage {
+    #[default]
+    Controls,
+    Audio,
+    Display,
+}
+
+impl SettingsPage {
+    pub const ALL: [SettingsPage; 3] = [
+        SettingsPage::Controls,
+        SettingsPage::Audio,
+        SettingsPage::Display,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SettingsPage::Controls => "Controls",
+            SettingsPage::Audio => "Audio",
+            SettingsPage::Display => "Display",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|page| page.label() == label)
+    }
+
+    fn fields(&self) -> Vec<SettingField> {
+        match self {
+            SettingsPage::Controls => settings::combo_symbols()
+                .into_iter()
+                .map(SettingField::Binding)
+                .collect(),
+            SettingsPage::Audio => vec![SettingField::MusicVolume, SettingField::SfxVolume],
+            SettingsPage::Display => vec![
+                SettingField::Fullscreen,
+                SettingField::Resolution,
+                SettingField::Vsync,
+            ],
+        }
+    }
+}
+
+/// The combo symbol waiting for its new key, if any.
+#[derive(Resource, Debug, Default)]
+pub struct Rebinding(pub Option<KeyCode>);
+
+/// One setting button. The same component sits on its text, which is
+/// relabelled whenever the settings change.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingField {
+    Binding(KeyCode),
+    MusicVolume,
+    SfxVolume,
+    Fullscreen,
+    Resolution,
+    Vsync,
+}
+
+impl SettingField {
+    pub fn label(&self, user_settings: &UserSettings, rebinding: &Rebinding) -> String {
+        let on_off = |on: bool| if on { "On" } else { "Off" };
+
+        match self {
+            SettingField::Binding(symbol) if rebinding.0 == Some(*symbol) => {
+                format!("{}: press a key", keycode::key_label(*symbol))
+            }
+            SettingField::Binding(symbol) => format!(
+                "{}: {}",
+                keycode::key_label(*symbol),
+                keycode::key_label(user_settings.key_for(*symbol))
+            ),
+            SettingField::MusicVolume => {
+                format!("Music: {:.0}%", user_settings.music_volume * 100.0)
+            }
+            SettingField::SfxVolume => format!("SFX: {:.0}%", user_settings.sfx_volume * 100.0),
+            SettingField::Fullscreen => format!("Fullscreen: {}", on_off(user_settings.fullscreen)),
+            SettingField::Resolution => {
+                let (width, height) = user_settings.resolution;
+                format!("Resolution: {}x{}", width, height)
+            }
+            SettingField::Vsync => format!("VSync: {}", on_off(user_settings.vsync)),
+        }
+    }
+}
+
+fn spawn_settings_button(
+    parent: &mut ChildSpawnerCommands,
+    name: &str,
+    label: String,
+    width: f32,
+    font: Handle<Font>,
+    field: Option<SettingField>,
+) {
+    let mut button = parent.spawn((
+        Name::new(name.to_string()),
+        Button,
+        Node {
+            width: Val::Px(width),
+            height: Val::Px(64.),
+            position_type: PositionType::Relative,
+            border: UiRect {
+                left: Val::Px(2.),
+                right: Val::Px(2.),
+                top: Val::Px(2.),
+                bottom: Val::Px(2.),
+            },
+            ..Default::default()
+        },
+        BorderColor(Color::WHITE),
+        BackgroundColor(Color::WHITE.with_alpha(0.0)),
+    ));
+
+    if let Some(field) = field {
+        button.insert(field);
+    }
+
+    button.with_children(|parent| {
+        parent
+            .spawn(Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            })
+            .with_children(|parent| {
+                let mut text = parent.spawn((
+                    Text::new(label),
+                    TextColor(Color::WHITE),
+                    TextLayout::new_with_justify(JustifyText::Center),
+                    TextFont {
+                        font,
+                        font_size: 32.,
+                        ..Default::default()
+                    },
+                ));
+
+                if let Some(field) = field {
+                    text.insert(field);
+                }
+            });
+    });
+}
+
+pub fn spawn_settings_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    let font = asset_server.load("fonts/pixeloid_mono.ttf");
+    let font_bold = asset_server.load("fonts/pixeloid_mono_bold.ttf");
+
+    rebinding.0 = None;
+
+    commands
+        .spawn((
+            SettingsUI,
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::SpaceEvenly,
+                ..Default::default()
+            },
+            BackgroundColor(Color::NONE),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Settings"),
+                TextColor(Color::WHITE),
+                TextLayout::new_with_justify(JustifyText::Center),
+                TextFont {
+                    font: font_bold.clone(),
+                    font_size: 64.,
+                    ..Default::default()
+                },
+            ));
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(24.),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    SettingsPage::ALL.iter().for_each(|page| {
+                        spawn_settings_button(
+                            parent,
+                            page.label(),
+                            page.label().to_string(),
+                            300.,
+                            font.clone(),
+                            None,
+                        );
+                    });
+                });
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                SettingsContent,
+                Node {
+                    width: Val::Px(1100.),
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    flex_wrap: FlexWrap::Wrap,
+                    justify_content: JustifyContent::Center,
+                    column_gap: Val::Px(24.),
+                    row_gap: Val::Px(16.),
+                    ..Default::default()
+                },
+            ));
+        })
+        .with_children(|parent| {
+            spawn_settings_button(parent, "Back", "Back".to_string(), 502., font.clone(), None);
+        });
+}
+
+pub fn spawn_settings_page(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings_page: Res<SettingsPage>,
+    user_settings: Res<UserSettings>,
+    rebinding: Res<Rebinding>,
+    content_query: Query<(Entity, Ref<SettingsContent>)>,
+) {
+    let font = asset_server.load("fonts/pixeloid_mono.ttf");
+
+    for (entity, content) in content_query.iter() {
+        if !settings_page.is_changed() && !content.is_added() {
+            continue;
+        }
+
+        commands
+            .entity(entity)
+            .despawn_related::<Children>()
+            .with_children(|parent| {
+                for field in settings_page.fields() {
+                    spawn_settings_button(
+                        parent,
+                        "Setting",
+                        field.label(&user_settings, &rebinding),
+                        340.,
+                        font.clone(),
+                        Some(field),
+                    );
+                }
+
+                if *settings_page == SettingsPage::Controls {
+                    spawn_settings_button(
+                        parent,
+                        "Reset Controls",
+                        "Reset Controls".to_string(),
+                        340.,
+                        font.clone(),
+                        None,
+                    );
+                }
+            });
+    }
+}
+
+pub fn update_settings_text(
+    user_settings: Res<UserSettings>,
+    rebinding: Res<Rebinding>,
+    mut text_query: Query<(&mut Text, &SettingField)>,
+) {
+    if !user_settings.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+
+    for (mut text, field) in text_query.iter_mut() {
+        *text = Text::new(field.label(&user_settings, &rebinding));
+    }
+}
+
+/// While a symbol is being rebound, the next key press becomes its key.
+/// Escape cancels.
+pub fn capture_rebinding(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut user_settings: ResMut<UserSettings>,
+) {
+    let Some(symbol) = rebinding.0 else {
+        return;
+    };
+
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        rebinding.0 = None;
+        return;
+    }
+
+    let Some(key) = ALL_KEYS
+        .into_iter()
+        .find(|key| keyboard_input.just_pressed(*key))
+    else {
+        return;
+    };
+
+    user_settings.bind(symbol, key);
+    rebinding.0 = None;
+
+    save_user_settings(&user_settings);
+}
+
+fn save_user_settings(user_settings: &UserSettings) {
+    if let Err(e) = user_settings.save(Path::new(settings::SETTINGS_FILE)) {
+        error!("❌ Could not save settings: {:?}", e);
+    }
+}
+
+pub fn settings_button_pressed_handler(
+    button_query: Query<(&Interaction, &Name, Option<&SettingField>), Changed<Interaction>>,
+    mut next_main_menu_state: ResMut<NextState<MainMenuState>>,
+    logged_in_state: Res<State<LoggedInState>>,
+    mut next_logged_in_state: ResMut<NextState<LoggedInState>>,
+    mut settings_page: ResMut<SettingsPage>,
+    mut user_settings: ResMut<UserSettings>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    for (interaction, name, field) in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        if let Some(field) = field {
+            match field {
+                SettingField::Binding(symbol) => {
+                    rebinding.0 = Some(*symbol);
+                    continue;
+                }
+                SettingField::MusicVolume => user_settings.next_music_volume(),
+                SettingField::SfxVolume => user_settings.next_sfx_volume(),
+                SettingField::Fullscreen => user_settings.fullscreen = !user_settings.fullscreen,
+                SettingField::Resolution => user_settings.next_resolution(),
+                SettingField::Vsync => user_settings.vsync = !user_settings.vsync,
+            }
+
+            save_user_settings(&user_settings);
+            continue;
+        }
+
+        if let Some(page) = SettingsPage::from_label(name.as_str()) {
+            rebinding.0 = None;
+            settings_page.set_if_neq(page);
+            continue;
+        }
+
+        match name.as_str() {
+            "Reset Controls" => {
+                rebinding.0 = None;
+                user_settings.reset_bindings();
+                save_user_settings(&user_settings);
+            }
+            "Back" => {
+                next_main_menu_state.set(MainMenuState::MainMenu);
+                // Re-entering the login state respawns the main menu.
+                next_logged_in_state.set(logged_in_state.get().clone());
+            }
+            _ => return,
+        }
+    }
+}
+
+pub fn settings_ui_interaction(
+    mut button_query: Query<(&Interaction, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    for (interaction, mut color) in button_query.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = BackgroundColor(Color::srgba(0.8, 0.8, 0.8, 0.15));
+            }
+            Interaction::Hovered => {
+                *color = BackgroundColor(Color::srgba(0.8, 0.8, 0.8, 0.07));
+            }
+            Interaction::None => {
+                *color = BackgroundColor(Color::NONE);
+            }
+        }
+    }
+}
+
+pub fn despawn_settings_ui(mut commands: Commands, query: Query<Entity, With<SettingsUI>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
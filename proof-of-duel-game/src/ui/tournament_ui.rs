@@ -0,0 +1,515 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy_quinnet::client::QuinnetClient;
+use uuid::Uuid;
+
+use crate::{
+    ClientChannel, ClientMessage, LoggedInState, ServerMessage,
+    connection::{ConnectionState, ServerMessageReceived},
+    player::{PlayerHertsStatus, ShootingLock},
+    shooting::MatchSettings,
+    tournament::{self, Bracket, MAX_CODE_LEN, TOURNAMENT_DIR},
+    ui::{
+        game_over::{MatchCommitment, WhoIsWinner},
+        join_game::{MatchIdInput, MatchIdTextInput},
+        main_menu::MainMenuState,
+        play_now_ui::GameStartTimer,
+    },
+    wallet::{self, MatchRequest, PendingMatchRequest},
+};
+
+const TOURNAMENT_BUTTONS: [&str; 6] = ["Create", "Register", "Watch", "Start", "Export", "Back"];
+
+#[derive(Component)]
+pub struct TournamentUI;
+
+#[derive(Component)]
+pub struct TournamentStatusText;
+
+/// Holds the bracket columns, rebuilt whenever the bracket changes.
+#[derive(Component)]
+pub struct BracketContent;
+
+/// This client's place in a tournament. It outlives the connection, so the
+/// spot is claimed back when the screen is opened again.
+#[derive(Resource, Debug, Default)]
+pub struct TournamentEntry {
+    pub code: Option<String>,
+    pub entry_token: Option<Uuid>,
+    pub needs_rejoin: bool,
+}
+
+/// The last thing the server or the screen had to say.
+#[derive(Resource, Debug, Default)]
+pub struct TournamentStatus(pub String);
+
+/// A random code for a new tournament.
+fn new_code() -> String {
+    Uuid::new_v4().simple().to_string()[..6].to_uppercase()
+}
+
+fn spawn_tournament_button(parent: &mut ChildSpawnerCommands, label: &str, font: Handle<Font>) {
+    parent
+        .spawn((
+            Name::new(label.to_string()),
+            Button,
+            Node {
+                width: Val::Px(180.),
+                height: Val::Px(64.),
+                position_type: PositionType::Relative,
+                border: UiRect {
+                    left: Val::Px(2.),
+                    right: Val::Px(2.),
+                    top: Val::Px(2.),
+                    bottom: Val::Px(2.),
+                },
+                ..Default::default()
+            },
+            BorderColor(Color::WHITE),
+            BackgroundColor(Color::WHITE.with_alpha(0.0)),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new(label.to_string()),
+                        TextColor(Color::WHITE),
+                        TextLayout::new_with_justify(JustifyText::Center),
+                        TextFont {
+                            font,
+                            font_size: 28.,
+                            ..Default::default()
+                        },
+                    ));
+                });
+        });
+}
+
+pub fn spawn_tournament_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut match_id_input: ResMut<MatchIdInput>,
+    mut tournament_entry: ResMut<TournamentEntry>,
+) {
+    let font = asset_server.load("fonts/pixeloid_mono.ttf");
+    let font_bold = asset_server.load("fonts/pixeloid_mono_bold.ttf");
+
+    match_id_input.0 = tournament_entry.code.clone().unwrap_or_default();
+    tournament_entry.needs_rejoin = tournament_entry.entry_token.is_some();
+
+    commands
+        .spawn((
+            TournamentUI,
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::SpaceEvenly,
+                ..Default::default()
+            },
+            BackgroundColor(Color::NONE),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Tournament"),
+                TextColor(Color::WHITE),
+                TextLayout::new_with_justify(JustifyText::Center),
+                TextFont {
+                    font: font_bold.clone(),
+                    font_size: 64.,
+                    ..Default::default()
+                },
+            ));
+            parent.spawn((
+                TournamentStatusText,
+                Text::new(""),
+                TextColor(Color::WHITE),
+                TextLayout::new_with_justify(JustifyText::Center),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 24.,
+                    ..Default::default()
+                },
+            ));
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(32.),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Code"),
+                        TextColor(Color::WHITE),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 40.,
+                            ..Default::default()
+                        },
+                    ));
+                    parent
+                        .spawn((
+                            Node {
+                                width: Val::Px(480.),
+                                height: Val::Px(72.),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                border: UiRect {
+                                    left: Val::Px(2.),
+                                    right: Val::Px(2.),
+                                    top: Val::Px(2.),
+                                    bottom: Val::Px(2.),
+                                },
+                                ..Default::default()
+                            },
+                            BorderColor(Color::WHITE),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                MatchIdTextInput,
+                                Text::new(match_id_input.0.clone()),
+                                TextColor(Color::WHITE),
+                                TextLayout::new_with_justify(JustifyText::Center),
+                                TextFont {
+                                    font: font.clone(),
+                                    font_size: 32.,
+                                    ..Default::default()
+                                },
+                            ));
+                        });
+                });
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(16.),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for label in TOURNAMENT_BUTTONS {
+                        spawn_tournament_button(parent, label, font.clone());
+                    }
+                });
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                BracketContent,
+                Node {
+                    width: Val::Percent(90.),
+                    height: Val::Px(360.),
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Row,
+                    justify_content: JustifyContent::SpaceEvenly,
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
+/// Lays the bracket out as one column per round, the final on the right.
+pub fn spawn_bracket(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    bracket: Res<Bracket>,
+    content_query: Query<(Entity, Ref<BracketContent>)>,
+) {
+    let font = asset_server.load("fonts/pixeloid_mono.ttf");
+
+    for (entity, content) in content_query.iter() {
+        if !bracket.is_changed() && !content.is_added() {
+            continue;
+        }
+
+        commands
+            .entity(entity)
+            .despawn_related::<Children>()
+            .with_children(|parent| {
+                if !bracket.is_started() {
+                    let entrants = if bracket.entrants.is_empty() {
+                        "No players registered yet".to_string()
+                    } else {
+                        format!("Registered:\n{}", bracket.entrants.join("\n"))
+                    };
+
+                    parent.spawn((
+                        Text::new(entrants),
+                        TextColor(Color::WHITE),
+                        TextLayout::new_with_justify(JustifyText::Center),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 20.,
+                            ..Default::default()
+                        },
+                    ));
+                    return;
+                }
+
+                for (round, matches) in bracket.rounds.iter().enumerate() {
+                    parent
+                        .spawn(Node {
+                            display: Display::Flex,
+                            flex_direction: FlexDirection::Column,
+                            justify_content: JustifyContent::SpaceAround,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new(bracket.round_label(round)),
+                                TextColor(Color::WHITE),
+                                TextFont {
+                                    font: font.clone(),
+                                    font_size: 20.,
+                                    ..Default::default()
+                                },
+                            ));
+
+                            for bracket_match in matches {
+                                parent.spawn((
+                                    Text::new(bracket_match.label()),
+                                    TextColor(Color::WHITE),
+                                    TextLayout::new_with_justify(JustifyText::Left),
+                                    TextFont {
+                                        font: font.clone(),
+                                        font_size: 16.,
+                                        ..Default::default()
+                                    },
+                                    Node {
+                                        padding: UiRect::all(Val::Px(6.)),
+                                        border: UiRect::all(Val::Px(1.)),
+                                        ..Default::default()
+                                    },
+                                    BorderColor(Color::WHITE),
+                                ));
+                            }
+                        });
+                }
+            });
+    }
+}
+
+pub fn update_tournament_status_text(
+    tournament_status: Res<TournamentStatus>,
+    bracket: Res<Bracket>,
+    game_start_timer: Res<GameStartTimer>,
+    mut text_query: Query<&mut Text, With<TournamentStatusText>>,
+) {
+    let status = if game_start_timer.active {
+        format!(
+            "Your duel starts in: {}",
+            game_start_timer.timer.remaining_secs().ceil()
+        )
+    } else if let Some(champion) = &bracket.champion {
+        format!("Champion: {}", champion)
+    } else {
+        tournament_status.0.clone()
+    };
+
+    for mut text in text_query.iter_mut() {
+        if text.0 != status {
+            *text = Text::new(status.clone());
+        }
+    }
+}
+
+/// Claims the player's spot back once the screen's connection is up.
+pub fn rejoin_tournament(
+    mut client: ResMut<QuinnetClient>,
+    mut tournament_entry: ResMut<TournamentEntry>,
+) {
+    if !tournament_entry.needs_rejoin {
+        return;
+    }
+
+    tournament_entry.needs_rejoin = false;
+
+    if let (Some(code), Some(entry_token)) =
+        (tournament_entry.code.clone(), tournament_entry.entry_token)
+    {
+        let _ = client.connection_mut().send_message_on(
+            ClientChannel::Lobby,
+            ClientMessage::RejoinTournament { code, entry_token },
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_tournament_messages(
+    mut server_message: EventReader<ServerMessageReceived>,
+    mut bracket: ResMut<Bracket>,
+    mut tournament_entry: ResMut<TournamentEntry>,
+    mut tournament_status: ResMut<TournamentStatus>,
+    mut player_hearts_status: ResMut<PlayerHertsStatus>,
+    mut who_is_winner: ResMut<WhoIsWinner>,
+    mut shooting_lock: ResMut<ShootingLock>,
+    mut match_commitment: ResMut<MatchCommitment>,
+) {
+    for ServerMessageReceived { channel, message } in server_message.read() {
+        if *channel != 0 {
+            continue;
+        }
+
+        match message {
+            // The next duel comes unasked, often while the last one's Game
+            // Over screen is still up, so what is left of that one is cleared.
+            ServerMessage::JoinedMatch { .. } if tournament_entry.entry_token.is_some() => {
+                player_hearts_status.reset();
+                who_is_winner.reset();
+                shooting_lock.reset();
+                match_commitment.reset();
+            }
+            ServerMessage::TournamentUpdated { bracket: updated }
+                if tournament_entry.code.as_ref() == Some(&updated.code) =>
+            {
+                *bracket = updated.clone();
+            }
+            ServerMessage::TournamentRegistered { code, entry_token } => {
+                tournament_entry.code = Some(code.clone());
+                tournament_entry.entry_token = Some(*entry_token);
+                tournament_status.0 = "Registered, waiting for the organizer to start".to_string();
+            }
+            ServerMessage::TournamentRejected { error_message } => {
+                tournament_status.0 = error_message.clone();
+            }
+            _ => {}
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn tournament_button_pressed_handler(
+    button_query: Query<(&Interaction, &Name), Changed<Interaction>>,
+    mut next_main_menu_state: ResMut<NextState<MainMenuState>>,
+    mut next_logged_in_state: ResMut<NextState<LoggedInState>>,
+    mut connection_state: ResMut<NextState<ConnectionState>>,
+    mut client: ResMut<QuinnetClient>,
+    mut pending_match_request: ResMut<PendingMatchRequest>,
+    mut match_id_input: ResMut<MatchIdInput>,
+    mut tournament_entry: ResMut<TournamentEntry>,
+    mut tournament_status: ResMut<TournamentStatus>,
+    mut bracket: ResMut<Bracket>,
+    match_settings: Res<MatchSettings>,
+) {
+    for (interaction, name) in button_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        if name.as_str() == "Create" && match_id_input.0.is_empty() {
+            match_id_input.0 = new_code();
+        }
+
+        let code = match_id_input.0.clone();
+
+        if matches!(name.as_str(), "Create" | "Register" | "Watch" | "Start") {
+            if !tournament::is_valid_code(&code) {
+                tournament_status.0 = format!("Codes are up to {} letters or digits", MAX_CODE_LEN);
+                continue;
+            }
+
+            // Following another tournament drops the old bracket and entry.
+            if tournament_entry.code.as_ref() != Some(&code) {
+                *tournament_entry = TournamentEntry {
+                    code: Some(code.clone()),
+                    ..Default::default()
+                };
+                *bracket = Bracket::default();
+            }
+        }
+
+        match name.as_str() {
+            "Create" => {
+                tournament_status.0 = format!("Tournament {} created, share the code", code);
+
+                let _ = client.connection_mut().send_message_on(
+                    ClientChannel::Lobby,
+                    ClientMessage::CreateTournament {
+                        code,
+                        settings: *match_settings,
+                    },
+                );
+            }
+            "Register" => {
+                tournament_status.0 = "Registering...".to_string();
+
+                wallet::request_match(
+                    &mut client,
+                    &mut pending_match_request,
+                    MatchRequest::RegisterTournament(code),
+                );
+            }
+            "Watch" => {
+                let _ = client.connection_mut().send_message_on(
+                    ClientChannel::Lobby,
+                    ClientMessage::WatchTournament { code },
+                );
+            }
+            "Start" => {
+                let _ = client.connection_mut().send_message_on(
+                    ClientChannel::Lobby,
+                    ClientMessage::StartTournament { code },
+                );
+            }
+            "Export" => {
+                if bracket.code.is_empty() {
+                    tournament_status.0 = "No bracket to export yet".to_string();
+                    continue;
+                }
+
+                tournament_status.0 = match bracket.save(Path::new(TOURNAMENT_DIR)) {
+                    Ok(path) => format!("Bracket exported to {}", path.display()),
+                    Err(e) => {
+                        error!("❌ Could not export bracket: {:?}", e);
+                        "Could not export the bracket".to_string()
+                    }
+                };
+            }
+            "Back" => {
+                connection_state.set(ConnectionState::Idle);
+
+                next_main_menu_state.set(MainMenuState::MainMenu);
+                next_logged_in_state.set(LoggedInState::LoggedIn);
+            }
+            _ => return,
+        }
+    }
+}
+
+pub fn tournament_ui_interaction(
+    mut button_query: Query<(&Interaction, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    for (interaction, mut color) in button_query.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *color = BackgroundColor(Color::srgba(0.8, 0.8, 0.8, 0.15));
+            }
+            Interaction::Hovered => {
+                *color = BackgroundColor(Color::srgba(0.8, 0.8, 0.8, 0.07));
+            }
+            Interaction::None => {
+                *color = BackgroundColor(Color::NONE);
+            }
+        }
+    }
+}
+
+pub fn despawn_tournament_ui(mut commands: Commands, query: Query<Entity, With<TournamentUI>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -0,0 +1,327 @@
+use axum::{Extension, Json, http::StatusCode, response::IntoResponse};
+use bevy::prelude::*;
+use bevy_quinnet::client::QuinnetClient;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    ClientChannel, ClientMessage,
+    bridge::{BridgeMessage, BridgeSender, WalletSigned},
+    config::GameConfig,
+    shooting::MatchSettings,
+    ui::profile::ProfileData,
+};
+
+/// The exact bytes a wallet signs to prove it owns the key behind `player_wallet`.
+pub fn challenge_message(nonce: &str) -> String {
+    format!("Proof of Duel wallet check: {nonce}")
+}
+
+/// Checks a base58 ed25519 `signature` of the challenge for `nonce` against the
+/// base58 Solana `wallet` address.
+pub fn verify_wallet_signature(wallet: &str, nonce: &str, signature: &str) -> bool {
+    let Some(public_key) = decode_base58::<32>(wallet) else {
+        return false;
+    };
+    let Some(signature) = decode_base58::<64>(signature) else {
+        return false;
+    };
+    let Ok(verifying_key) = VerifyingKey::from_bytes(&public_key) else {
+        return false;
+    };
+
+    verifying_key
+        .verify(
+            challenge_message(nonce).as_bytes(),
+            &Signature::from_bytes(&signature),
+        )
+        .is_ok()
+}
+
+/// Shortens a wallet address to its first and last four characters for display.
+pub fn short_wallet(wallet: &str) -> String {
+    if wallet.len() <= 8 || !wallet.is_ascii() {
+        return wallet.to_string();
+    }
+
+    format!("{}...{}", &wallet[..4], &wallet[wallet.len() - 4..])
+}
+
+fn decode_base58<const N: usize>(value: &str) -> Option<[u8; N]> {
+    bs58::decode(value).into_vec().ok()?.try_into().ok()
+}
+
+/// A local Solana CLI keypair used instead of the Civic wallet while developing.
+/// Loaded from the JSON file at `DEV_KEYPAIR`.
+#[derive(Resource, Default)]
+pub struct DevKeypair(pub Option<SigningKey>);
+
+impl DevKeypair {
+    /// Fails when `DEV_KEYPAIR` points at something that isn't a keypair file.
+    /// This runs before logging is set up, so the caller reports the error.
+    pub fn from_env() -> Result<Self, String> {
+        dotenvy::dotenv().ok();
+
+        let Ok(path) = dotenvy::var("DEV_KEYPAIR") else {
+            return Ok(Self(None));
+        };
+
+        std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<Vec<u8>>(&json).ok())
+            .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+            .and_then(|bytes| SigningKey::from_keypair_bytes(&bytes).ok())
+            .map(|keypair| Self(Some(keypair)))
+            .ok_or_else(|| format!("DEV_KEYPAIR at {} is not a valid keypair file", path))
+    }
+
+    pub fn wallet(&self) -> Option<String> {
+        self.0
+            .as_ref()
+            .map(|key| bs58::encode(key.verifying_key().as_bytes()).into_string())
+    }
+
+    pub fn sign(&self, nonce: &str) -> Option<String> {
+        self.0.as_ref().map(|key| {
+            bs58::encode(key.sign(challenge_message(nonce).as_bytes()).to_bytes()).into_string()
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WalletSignaturePayload {
+    public_key: String,
+    nonce: String,
+    signature: String,
+}
+
+pub async fn wallet_signature(
+    Extension(bridge): Extension<BridgeSender>,
+    Json(payload): Json<WalletSignaturePayload>,
+) -> impl IntoResponse {
+    bridge.send(BridgeMessage::WalletSigned {
+        public_key: payload.public_key,
+        nonce: payload.nonce,
+        signature: payload.signature,
+    });
+
+    (StatusCode::OK, "OK").into_response()
+}
+
+#[derive(Debug, Clone)]
+pub enum MatchRequest {
+    Create(Uuid),
+    Join(Uuid),
+    /// Signing up for the tournament with this code.
+    RegisterTournament(String),
+}
+
+/// The create/join/register request waiting on a signed wallet challenge before it is sent.
+#[derive(Resource, Default)]
+pub struct PendingMatchRequest(pub Option<MatchRequest>);
+
+#[derive(Resource, Default)]
+pub struct WalletProof {
+    pub nonce: Option<String>,
+    pub wallet: String,
+    pub signature: Option<String>,
+    pub browser_opened: bool,
+}
+
+impl WalletProof {
+    pub fn begin(&mut self, nonce: String) {
+        self.nonce = Some(nonce);
+        self.wallet.clear();
+        self.signature = None;
+        self.browser_opened = false;
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.signature.is_some()
+    }
+
+    pub fn reset(&mut self) {
+        self.nonce = None;
+        self.wallet.clear();
+        self.signature = None;
+        self.browser_opened = false;
+    }
+}
+
+pub fn request_match(
+    client: &mut QuinnetClient,
+    pending_match_request: &mut PendingMatchRequest,
+    request: MatchRequest,
+) {
+    pending_match_request.0 = Some(request);
+
+    let _ = client
+        .connection_mut()
+        .send_message_on(ClientChannel::Lobby, ClientMessage::WalletChallengeRequest);
+}
+
+pub fn sign_wallet_challenge(
+    mut wallet_proof: ResMut<WalletProof>,
+    dev_keypair: Res<DevKeypair>,
+    profile_data: Res<ProfileData>,
+    mut wallet_signed: EventReader<WalletSigned>,
+    config: Res<GameConfig>,
+) {
+    if wallet_proof.is_ready() {
+        return;
+    }
+
+    let Some(nonce) = wallet_proof.nonce.clone() else {
+        return;
+    };
+
+    if profile_data.guest {
+        wallet_proof.signature = Some(String::new());
+        return;
+    }
+
+    if let (Some(wallet), Some(signature)) = (dev_keypair.wallet(), dev_keypair.sign(&nonce)) {
+        wallet_proof.wallet = wallet;
+        wallet_proof.signature = Some(signature);
+        return;
+    }
+
+    // Without a wallet the player joins anonymously and the match isn't recorded on-chain.
+    if profile_data.public_key.is_empty() {
+        wallet_proof.signature = Some(String::new());
+        return;
+    }
+
+    if !wallet_proof.browser_opened {
+        wallet_proof.browser_opened = true;
+
+        let url = format!("{}?nonce={}", config.api_url("/sign"), nonce);
+        match webbrowser::open(&url) {
+            Ok(_) => info!("Opening browser to sign the wallet challenge..."),
+            Err(e) => error!(
+                "❌ Could not open the browser to sign the wallet challenge: {:?}",
+                e
+            ),
+        }
+    }
+
+    for event in wallet_signed.read() {
+        if event.nonce == nonce && event.public_key == profile_data.public_key {
+            wallet_proof.wallet = event.public_key.clone();
+            wallet_proof.signature = Some(event.signature.clone());
+        }
+    }
+}
+
+pub fn send_pending_match_request(
+    mut client: ResMut<QuinnetClient>,
+    mut pending_match_request: ResMut<PendingMatchRequest>,
+    mut wallet_proof: ResMut<WalletProof>,
+    profile_data: Res<ProfileData>,
+    match_settings: Res<MatchSettings>,
+) {
+    if !wallet_proof.is_ready() {
+        return;
+    }
+
+    let Some(request) = pending_match_request.0.take() else {
+        return;
+    };
+
+    let player_wallet = wallet_proof.wallet.clone();
+    let wallet_signature = wallet_proof.signature.clone().unwrap_or_default();
+    let username = profile_data.username.clone();
+
+    let message = match request {
+        MatchRequest::Create(match_id) => ClientMessage::CreateMatchRequest {
+            match_id,
+            player_wallet,
+            wallet_signature,
+            username,
+            settings: *match_settings,
+        },
+        MatchRequest::Join(match_id) => ClientMessage::JoinMatchRequest {
+            match_id,
+            player_wallet,
+            wallet_signature,
+            username,
+        },
+        MatchRequest::RegisterTournament(code) => ClientMessage::RegisterTournament {
+            code,
+            player_wallet,
+            wallet_signature,
+        },
+    };
+
+    let _ = client
+        .connection_mut()
+        .send_message_on(ClientChannel::Lobby, message);
+
+    wallet_proof.reset();
+}
+
+pub fn reset_wallet_proof(
+    mut pending_match_request: ResMut<PendingMatchRequest>,
+    mut wallet_proof: ResMut<WalletProof>,
+) {
+    pending_match_request.0 = None;
+    wallet_proof.reset();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dev_keypair(seed: u8) -> DevKeypair {
+        DevKeypair(Some(SigningKey::from_bytes(&[seed; 32])))
+    }
+
+    #[test]
+    fn accepts_a_signature_of_the_challenge() {
+        let keypair = dev_keypair(7);
+        let wallet = keypair.wallet().unwrap();
+        let signature = keypair.sign("nonce-1").unwrap();
+
+        assert!(verify_wallet_signature(&wallet, "nonce-1", &signature));
+    }
+
+    #[test]
+    fn rejects_a_signature_of_another_nonce() {
+        let keypair = dev_keypair(7);
+        let wallet = keypair.wallet().unwrap();
+        let signature = keypair.sign("nonce-1").unwrap();
+
+        assert!(!verify_wallet_signature(&wallet, "nonce-2", &signature));
+    }
+
+    #[test]
+    fn rejects_a_signature_from_another_wallet() {
+        let wallet = dev_keypair(7).wallet().unwrap();
+        let signature = dev_keypair(8).sign("nonce-1").unwrap();
+
+        assert!(!verify_wallet_signature(&wallet, "nonce-1", &signature));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let keypair = dev_keypair(7);
+        let wallet = keypair.wallet().unwrap();
+        let signature = keypair.sign("nonce-1").unwrap();
+
+        assert!(!verify_wallet_signature("", "nonce-1", &signature));
+        assert!(!verify_wallet_signature(
+            "not base58 0OIl",
+            "nonce-1",
+            &signature
+        ));
+        assert!(!verify_wallet_signature(&wallet, "nonce-1", ""));
+        assert!(!verify_wallet_signature(&wallet, "nonce-1", &wallet));
+    }
+
+    #[test]
+    fn shortens_long_wallets_only() {
+        assert_eq!(short_wallet("ABCDEFGHIJKL"), "ABCD...IJKL");
+        assert_eq!(short_wallet("ABCDEFGH"), "ABCDEFGH");
+    }
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum DuelError {
+    #[msg("Dispute window must be between 1 second and 24 hours")]
+    InvalidDisputeWindow,
+    #[msg("Winner and loser must be different wallets")]
+    SameWinnerAndLoser,
+    #[msg("Only a participant of the match can dispute its result")]
+    NotAParticipant,
+    #[msg("The dispute window for this match has closed")]
+    DisputeWindowClosed,
+    #[msg("The dispute window for this match is still open")]
+    DisputeWindowOpen,
+    #[msg("This match result has been disputed")]
+    MatchDisputed,
+    #[msg("This match result has already been finalized")]
+    AlreadyFinalized,
+    #[msg("Only the server authority can do this")]
+    Unauthorized,
+    #[msg("This match result has not been disputed")]
+    NotDisputed,
+}
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::DuelError;
+use crate::states::{Config, MatchResult};
+
+#[derive(Accounts)]
+#[instruction(match_id: [u8; 16])]
+pub struct CommitMatch<'info> {
+    #[account(mut, address = config.authority @ DuelError::Unauthorized)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"match", match_id.as_ref()], // One result per match id, so a retried commit can't double-count
+        bump,
+        space = 8 + MatchResult::INIT_SPACE,
+    )]
+    pub match_result: Account<'info, MatchResult>,
+    pub system_program: Program<'info, System>,
+}
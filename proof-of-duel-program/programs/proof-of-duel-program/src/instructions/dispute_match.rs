@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+use crate::states::MatchResult;
+
+#[derive(Accounts)]
+pub struct DisputeMatch<'info> {
+    pub disputer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"match", match_result.match_id.as_ref()],
+        bump
+    )]
+    pub match_result: Account<'info, MatchResult>,
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::DuelError;
+use crate::states::{MatchResult, Player};
+
+#[derive(Accounts)]
+pub struct FinalizeMatch<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"match", match_result.match_id.as_ref()],
+        bump,
+        has_one = authority @ DuelError::Unauthorized,
+    )]
+    pub match_result: Account<'info, MatchResult>,
+
+    #[account(
+        mut,
+        seeds = [b"player", match_result.winner.as_ref()],
+        bump
+    )]
+    pub winner_player: Account<'info, Player>,
+
+    #[account(
+        mut,
+        seeds = [b"player", match_result.loser.as_ref()],
+        bump
+    )]
+    pub loser_player: Account<'info, Player>,
+}
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::DuelError;
+use crate::program::ProofOfDuelProgram;
+use crate::states::Config;
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub upgrade_authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = upgrade_authority,
+        seeds = [b"config"],
+        bump,
+        space = 8 + Config::INIT_SPACE,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, ProofOfDuelProgram>,
+
+    // Only whoever deployed the program gets to pick the server key
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(upgrade_authority.key()) @ DuelError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
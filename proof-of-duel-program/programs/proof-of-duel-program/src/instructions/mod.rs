@@ -1,7 +1,13 @@
+pub mod commit_match;
+pub mod dispute_match;
+pub mod finalize_match;
+pub mod initialize_config;
 pub mod initialize_player;
-pub mod loss_increment;
-pub mod win_increment;
+pub mod resolve_match;
 
+pub use commit_match::*;
+pub use dispute_match::*;
+pub use finalize_match::*;
+pub use initialize_config::*;
 pub use initialize_player::*;
-pub use loss_increment::*;
-pub use win_increment::*;
+pub use resolve_match::*;
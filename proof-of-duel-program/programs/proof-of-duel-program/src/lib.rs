@@ -1,9 +1,14 @@
+#![allow(unexpected_cfgs, deprecated)]
+
 use anchor_lang::prelude::*;
 
+pub mod errors;
 pub mod instructions;
 pub mod states;
 
+use errors::DuelError;
 use instructions::*;
+use states::MAX_DISPUTE_WINDOW_SECONDS;
 
 declare_id!("GsetEEa4YtiaFcQP4NnqM2vBtJrtbFjKBgfdszMK8ePC");
 
@@ -17,15 +22,95 @@ pub mod proof_of_duel_program {
         Ok(())
     }
 
-    pub fn win_increment(ctx: Context<WinIncrement>) -> Result<()> {
-        let player = &mut ctx.accounts.player;
-        player.win_increment();
+    pub fn initialize_config(ctx: Context<InitializeConfig>, authority: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.initialize(authority);
         Ok(())
     }
 
-    pub fn loss_increment(ctx: Context<LossIncrement>) -> Result<()> {
-        let player = &mut ctx.accounts.player;
-        player.loss_increment();
+    pub fn commit_match(
+        ctx: Context<CommitMatch>,
+        match_id: [u8; 16],
+        transcript_hash: [u8; 32],
+        winner: Pubkey,
+        loser: Pubkey,
+        dispute_window: i64,
+    ) -> Result<()> {
+        require!(
+            dispute_window > 0 && dispute_window <= MAX_DISPUTE_WINDOW_SECONDS,
+            DuelError::InvalidDisputeWindow
+        );
+        require_keys_neq!(winner, loser, DuelError::SameWinnerAndLoser);
+
+        let now = Clock::get()?.unix_timestamp;
+        let authority = ctx.accounts.authority.key();
+
+        let match_result = &mut ctx.accounts.match_result;
+        match_result.commit(
+            match_id,
+            authority,
+            winner,
+            loser,
+            transcript_hash,
+            now,
+            dispute_window,
+        );
+        Ok(())
+    }
+
+    pub fn dispute_match(ctx: Context<DisputeMatch>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let disputer = ctx.accounts.disputer.key();
+
+        let match_result = &mut ctx.accounts.match_result;
+        require!(!match_result.finalized, DuelError::AlreadyFinalized);
+        require!(!match_result.is_disputed(), DuelError::MatchDisputed);
+        require!(
+            match_result.is_participant(&disputer),
+            DuelError::NotAParticipant
+        );
+        require!(
+            now < match_result.dispute_deadline(),
+            DuelError::DisputeWindowClosed
+        );
+
+        match_result.dispute(disputer);
+        Ok(())
+    }
+
+    pub fn finalize_match(ctx: Context<FinalizeMatch>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+
+        let match_result = &mut ctx.accounts.match_result;
+        require!(!match_result.finalized, DuelError::AlreadyFinalized);
+        require!(!match_result.is_disputed(), DuelError::MatchDisputed);
+        require!(
+            now >= match_result.dispute_deadline(),
+            DuelError::DisputeWindowOpen
+        );
+
+        match_result.finalize();
+        ctx.accounts.winner_player.win_increment();
+        ctx.accounts.loser_player.loss_increment();
+        Ok(())
+    }
+
+    /// Settles a disputed match. The transcript lives off-chain, so the
+    /// program can't replay it. Resolution is trusted to the authority, which
+    /// replays the transcript, checks it against the committed hash and says
+    /// whether the result stands. A result that doesn't stand is voided.
+    pub fn resolve_match(ctx: Context<ResolveMatch>, result_stands: bool) -> Result<()> {
+        let match_result = &mut ctx.accounts.match_result;
+        require!(!match_result.finalized, DuelError::AlreadyFinalized);
+        require!(match_result.is_disputed(), DuelError::NotDisputed);
+
+        if result_stands {
+            match_result.finalize();
+            ctx.accounts.winner_player.win_increment();
+            ctx.accounts.loser_player.loss_increment();
+        } else {
+            match_result.void();
+        }
         Ok(())
     }
 }
@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    /// The game server key, the only one allowed to commit, finalize and
+    /// resolve match results.
+    pub authority: Pubkey,
+}
+
+impl Config {
+    pub fn initialize(&mut self, authority: Pubkey) {
+        self.authority = authority;
+    }
+}
@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+
+pub const MAX_DISPUTE_WINDOW_SECONDS: i64 = 60 * 60 * 24;
+
+#[account]
+#[derive(InitSpace)]
+pub struct MatchResult {
+    pub match_id: [u8; 16],
+    pub authority: Pubkey,
+    pub winner: Pubkey,
+    pub loser: Pubkey,
+    pub transcript_hash: [u8; 32],
+    pub committed_at: i64,
+    pub dispute_window: i64,
+    pub disputed_by: Option<Pubkey>,
+    pub finalized: bool,
+    /// Set when a dispute was resolved against the result, it never counts.
+    pub voided: bool,
+}
+
+impl MatchResult {
+    #[allow(clippy::too_many_arguments)]
+    pub fn commit(
+        &mut self,
+        match_id: [u8; 16],
+        authority: Pubkey,
+        winner: Pubkey,
+        loser: Pubkey,
+        transcript_hash: [u8; 32],
+        committed_at: i64,
+        dispute_window: i64,
+    ) {
+        self.match_id = match_id;
+        self.authority = authority;
+        self.winner = winner;
+        self.loser = loser;
+        self.transcript_hash = transcript_hash;
+        self.committed_at = committed_at;
+        self.dispute_window = dispute_window;
+        self.disputed_by = None;
+        self.finalized = false;
+        self.voided = false;
+    }
+
+    pub fn dispute_deadline(&self) -> i64 {
+        self.committed_at.saturating_add(self.dispute_window)
+    }
+
+    pub fn is_participant(&self, key: &Pubkey) -> bool {
+        self.winner == *key || self.loser == *key
+    }
+
+    pub fn is_disputed(&self) -> bool {
+        self.disputed_by.is_some()
+    }
+
+    pub fn dispute(&mut self, disputer: Pubkey) {
+        self.disputed_by = Some(disputer);
+    }
+
+    pub fn finalize(&mut self) {
+        self.finalized = true;
+    }
+
+    pub fn void(&mut self) {
+        self.finalized = true;
+        self.voided = true;
+    }
+}
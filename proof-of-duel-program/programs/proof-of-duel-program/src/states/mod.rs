@@ -1,3 +1,7 @@
+pub mod config;
+pub mod match_result;
 pub mod player;
 
+pub use config::*;
+pub use match_result::*;
 pub use player::*;
@@ -10,26 +10,82 @@ describe("proof-of-duel-program", () => {
   const program = anchor.workspace.proofOfDuelProgram as Program<ProofOfDuelProgram>;
 
   const player = anchor.web3.Keypair.generate();
+  const opponent = anchor.web3.Keypair.generate();
 
   const [playerPda] = anchor.web3.PublicKey.findProgramAddressSync(
     [Buffer.from("player"), player.publicKey.toBuffer()],
     program.programId,
   );
 
-  it("initialize player test", async () => {
-    await airdrop(player.publicKey);
+  const [opponentPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("player"), opponent.publicKey.toBuffer()],
+    program.programId,
+  );
+
+  const [configPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId,
+  );
+
+  const [programDataPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    anchor.web3.BPF_LOADER_UPGRADEABLE_PROGRAM_ID,
+  );
 
+  const transcriptHash = Array.from({ length: 32 }, (_, i) => i);
+
+  const matchPda = (matchId: number[]) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("match"), Buffer.from(matchId)],
+      program.programId,
+    )[0];
+
+  const newMatchId = () => Array.from(anchor.web3.Keypair.generate().publicKey.toBytes().slice(0, 16));
+
+  const commit = async (matchId: number[], disputeWindow: number) => {
     await program.methods
-      .initializePlayer()
+      .commitMatch(matchId, transcriptHash, player.publicKey, opponent.publicKey, new anchor.BN(disputeWindow))
       .accountsPartial({
-        signer: player.publicKey,
-        player: playerPda,
+        authority: provider.wallet.publicKey,
+        config: configPda,
+        matchResult: matchPda(matchId),
         systemProgram: anchor.web3.SystemProgram.programId,
       })
-      .signers([player])
       .rpc();
+  };
 
-    console.log("Player initialized");
+  const finalize = async (matchId: number[]) => {
+    await program.methods
+      .finalizeMatch()
+      .accountsPartial({
+        authority: provider.wallet.publicKey,
+        matchResult: matchPda(matchId),
+        winnerPlayer: playerPda,
+        loserPlayer: opponentPda,
+      })
+      .rpc();
+  };
+
+  it("initialize player test", async () => {
+    await airdrop(player.publicKey);
+    await airdrop(opponent.publicKey);
+
+    for (const [signer, pda] of [
+      [player, playerPda],
+      [opponent, opponentPda],
+    ] as const) {
+      await program.methods
+        .initializePlayer()
+        .accountsPartial({
+          signer: signer.publicKey,
+          player: pda,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([signer])
+        .rpc();
+    }
+
+    console.log("Players initialized");
 
     const playerAccount = await program.account.player.fetch(playerPda);
 
@@ -37,34 +93,196 @@ describe("proof-of-duel-program", () => {
     assert.equal(playerAccount.loss.toNumber(), 0);
   });
 
-  it("increment win test", async () => {
+  it("initialize config test", async () => {
     await program.methods
-      .winIncrement()
+      .initializeConfig(provider.wallet.publicKey)
       .accountsPartial({
-        player: playerPda,
-        wallet: player.publicKey,
+        upgradeAuthority: provider.wallet.publicKey,
+        config: configPda,
+        program: program.programId,
+        programData: programDataPda,
+        systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
 
+    const config = await program.account.config.fetch(configPda);
+    assert.isTrue(config.authority.equals(provider.wallet.publicKey));
+  });
+
+  it("only the server authority can commit test", async () => {
+    const matchId = newMatchId();
+    const stranger = anchor.web3.Keypair.generate();
+
+    try {
+      await program.methods
+        .commitMatch(matchId, transcriptHash, stranger.publicKey, opponent.publicKey, new anchor.BN(60))
+        .accountsPartial({
+          authority: stranger.publicKey,
+          config: configPda,
+          matchResult: matchPda(matchId),
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([stranger])
+        .rpc();
+      assert.fail("commit should fail for anyone but the server authority");
+    } catch (error) {
+      assert.include(String(error), "Unauthorized");
+    }
+  });
+
+  it("commit match test", async () => {
+    const matchId = newMatchId();
+    await commit(matchId, 60);
+
+    const matchResult = await program.account.matchResult.fetch(matchPda(matchId));
+
+    assert.deepEqual(matchResult.transcriptHash, transcriptHash);
+    assert.isTrue(matchResult.winner.equals(player.publicKey));
+    assert.isTrue(matchResult.loser.equals(opponent.publicKey));
+    assert.isNull(matchResult.disputedBy);
+    assert.isFalse(matchResult.finalized);
+  });
+
+  it("finalize during dispute window fails test", async () => {
+    const matchId = newMatchId();
+    await commit(matchId, 60);
+
+    try {
+      await finalize(matchId);
+      assert.fail("finalize should fail while the dispute window is open");
+    } catch (error) {
+      assert.include(String(error), "DisputeWindowOpen");
+    }
+  });
+
+  it("finalize after dispute window test", async () => {
+    const matchId = newMatchId();
+    await commit(matchId, 1);
+    await sleep(2000);
+    await finalize(matchId);
+
     const playerAccount = await program.account.player.fetch(playerPda);
+    const opponentAccount = await program.account.player.fetch(opponentPda);
 
     assert.equal(playerAccount.win.toNumber(), 1);
+    assert.equal(opponentAccount.loss.toNumber(), 1);
   });
 
-  it("increment loss test", async () => {
+  it("disputed match cannot be finalized test", async () => {
+    const matchId = newMatchId();
+    await commit(matchId, 2);
+
     await program.methods
-      .lossIncrement()
+      .disputeMatch()
       .accountsPartial({
-        player: playerPda,
-        wallet: player.publicKey,
+        disputer: opponent.publicKey,
+        matchResult: matchPda(matchId),
       })
+      .signers([opponent])
       .rpc();
 
-    const playerAccount = await program.account.player.fetch(playerPda);
+    const matchResult = await program.account.matchResult.fetch(matchPda(matchId));
+    assert.isTrue(matchResult.disputedBy.equals(opponent.publicKey));
+
+    await sleep(3000);
+
+    try {
+      await finalize(matchId);
+      assert.fail("finalize should fail for a disputed match");
+    } catch (error) {
+      assert.include(String(error), "MatchDisputed");
+    }
+  });
+
+  const dispute = async (matchId: number[]) => {
+    await program.methods
+      .disputeMatch()
+      .accountsPartial({
+        disputer: opponent.publicKey,
+        matchResult: matchPda(matchId),
+      })
+      .signers([opponent])
+      .rpc();
+  };
+
+  const resolve = async (matchId: number[], resultStands: boolean) => {
+    await program.methods
+      .resolveMatch(resultStands)
+      .accountsPartial({
+        authority: provider.wallet.publicKey,
+        matchResult: matchPda(matchId),
+        winnerPlayer: playerPda,
+        loserPlayer: opponentPda,
+      })
+      .rpc();
+  };
+
+  it("resolved dispute whose result stands counts test", async () => {
+    const matchId = newMatchId();
+    await commit(matchId, 60);
+    await dispute(matchId);
+
+    const before = await program.account.player.fetch(playerPda);
+    await resolve(matchId, true);
+
+    const matchResult = await program.account.matchResult.fetch(matchPda(matchId));
+    const after = await program.account.player.fetch(playerPda);
 
-    assert.equal(playerAccount.loss.toNumber(), 1);
+    assert.isTrue(matchResult.finalized);
+    assert.isFalse(matchResult.voided);
+    assert.equal(after.win.toNumber(), before.win.toNumber() + 1);
   });
 
+  it("resolved dispute whose result does not stand is voided test", async () => {
+    const matchId = newMatchId();
+    await commit(matchId, 60);
+    await dispute(matchId);
+
+    const before = await program.account.player.fetch(playerPda);
+    await resolve(matchId, false);
+
+    const matchResult = await program.account.matchResult.fetch(matchPda(matchId));
+    const after = await program.account.player.fetch(playerPda);
+
+    assert.isTrue(matchResult.finalized);
+    assert.isTrue(matchResult.voided);
+    assert.equal(after.win.toNumber(), before.win.toNumber());
+  });
+
+  it("undisputed match cannot be resolved test", async () => {
+    const matchId = newMatchId();
+    await commit(matchId, 60);
+
+    try {
+      await resolve(matchId, true);
+      assert.fail("resolve should fail for an undisputed match");
+    } catch (error) {
+      assert.include(String(error), "NotDisputed");
+    }
+  });
+
+  it("only participants can dispute test", async () => {
+    const matchId = newMatchId();
+    const stranger = anchor.web3.Keypair.generate();
+    await commit(matchId, 60);
+
+    try {
+      await program.methods
+        .disputeMatch()
+        .accountsPartial({
+          disputer: stranger.publicKey,
+          matchResult: matchPda(matchId),
+        })
+        .signers([stranger])
+        .rpc();
+      assert.fail("dispute should fail for a non-participant");
+    } catch (error) {
+      assert.include(String(error), "NotAParticipant");
+    }
+  });
+
+  const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
   const airdrop = async (pubkey: anchor.web3.PublicKey) => {
     const sig = await anchor.getProvider().connection.requestAirdrop(pubkey, 0.01 * anchor.web3.LAMPORTS_PER_SOL);
     const blockhash = await anchor.getProvider().connection.getLatestBlockhash();